@@ -0,0 +1,519 @@
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use clap::Parser;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+
+use crate::action::Heading;
+use crate::analysis::load::load_champion;
+use crate::analysis::stats::{mann_whitney, mean_std};
+use crate::analysis::{format_value, print_table};
+use crate::brain::{Brain, BrainSnapshot};
+use crate::config::Config;
+use crate::creature::Creature;
+use crate::fitness::fitness_of;
+use crate::genome::Activation;
+use crate::metrics::collector::MetricsCollector;
+use crate::neat::NodeKind;
+use crate::neural_net::ForwardScratch;
+use crate::sensors::SensorSuite;
+use crate::simulation::EpisodeRunner;
+use crate::world::World;
+
+/// Values per input kept from the intact episodes for shuffling.
+const SHUFFLE_POOL: usize = 4096;
+/// p-value below which a variant counts as changing fitness.
+const SIGNIFICANCE: f64 = 0.05;
+
+/// `evobrain ablate`: measures what a saved champion relies on by replaying
+/// it with single inputs zeroed or shuffled, hidden neurons knocked out and
+/// its weights perturbed, each over the same seeded episodes.
+#[derive(Parser, Debug, Clone)]
+#[command(name = "evobrain ablate")]
+pub struct AblateArgs {
+    /// Run directory containing `manifest.json` and `champion.json`.
+    pub run: PathBuf,
+    /// Brain to probe instead of `<run>/champion.json`; any JSON object with
+    /// a `brain` field, e.g. a saved champion.
+    #[arg(long)]
+    pub champion: Option<PathBuf>,
+    #[arg(long, default_value_t = 20)]
+    pub episodes: usize,
+    /// Seed of the first episode. Defaults to the run's seed.
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Copies of the brain sharing each episode. Defaults to the run's
+    /// population size.
+    #[arg(long)]
+    pub copies: Option<usize>,
+    /// Half-widths of the uniform weight noise, one robustness point each.
+    #[arg(long, value_delimiter = ',', default_value = "0.05,0.1,0.2,0.5,1.0")]
+    pub noise: Vec<f32>,
+    /// Perturbed brains evaluated per noise level.
+    #[arg(long, default_value_t = 5)]
+    pub noise_samples: usize,
+    /// Also write the sensitivity table as CSV.
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+}
+
+/// One line of the sensitivity table.
+#[derive(Debug, Clone, Serialize)]
+pub struct Sensitivity {
+    /// `intact`, `zero`, `shuffle`, `knockout` or `weight_noise`.
+    pub kind: &'static str,
+    /// Input name, hidden neuron (`h<layer>.<index>` or `node<id>`) or noise
+    /// level.
+    pub target: String,
+    pub mean: f64,
+    pub std: f64,
+    /// Mean fitness change from the intact brain.
+    pub delta: f64,
+    /// Two-sided Mann–Whitney p-value against the intact episodes.
+    pub p: Option<f64>,
+    /// Vargha–Delaney A12 of the variant over the intact brain.
+    pub a12: Option<f64>,
+}
+
+impl Sensitivity {
+    fn of(kind: &'static str, target: String, fitness: &[f64], intact: &[f64]) -> Self {
+        let (mean, std) = mean_std(fitness);
+        let test = (kind != "intact")
+            .then(|| mann_whitney(fitness, intact))
+            .flatten();
+        Self {
+            kind,
+            target,
+            mean,
+            std,
+            delta: mean - mean_std(intact).0,
+            p: test.map(|test| test.p),
+            a12: test.map(|test| test.a12),
+        }
+    }
+}
+
+/// How an [`InputProbe`] treats the brain's inputs.
+#[derive(Debug, Clone)]
+enum ProbeMode {
+    /// Passes inputs through and keeps a reservoir sample of each one.
+    Record(Arc<Mutex<InputPool>>),
+    Zero(usize),
+    /// Replaces the input with values the intact brain saw on it.
+    Shuffle {
+        input: usize,
+        pool: Arc<Vec<f32>>,
+        rng: Box<StdRng>,
+    },
+}
+
+#[derive(Debug)]
+struct InputPool {
+    values: Vec<Vec<f32>>,
+    seen: usize,
+    rng: StdRng,
+}
+
+impl InputPool {
+    fn new(inputs: usize, seed: u64) -> Self {
+        Self {
+            values: vec![Vec::new(); inputs],
+            seen: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    fn record(&mut self, inputs: &[f32]) {
+        self.seen += 1;
+        let slot = if self.seen <= SHUFFLE_POOL {
+            None
+        } else {
+            Some(self.rng.gen_range(0..self.seen)).filter(|&slot| slot < SHUFFLE_POOL)
+        };
+        for (values, &value) in self.values.iter_mut().zip(inputs) {
+            match slot {
+                None => values.push(value),
+                Some(slot) => values[slot] = value,
+            }
+        }
+    }
+}
+
+/// Wraps a brain and rewrites its inputs before every step.
+#[derive(Debug, Clone)]
+struct InputProbe {
+    brain: Box<dyn Brain>,
+    mode: ProbeMode,
+    inputs: Vec<f32>,
+}
+
+impl InputProbe {
+    fn new(brain: Box<dyn Brain>, mode: ProbeMode) -> Self {
+        Self {
+            brain,
+            mode,
+            inputs: Vec::new(),
+        }
+    }
+}
+
+impl Brain for InputProbe {
+    fn forward_into<'s>(&mut self, inputs: &[f32], scratch: &'s mut ForwardScratch) -> &'s [f32] {
+        self.inputs.clear();
+        self.inputs.extend_from_slice(inputs);
+        match &mut self.mode {
+            ProbeMode::Record(pool) => pool
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .record(inputs),
+            ProbeMode::Zero(input) => self.inputs[*input] = 0.0,
+            ProbeMode::Shuffle { input, pool, rng } => {
+                if !pool.is_empty() {
+                    self.inputs[*input] = pool[rng.gen_range(0..pool.len())];
+                }
+            }
+        }
+        self.brain.forward_into(&self.inputs, scratch)
+    }
+
+    fn reset_state(&mut self) {
+        self.brain.reset_state();
+    }
+
+    fn observe_world(&mut self, world: &World, position: (usize, usize), heading: Heading) {
+        self.brain.observe_world(world, position, heading);
+    }
+
+    fn param_count(&self) -> u32 {
+        self.brain.param_count()
+    }
+
+    fn layer_count(&self) -> u32 {
+        self.brain.layer_count()
+    }
+
+    fn hidden_count(&self) -> u32 {
+        self.brain.hidden_count()
+    }
+
+    fn hidden_activations(&self) -> Vec<Activation> {
+        self.brain.hidden_activations()
+    }
+
+    fn snapshot(&self) -> BrainSnapshot {
+        self.brain.snapshot()
+    }
+
+    fn box_clone(&self) -> Box<dyn Brain> {
+        Box::new(self.clone())
+    }
+}
+
+/// Plays `episodes` seeded episodes with `copies` creatures and returns the
+/// mean fitness of each. Every variant sees the same worlds and start
+/// positions.
+pub(crate) struct Evaluator<'a> {
+    config: &'a Config,
+    runner: EpisodeRunner<'a>,
+    episodes: usize,
+    copies: usize,
+    seed: u64,
+}
+
+impl<'a> Evaluator<'a> {
+    pub(crate) fn new(config: &'a Config, episodes: usize, copies: usize, seed: u64) -> Self {
+        Self {
+            config,
+            runner: EpisodeRunner::new(config).quiet(),
+            episodes,
+            copies,
+            seed,
+        }
+    }
+
+    /// `brain(i)` builds the `i`-th creature over all episodes.
+    pub(crate) fn fitness(&mut self, brain: impl Fn(usize) -> Box<dyn Brain>) -> Vec<f64> {
+        let config = self.config;
+        (0..self.episodes)
+            .map(|episode| {
+                let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(episode as u64));
+                let mut world = World::new(config.width, config.height, config.food, &mut rng);
+                let mut population: Vec<Creature> = (0..self.copies)
+                    .map(|copy| {
+                        Creature::from_boxed(brain(episode * self.copies + copy), 0, 0, 0.0)
+                    })
+                    .collect();
+                let mut collector = MetricsCollector::new();
+                self.runner
+                    .run(0, &mut population, &mut world, &mut rng, &mut collector);
+                let total: f64 = population
+                    .iter()
+                    .map(|creature| fitness_of(creature, config) as f64)
+                    .sum();
+                total / population.len().max(1) as f64
+            })
+            .collect()
+    }
+}
+
+/// Hidden neurons of a layered or graph brain, as `(label, knocked-out copy)`.
+fn knockouts(snapshot: &BrainSnapshot) -> Vec<(String, BrainSnapshot)> {
+    match snapshot {
+        BrainSnapshot::Layered { genome } => (1..genome.layers.len().saturating_sub(1))
+            .flat_map(|layer| (0..genome.layers[layer]).map(move |neuron| (layer, neuron)))
+            .map(|(layer, neuron)| {
+                let mut genome = genome.clone();
+                genome.knock_out(layer, neuron);
+                (
+                    format!("h{layer}.{neuron}"),
+                    BrainSnapshot::Layered { genome },
+                )
+            })
+            .collect(),
+        BrainSnapshot::Graph { genome } => genome
+            .nodes
+            .iter()
+            .filter(|node| node.kind == NodeKind::Hidden)
+            .map(|node| {
+                let mut genome = genome.clone();
+                genome.knock_out(node.id);
+                (format!("node{}", node.id), BrainSnapshot::Graph { genome })
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Adds uniform noise in `[-sigma, sigma]` to every weight and bias. `None`
+/// for brains without evolved parameters.
+fn perturb(snapshot: &BrainSnapshot, sigma: f32, rng: &mut impl Rng) -> Option<BrainSnapshot> {
+    let mut noisy = snapshot.clone();
+    match &mut noisy {
+        BrainSnapshot::Layered { genome } => {
+            for weight in &mut genome.weights {
+                *weight += rng.gen_range(-sigma..=sigma);
+            }
+        }
+        BrainSnapshot::Graph { genome } => {
+            for conn in &mut genome.connections {
+                conn.weight += rng.gen_range(-sigma..=sigma);
+            }
+            for node in &mut genome.nodes {
+                node.bias += rng.gen_range(-sigma..=sigma);
+            }
+        }
+        _ => return None,
+    }
+    Some(noisy)
+}
+
+/// Builds the sensitivity table: the intact brain, then every input zeroed
+/// and shuffled, every hidden neuron knocked out and every noise level.
+pub fn sensitivity_table(
+    config: &Config,
+    snapshot: &BrainSnapshot,
+    args: &AblateArgs,
+) -> Vec<Sensitivity> {
+    let seed = args.seed.unwrap_or(config.seed);
+    let copies = args.copies.unwrap_or(config.population).max(1);
+    let mut evaluator = Evaluator::new(config, args.episodes, copies, seed);
+    let input_names = SensorSuite::from_config(config).input_names();
+
+    let pool = Arc::new(Mutex::new(InputPool::new(input_names.len(), seed)));
+    let intact = evaluator.fitness(|_| {
+        Box::new(InputProbe::new(
+            snapshot.clone().into_brain(),
+            ProbeMode::Record(pool.clone()),
+        ))
+    });
+    let pools: Vec<Arc<Vec<f32>>> =
+        std::mem::take(&mut pool.lock().unwrap_or_else(PoisonError::into_inner).values)
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+
+    let mut rows = vec![Sensitivity::of("intact", "-".to_string(), &intact, &intact)];
+    for (input, name) in input_names.iter().enumerate() {
+        let zeroed = evaluator.fitness(|_| {
+            Box::new(InputProbe::new(
+                snapshot.clone().into_brain(),
+                ProbeMode::Zero(input),
+            ))
+        });
+        rows.push(Sensitivity::of("zero", name.clone(), &zeroed, &intact));
+        let shuffled = evaluator.fitness(|idx| {
+            Box::new(InputProbe::new(
+                snapshot.clone().into_brain(),
+                ProbeMode::Shuffle {
+                    input,
+                    pool: pools[input].clone(),
+                    rng: Box::new(StdRng::seed_from_u64(
+                        seed ^ ((input as u64) << 32) ^ idx as u64,
+                    )),
+                },
+            ))
+        });
+        rows.push(Sensitivity::of("shuffle", name.clone(), &shuffled, &intact));
+    }
+
+    for (label, knocked) in knockouts(snapshot) {
+        let fitness = evaluator.fitness(|_| knocked.clone().into_brain());
+        rows.push(Sensitivity::of("knockout", label, &fitness, &intact));
+    }
+
+    let mut noise_rng = StdRng::seed_from_u64(seed);
+    for &sigma in &args.noise {
+        let mut fitness = Vec::with_capacity(args.noise_samples * args.episodes);
+        for _ in 0..args.noise_samples {
+            let Some(noisy) = perturb(snapshot, sigma, &mut noise_rng) else {
+                break;
+            };
+            fitness.extend(evaluator.fitness(|_| noisy.clone().into_brain()));
+        }
+        if !fitness.is_empty() {
+            rows.push(Sensitivity::of(
+                "weight_noise",
+                sigma.to_string(),
+                &fitness,
+                &intact,
+            ));
+        }
+    }
+    rows
+}
+
+/// One sentence on whether fitness depends on the `noise` input.
+fn noise_verdict(rows: &[Sensitivity]) -> String {
+    let noise: Vec<&Sensitivity> = rows.iter().filter(|row| row.target == "noise").collect();
+    if noise.is_empty() {
+        return "The run has no noise input.".to_string();
+    }
+    let significant: Vec<String> = noise
+        .iter()
+        .filter(|row| row.p.is_some_and(|p| p < SIGNIFICANCE))
+        .map(|row| format!("{} changes fitness by {:.3}", row.kind, row.delta))
+        .collect();
+    if significant.is_empty() {
+        format!("No evidence the champion exploits the noise input (p >= {SIGNIFICANCE}).")
+    } else {
+        format!(
+            "The champion depends on the noise input (p < {SIGNIFICANCE}): {}.",
+            significant.join(", ")
+        )
+    }
+}
+
+pub fn run_ablation(args: &AblateArgs) -> Result<(), Box<dyn Error>> {
+    if args.episodes == 0 {
+        return Err("episodes must be at least 1".into());
+    }
+    if let Some(sigma) = args
+        .noise
+        .iter()
+        .find(|sigma| !(sigma.is_finite() && **sigma >= 0.0))
+    {
+        return Err(format!("noise levels must be finite and non-negative, got {sigma}").into());
+    }
+    let (config, brain) = load_champion(&args.run, args.champion.as_deref())?;
+    let rows = sensitivity_table(&config, &brain, args);
+    print_table(
+        &["kind", "target", "mean", "std", "delta", "p", "a12"],
+        rows.iter()
+            .map(|row| {
+                vec![
+                    row.kind.to_string(),
+                    row.target.clone(),
+                    format_value(row.mean),
+                    format_value(row.std),
+                    format_value(row.delta),
+                    row.p.map(format_value).unwrap_or_else(|| "-".to_string()),
+                    row.a12.map(format_value).unwrap_or_else(|| "-".to_string()),
+                ]
+            })
+            .collect(),
+    );
+    println!("\n{}", noise_verdict(&rows));
+
+    if let Some(out) = &args.out {
+        let mut writer = csv::Writer::from_path(out)?;
+        for row in &rows {
+            writer.serialize(row)?;
+        }
+        writer.flush()?;
+        println!("Wrote {}", out.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::{Genome, Recurrence};
+    use crate::neural_net::NeuralNet;
+
+    #[test]
+    fn knocking_out_every_hidden_neuron_cuts_the_inputs() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let genome = Genome::random_with_recurrence(vec![3, 4, 2], Recurrence::Elman, &mut rng);
+        let snapshot = BrainSnapshot::Layered { genome };
+        let variants = knockouts(&snapshot);
+        assert_eq!(variants.len(), 4);
+        assert_eq!(variants[1].0, "h1.1");
+
+        let mut genome = match snapshot {
+            BrainSnapshot::Layered { genome } => genome,
+            _ => unreachable!(),
+        };
+        for neuron in 0..4 {
+            genome.knock_out(1, neuron);
+        }
+        let mut net = NeuralNet::new(genome);
+        let mut scratch = ForwardScratch::default();
+        let first = net.forward_into(&[1.0, -1.0, 0.5], &mut scratch).to_vec();
+        let second = net.forward_into(&[-0.3, 0.9, 0.0], &mut scratch).to_vec();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn probe_zeroes_and_records_inputs() {
+        let brain = BrainSnapshot::RandomWalker {
+            outputs: 2,
+            seed: 1,
+        };
+        let pool = Arc::new(Mutex::new(InputPool::new(2, 0)));
+        let mut recorder =
+            InputProbe::new(brain.clone().into_brain(), ProbeMode::Record(pool.clone()));
+        let mut scratch = ForwardScratch::default();
+        for step in 0..(SHUFFLE_POOL + 10) {
+            recorder.forward_into(&[step as f32, 1.0], &mut scratch);
+        }
+        let pool = pool.lock().unwrap();
+        assert_eq!(pool.values[0].len(), SHUFFLE_POOL);
+        assert!(pool.values[1].iter().all(|&value| value == 1.0));
+
+        let mut zeroed = InputProbe::new(brain.into_brain(), ProbeMode::Zero(1));
+        zeroed.forward_into(&[2.0, 3.0], &mut scratch);
+        assert_eq!(zeroed.inputs, vec![2.0, 0.0]);
+    }
+
+    #[test]
+    fn verdict_reports_significant_noise_rows() {
+        let row = |kind, target: &str, p| Sensitivity {
+            kind,
+            target: target.to_string(),
+            mean: 0.0,
+            std: 0.0,
+            delta: -1.0,
+            p: Some(p),
+            a12: Some(0.0),
+        };
+        let quiet = [row("zero", "noise", 0.4), row("zero", "energy", 0.001)];
+        assert!(noise_verdict(&quiet).starts_with("No evidence"));
+        let used = [row("zero", "noise", 0.4), row("shuffle", "noise", 0.01)];
+        assert!(noise_verdict(&used).contains("shuffle changes fitness by -1.000"));
+        assert!(noise_verdict(&[]).contains("no noise input"));
+    }
+}
@@ -0,0 +1,451 @@
+use clap::ValueEnum;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+    Stay,
+    TurnLeft,
+    TurnRight,
+    Forward,
+}
+
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::UpLeft,
+        Action::UpRight,
+        Action::DownLeft,
+        Action::DownRight,
+        Action::Stay,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::Forward,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::UpLeft => "up-left",
+            Action::UpRight => "up-right",
+            Action::DownLeft => "down-left",
+            Action::DownRight => "down-right",
+            Action::Stay => "stay",
+            Action::TurnLeft => "turn-left",
+            Action::TurnRight => "turn-right",
+            Action::Forward => "forward",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Grid offset of an absolute move, `None` for heading-relative actions.
+    pub fn offset(self) -> Option<(i32, i32)> {
+        match self {
+            Action::Up => Some((0, -1)),
+            Action::Down => Some((0, 1)),
+            Action::Left => Some((-1, 0)),
+            Action::Right => Some((1, 0)),
+            Action::UpLeft => Some((-1, -1)),
+            Action::UpRight => Some((1, -1)),
+            Action::DownLeft => Some((-1, 1)),
+            Action::DownRight => Some((1, 1)),
+            Action::Stay => Some((0, 0)),
+            Action::TurnLeft | Action::TurnRight | Action::Forward => None,
+        }
+    }
+
+    /// The action that undoes this one, if any.
+    pub fn opposite(self) -> Option<Action> {
+        match self {
+            Action::Up => Some(Action::Down),
+            Action::Down => Some(Action::Up),
+            Action::Left => Some(Action::Right),
+            Action::Right => Some(Action::Left),
+            Action::UpLeft => Some(Action::DownRight),
+            Action::DownRight => Some(Action::UpLeft),
+            Action::UpRight => Some(Action::DownLeft),
+            Action::DownLeft => Some(Action::UpRight),
+            Action::TurnLeft => Some(Action::TurnRight),
+            Action::TurnRight => Some(Action::TurnLeft),
+            Action::Stay | Action::Forward => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Heading {
+    #[default]
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Heading {
+    pub const ALL: [Heading; 4] = [Heading::Up, Heading::Right, Heading::Down, Heading::Left];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Heading::Up => "up",
+            Heading::Right => "right",
+            Heading::Down => "down",
+            Heading::Left => "left",
+        }
+    }
+
+    pub fn turn_left(self) -> Heading {
+        match self {
+            Heading::Up => Heading::Left,
+            Heading::Left => Heading::Down,
+            Heading::Down => Heading::Right,
+            Heading::Right => Heading::Up,
+        }
+    }
+
+    pub fn turn_right(self) -> Heading {
+        match self {
+            Heading::Up => Heading::Right,
+            Heading::Right => Heading::Down,
+            Heading::Down => Heading::Left,
+            Heading::Left => Heading::Up,
+        }
+    }
+
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Heading::Up => (0, -1),
+            Heading::Right => (1, 0),
+            Heading::Down => (0, 1),
+            Heading::Left => (-1, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum ActionSet {
+    Four,
+    Eight,
+    Heading,
+}
+
+/// The actions a brain can choose from, in output-neuron order, together with
+/// the energy each one costs.
+#[derive(Debug, Clone)]
+pub struct ActionSpace {
+    actions: Vec<Action>,
+    costs: [f32; Action::ALL.len()],
+}
+
+impl ActionSpace {
+    pub fn from_config(config: &Config) -> Self {
+        let mut actions = match config.action_set {
+            ActionSet::Four => vec![Action::Up, Action::Down, Action::Left, Action::Right],
+            ActionSet::Eight => vec![
+                Action::Up,
+                Action::Down,
+                Action::Left,
+                Action::Right,
+                Action::UpLeft,
+                Action::UpRight,
+                Action::DownLeft,
+                Action::DownRight,
+            ],
+            ActionSet::Heading => vec![Action::TurnLeft, Action::TurnRight, Action::Forward],
+        };
+        if config.allow_stay {
+            actions.push(Action::Stay);
+        }
+        let mut costs = [config.move_cost; Action::ALL.len()];
+        let overrides =
+            parse_action_costs(&config.action_costs).expect("action-costs should be validated");
+        for (action, cost) in overrides {
+            costs[action as usize] = cost;
+        }
+        Self { actions, costs }
+    }
+
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    pub fn action(&self, idx: usize) -> Action {
+        self.actions[idx]
+    }
+
+    pub fn index_of(&self, action: Action) -> Option<usize> {
+        self.actions
+            .iter()
+            .position(|&candidate| candidate == action)
+    }
+
+    pub fn cost(&self, action: Action) -> f32 {
+        self.costs[action as usize]
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.actions
+            .iter()
+            .map(|action| action.name().to_string())
+            .collect()
+    }
+
+    /// Keeps only the stronger output of every pair of opposite actions in
+    /// `chosen`, so a step never pays for a move and its reversal. On a tie
+    /// the action with the lower output index stays.
+    pub fn drop_opposites(&self, outputs: &[f32], chosen: &mut Vec<Action>) {
+        let picked = chosen
+            .iter()
+            .fold(0u32, |mask, &action| mask | 1 << action as u32);
+        let rank = |action: Action| {
+            let idx = self
+                .index_of(action)
+                .expect("chosen actions are in the space");
+            (outputs[idx], std::cmp::Reverse(idx))
+        };
+        chosen.retain(|&action| match action.opposite() {
+            Some(opposite) if picked & (1 << opposite as u32) != 0 => rank(action) > rank(opposite),
+            _ => true,
+        });
+    }
+
+    /// Fills `chosen` with the actions `policy` picks from the network
+    /// outputs.
+    pub fn choose(
+        &self,
+        outputs: &[f32],
+        policy: &ActionPolicy,
+        rng: &mut impl Rng,
+        chosen: &mut Vec<Action>,
+    ) {
+        chosen.clear();
+        policy.select(outputs, rng, |idx| chosen.push(self.action(idx)));
+        if chosen.len() > 1 {
+            self.drop_opposites(outputs, chosen);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum PolicyMode {
+    Argmax,
+    Softmax,
+    #[value(name = "epsilon")]
+    EpsilonGreedy,
+    /// Every output at `--action-threshold` or above, keeping only the
+    /// stronger of two opposite actions.
+    Threshold,
+}
+
+/// Turns the network outputs into the actions taken this step. Every random
+/// choice goes through the simulation RNG so runs stay reproducible per seed.
+#[derive(Debug, Clone, Copy)]
+pub struct ActionPolicy {
+    pub mode: PolicyMode,
+    pub temperature: f32,
+    pub epsilon: f32,
+    pub threshold: f32,
+}
+
+impl ActionPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            mode: config.policy,
+            temperature: config.temperature,
+            epsilon: config.epsilon,
+            threshold: config.action_threshold,
+        }
+    }
+
+    /// Calls `emit` with each chosen output index. Only the threshold mode can
+    /// pick more than one; it falls back to the argmax when no output reaches
+    /// the threshold.
+    pub fn select(&self, outputs: &[f32], rng: &mut impl Rng, mut emit: impl FnMut(usize)) {
+        match self.mode {
+            PolicyMode::Argmax => emit(argmax(outputs)),
+            PolicyMode::Softmax => emit(sample_softmax(outputs, self.temperature, rng)),
+            PolicyMode::EpsilonGreedy => {
+                if rng.gen::<f32>() < self.epsilon {
+                    emit(rng.gen_range(0..outputs.len()));
+                } else {
+                    emit(argmax(outputs));
+                }
+            }
+            PolicyMode::Threshold => {
+                let mut any = false;
+                for (idx, &value) in outputs.iter().enumerate() {
+                    if value >= self.threshold {
+                        emit(idx);
+                        any = true;
+                    }
+                }
+                if !any {
+                    emit(argmax(outputs));
+                }
+            }
+        }
+    }
+}
+
+pub fn argmax(values: &[f32]) -> usize {
+    let mut best_idx = 0;
+    let mut best_val = values[0];
+    for (idx, &val) in values.iter().enumerate().skip(1) {
+        if val > best_val {
+            best_val = val;
+            best_idx = idx;
+        }
+    }
+    best_idx
+}
+
+fn sample_softmax(values: &[f32], temperature: f32, rng: &mut impl Rng) -> usize {
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let total: f32 = values
+        .iter()
+        .map(|&value| ((value - max) / temperature).exp())
+        .sum();
+    let mut target = rng.gen::<f32>() * total;
+    for (idx, &value) in values.iter().enumerate() {
+        target -= ((value - max) / temperature).exp();
+        if target <= 0.0 {
+            return idx;
+        }
+    }
+    values.len() - 1
+}
+
+/// Parses a cost override list such as `stay=0.2,turn-left=0.5`. Actions that
+/// are not listed cost `move-cost`.
+pub fn parse_action_costs(spec: &str) -> Result<Vec<(Action, f32)>, String> {
+    let mut costs = Vec::new();
+    for part in spec.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let (name, value) = part
+            .split_once('=')
+            .ok_or_else(|| format!("expected <action>=<cost> in '{part}'"))?;
+        let action = Action::from_name(name.trim())
+            .ok_or_else(|| format!("unknown action '{}' in '{part}'", name.trim()))?;
+        let cost = value
+            .trim()
+            .parse::<f32>()
+            .map_err(|_| format!("invalid cost '{}' in '{part}'", value.trim()))?;
+        if !cost.is_finite() || cost < 0.0 {
+            return Err(format!("cost must be a non-negative number in '{part}'"));
+        }
+        costs.push((action, cost));
+    }
+    Ok(costs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cost_overrides() {
+        let costs = parse_action_costs("stay=0.2, turn-left=0.5").unwrap();
+        assert_eq!(costs, vec![(Action::Stay, 0.2), (Action::TurnLeft, 0.5)]);
+        assert!(parse_action_costs("").unwrap().is_empty());
+        assert!(parse_action_costs("jump=1").is_err());
+        assert!(parse_action_costs("up=-1").is_err());
+    }
+
+    #[test]
+    fn policies_pick_expected_outputs() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let outputs = [0.1, 0.9, -0.4, 0.6];
+        let mut chosen = Vec::new();
+        let select = |policy: &ActionPolicy, rng: &mut StdRng, chosen: &mut Vec<usize>| {
+            chosen.clear();
+            policy.select(&outputs, rng, |idx| chosen.push(idx));
+        };
+        let mut policy = ActionPolicy {
+            mode: PolicyMode::Argmax,
+            temperature: 1.0,
+            epsilon: 0.0,
+            threshold: 0.5,
+        };
+        select(&policy, &mut rng, &mut chosen);
+        assert_eq!(chosen, vec![1]);
+
+        policy.mode = PolicyMode::Threshold;
+        select(&policy, &mut rng, &mut chosen);
+        assert_eq!(chosen, vec![1, 3]);
+
+        policy.mode = PolicyMode::Softmax;
+        policy.temperature = 0.01;
+        select(&policy, &mut rng, &mut chosen);
+        assert_eq!(chosen, vec![1], "a cold softmax behaves like argmax");
+
+        policy.temperature = 100.0;
+        let mut seen = [false; 4];
+        for _ in 0..200 {
+            select(&policy, &mut rng, &mut chosen);
+            seen[chosen[0]] = true;
+        }
+        assert!(
+            seen.iter().all(|&hit| hit),
+            "a hot softmax explores every action"
+        );
+    }
+
+    #[test]
+    fn opposite_actions_keep_the_stronger_output() {
+        use clap::Parser;
+
+        let config = Config::try_parse_from(["evobrain", "--actions", "eight"]).unwrap();
+        let space = ActionSpace::from_config(&config);
+        // up, down, left, right, up-left, up-right, down-left, down-right
+        let outputs = [0.6, 0.8, 0.7, 0.7, 0.9, 0.1, 0.1, 0.1];
+        let mut chosen = vec![Action::Up, Action::Down, Action::Left, Action::Right];
+        chosen.push(Action::UpLeft);
+        space.drop_opposites(&outputs, &mut chosen);
+        assert_eq!(chosen, vec![Action::Down, Action::Left, Action::UpLeft]);
+    }
+
+    #[test]
+    fn turning_cycles_through_headings() {
+        let mut heading = Heading::Up;
+        for _ in 0..4 {
+            heading = heading.turn_right();
+        }
+        assert_eq!(heading, Heading::Up);
+        assert_eq!(Heading::Up.turn_left(), Heading::Left);
+    }
+}
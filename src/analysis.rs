@@ -0,0 +1,398 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{create_dir_all, write};
+use std::path::PathBuf;
+
+use clap::Parser;
+use serde_json::Value;
+
+use crate::analysis::load::RunData;
+use crate::analysis::stats::{
+    area_under_curve, mann_whitney, mean_std, plateau_generation, MannWhitney,
+};
+use crate::analysis::svg::{line_plot, Series};
+
+pub mod load;
+pub mod stats;
+pub mod svg;
+
+/// Config fields that do not change what a run evolves; runs differing only
+/// in these belong to the same group.
+const NON_EXPERIMENT_FIELDS: [&str; 16] = [
+    "seed",
+    "run_id",
+    "out",
+    "run_metadata",
+    "dump_frames",
+    "frame_every",
+    "frames_dir",
+    "progress",
+    "fail_fast",
+    "logging_mode",
+    "log_gens",
+    "full_log_gens",
+    "full_log_keep",
+    "quick_keep",
+    "sinks",
+    "save_best",
+];
+
+/// `evobrain analyze`: summarizes, compares and plots finished run
+/// directories.
+#[derive(Parser, Debug, Clone)]
+#[command(name = "evobrain analyze")]
+pub struct AnalyzeArgs {
+    /// Run directories containing `manifest.json` and `generations.csv`.
+    #[arg(required = true)]
+    pub runs: Vec<PathBuf>,
+    /// Column summarized and compared between groups.
+    #[arg(long, default_value = "fitness_best")]
+    pub metric: String,
+    /// Config fields that define a group. By default runs whose configs differ
+    /// only in seed and output options form one group.
+    #[arg(long, value_delimiter = ',')]
+    pub group_by: Vec<String>,
+    /// Columns to plot, one `<out-dir>/<column>.svg` each.
+    #[arg(long, value_delimiter = ',')]
+    pub plot: Vec<String>,
+    #[arg(long, default_value = "analysis")]
+    pub out_dir: PathBuf,
+    /// Share of a run's total improvement that counts as reaching its plateau.
+    #[arg(long, default_value_t = 0.95)]
+    pub plateau: f64,
+}
+
+/// Summary of one run's `--metric` curve.
+#[derive(Debug, Clone, Copy)]
+struct CurveStats {
+    last: f64,
+    best: f64,
+    plateau: Option<u32>,
+    auc: f64,
+}
+
+impl CurveStats {
+    fn of(run: &RunData, metric: &str, plateau: f64) -> Result<Self, String> {
+        let values = run.column(metric)?;
+        let finite = || values.iter().copied().filter(|v| v.is_finite());
+        Ok(Self {
+            last: finite().next_back().unwrap_or(f64::NAN),
+            best: finite().fold(f64::NAN, f64::max),
+            plateau: plateau_generation(&run.generations, values, plateau),
+            auc: area_under_curve(&run.generations, values),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Group {
+    label: String,
+    runs: Vec<usize>,
+}
+
+pub fn run_analysis(args: &AnalyzeArgs) -> Result<(), Box<dyn Error>> {
+    if !(args.plateau > 0.0 && args.plateau <= 1.0) {
+        return Err("plateau must be in (0, 1]".into());
+    }
+    let runs = args
+        .runs
+        .iter()
+        .map(|dir| RunData::load(dir))
+        .collect::<Result<Vec<_>, _>>()?;
+    let groups = group_runs(&runs, &args.group_by);
+    let stats = runs
+        .iter()
+        .map(|run| CurveStats::of(run, &args.metric, args.plateau))
+        .collect::<Result<Vec<_>, _>>()?;
+    let group_of = |idx: usize| {
+        groups
+            .iter()
+            .find(|group| group.runs.contains(&idx))
+            .map(|group| group.label.as_str())
+            .unwrap_or("")
+    };
+
+    println!("Runs ({}):", args.metric);
+    print_table(
+        &[
+            "run", "group", "seed", "gens", "final", "best", "plateau", "auc",
+        ],
+        runs.iter()
+            .zip(&stats)
+            .enumerate()
+            .map(|(idx, (run, stats))| {
+                vec![
+                    run.run_id.clone(),
+                    group_of(idx).to_string(),
+                    run.seed.to_string(),
+                    run.generations.len().to_string(),
+                    format_value(stats.last),
+                    format_value(stats.best),
+                    stats
+                        .plateau
+                        .map(|gen| gen.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                    format_value(stats.auc),
+                ]
+            })
+            .collect(),
+    );
+
+    let sample = |group: &Group, pick: fn(&CurveStats) -> f64| -> Vec<f64> {
+        group
+            .runs
+            .iter()
+            .map(|&idx| pick(&stats[idx]))
+            .filter(|v| v.is_finite())
+            .collect()
+    };
+    println!("\nGroups:");
+    print_table(
+        &["group", "runs", "final", "best", "plateau", "auc"],
+        groups
+            .iter()
+            .map(|group| {
+                let plateaus: Vec<f64> = group
+                    .runs
+                    .iter()
+                    .filter_map(|&idx| stats[idx].plateau.map(f64::from))
+                    .collect();
+                vec![
+                    group.label.clone(),
+                    group.runs.len().to_string(),
+                    format_mean_std(&sample(group, |s| s.last)),
+                    format_mean_std(&sample(group, |s| s.best)),
+                    format_mean_std(&plateaus),
+                    format_mean_std(&sample(group, |s| s.auc)),
+                ]
+            })
+            .collect(),
+    );
+
+    if groups.len() > 1 {
+        println!("\nMann-Whitney U tests (two-sided):");
+        let mut rows = Vec::new();
+        for (i, a) in groups.iter().enumerate() {
+            for b in &groups[i + 1..] {
+                for (name, pick) in [
+                    ("final", (|s: &CurveStats| s.last) as fn(&CurveStats) -> f64),
+                    ("auc", |s: &CurveStats| s.auc),
+                ] {
+                    let test = mann_whitney(&sample(a, pick), &sample(b, pick));
+                    rows.push(comparison_row(&a.label, &b.label, name, test));
+                }
+            }
+        }
+        print_table(&["a", "b", "value", "U", "p", "A12", "method"], rows);
+    }
+
+    if !args.plot.is_empty() {
+        create_dir_all(&args.out_dir)?;
+        for column in &args.plot {
+            let series = groups
+                .iter()
+                .map(|group| group_series(group, &runs, column))
+                .collect::<Result<Vec<_>, _>>()?;
+            let path = args.out_dir.join(format!("{column}.svg"));
+            write(&path, line_plot(column, column, &series))?;
+            println!("Wrote {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Groups runs by the config fields in `group_by`, or by every field outside
+/// `NON_EXPERIMENT_FIELDS`. Labels name the fields whose values differ
+/// between groups.
+fn group_runs(runs: &[RunData], group_by: &[String]) -> Vec<Group> {
+    let key_of = |run: &RunData| -> BTreeMap<String, Value> {
+        let fields = run.config.as_object().cloned().unwrap_or_default();
+        if group_by.is_empty() {
+            fields
+                .into_iter()
+                .filter(|(name, _)| !NON_EXPERIMENT_FIELDS.contains(&name.as_str()))
+                .collect()
+        } else {
+            group_by
+                .iter()
+                .map(|name| {
+                    (
+                        name.clone(),
+                        fields.get(name).cloned().unwrap_or(Value::Null),
+                    )
+                })
+                .collect()
+        }
+    };
+    let mut keys: Vec<BTreeMap<String, Value>> = Vec::new();
+    let mut members: Vec<Vec<usize>> = Vec::new();
+    for (idx, run) in runs.iter().enumerate() {
+        let key = key_of(run);
+        match keys.iter().position(|existing| *existing == key) {
+            Some(group) => members[group].push(idx),
+            None => {
+                keys.push(key);
+                members.push(vec![idx]);
+            }
+        }
+    }
+
+    let differing: Vec<&String> = match keys.first() {
+        Some(first) if group_by.is_empty() => first
+            .keys()
+            .chain(keys.iter().flat_map(|key| key.keys()))
+            .filter(|name| keys.iter().any(|key| key.get(*name) != first.get(*name)))
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+        _ => group_by.iter().collect(),
+    };
+    keys.iter()
+        .zip(members)
+        .map(|(key, runs)| {
+            let label = differing
+                .iter()
+                .map(|name| format!("{name}={}", format_field(key.get(*name))))
+                .collect::<Vec<_>>()
+                .join(",");
+            Group {
+                label: if label.is_empty() {
+                    "all".to_string()
+                } else {
+                    label
+                },
+                runs,
+            }
+        })
+        .collect()
+}
+
+/// Mean of `column` per generation over the group's runs, with the min–max
+/// band across seeds.
+fn group_series(group: &Group, runs: &[RunData], column: &str) -> Result<Series, String> {
+    let mut by_generation: BTreeMap<u32, Vec<f64>> = BTreeMap::new();
+    for &idx in &group.runs {
+        let run = &runs[idx];
+        for (&gen, &value) in run.generations.iter().zip(run.column(column)?) {
+            if value.is_finite() {
+                by_generation.entry(gen).or_default().push(value);
+            }
+        }
+    }
+    let points = by_generation
+        .into_iter()
+        .map(|(gen, values)| {
+            let (mean, _) = mean_std(&values);
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            (gen as f64, mean, min, max)
+        })
+        .collect();
+    Ok(Series {
+        label: group.label.clone(),
+        points,
+    })
+}
+
+fn comparison_row(a: &str, b: &str, value: &str, test: Option<MannWhitney>) -> Vec<String> {
+    let mut row = vec![a.to_string(), b.to_string(), value.to_string()];
+    match test {
+        Some(test) => row.extend([
+            format_value(test.u),
+            format!("{:.4}", test.p),
+            format!("{:.3}", test.a12),
+            if test.exact { "exact" } else { "normal" }.to_string(),
+        ]),
+        None => row.extend(["-", "-", "-", "no data"].map(String::from)),
+    }
+    row
+}
+
+fn format_field(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+pub(crate) fn format_value(value: f64) -> String {
+    if value.is_finite() {
+        format!("{value:.3}")
+    } else {
+        "-".to_string()
+    }
+}
+
+fn format_mean_std(values: &[f64]) -> String {
+    match mean_std(values) {
+        (mean, std) if mean.is_finite() => format!("{mean:.3} ± {std:.3}"),
+        _ => "-".to_string(),
+    }
+}
+
+pub(crate) fn print_table(headers: &[&str], rows: Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<String>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    println!("{}", line(headers.iter().map(|h| h.to_string()).collect()));
+    for row in rows {
+        println!("{}", line(row));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(seed: u64, mut_rate: f64) -> RunData {
+        RunData {
+            dir: PathBuf::new(),
+            run_id: format!("r{seed}"),
+            seed,
+            config: serde_json::json!({
+                "seed": seed,
+                "run_id": format!("r{seed}"),
+                "mut_rate": mut_rate,
+                "population": 50,
+            }),
+            generations: vec![0, 1],
+            columns: BTreeMap::from([("fitness_best".to_string(), vec![1.0, seed as f64])]),
+        }
+    }
+
+    #[test]
+    fn groups_ignore_seed_and_name_differing_fields() {
+        let runs = [run(1, 0.1), run(2, 0.1), run(3, 0.2)];
+        let groups = group_runs(&runs, &[]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].label, "mut_rate=0.1");
+        assert_eq!(groups[0].runs, vec![0, 1]);
+        assert_eq!(groups[1].runs, vec![2]);
+
+        let one = group_runs(&runs[..2], &[]);
+        assert_eq!(one[0].label, "all");
+        let by_population = group_runs(&runs, &["population".to_string()]);
+        assert_eq!(by_population.len(), 1);
+        assert_eq!(by_population[0].label, "population=50");
+
+        let series = group_series(&groups[0], &runs, "fitness_best").unwrap();
+        assert_eq!(
+            series.points,
+            vec![(0.0, 1.0, 1.0, 1.0), (1.0, 1.5, 1.0, 2.0)]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::brain::BrainSnapshot;
+use crate::config::Config;
+use crate::sensors::SensorSuite;
+
+/// The parts of `manifest.json` the analysis needs.
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    run_id: String,
+    seed: u64,
+    config: serde_json::Value,
+}
+
+/// Any JSON object with a `brain` field, e.g. `champion.json` or a
+/// `--save-best` file.
+#[derive(Debug, Deserialize)]
+pub struct SavedBrain {
+    #[serde(default)]
+    pub generation: Option<u32>,
+    pub brain: BrainSnapshot,
+}
+
+impl SavedBrain {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        load_json(path)
+    }
+
+    /// Fails if the brain does not take `inputs` values.
+    pub fn check_inputs(&self, path: &Path, inputs: usize) -> Result<(), String> {
+        let expected = match &self.brain {
+            BrainSnapshot::Layered { genome } => genome.layers.first().copied(),
+            BrainSnapshot::Graph { genome } => Some(genome.inputs),
+            _ => None,
+        };
+        match expected {
+            Some(expected) if expected != inputs => Err(format!(
+                "{} takes {expected} inputs but the run's sensors give {inputs}",
+                path.display()
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// One run directory: its manifest and the numeric columns of
+/// `generations.csv`, one value per logged generation.
+#[derive(Debug, Clone)]
+pub struct RunData {
+    pub dir: PathBuf,
+    pub run_id: String,
+    pub seed: u64,
+    pub config: serde_json::Value,
+    pub generations: Vec<u32>,
+    /// Cells that are empty or not numbers (e.g. histograms) are NaN.
+    pub columns: BTreeMap<String, Vec<f64>>,
+}
+
+impl RunData {
+    pub fn load(dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let manifest_path = dir.join("manifest.json");
+        let manifest: Manifest = serde_json::from_reader(BufReader::new(
+            File::open(&manifest_path)
+                .map_err(|err| format!("cannot open {}: {err}", manifest_path.display()))?,
+        ))?;
+        let csv_path = dir.join("generations.csv");
+        let mut reader = csv::Reader::from_path(&csv_path)
+            .map_err(|err| format!("cannot open {}: {err}", csv_path.display()))?;
+        let headers = reader.headers()?.clone();
+        let generation_col = headers
+            .iter()
+            .position(|header| header == "generation")
+            .ok_or_else(|| format!("{} has no generation column", csv_path.display()))?;
+
+        // A rerun with the same id appends to the CSV; the last row for a
+        // generation wins.
+        let mut rows: BTreeMap<u32, csv::StringRecord> = BTreeMap::new();
+        for record in reader.records() {
+            let record = record?;
+            let generation = record[generation_col]
+                .parse::<u32>()
+                .map_err(|err| format!("{}: bad generation: {err}", csv_path.display()))?;
+            rows.insert(generation, record);
+        }
+
+        let mut columns: BTreeMap<String, Vec<f64>> = headers
+            .iter()
+            .enumerate()
+            .filter(|&(idx, _)| idx != generation_col)
+            .map(|(_, header)| (header.to_string(), Vec::with_capacity(rows.len())))
+            .collect();
+        for record in rows.values() {
+            for (header, cell) in headers.iter().zip(record.iter()) {
+                if let Some(values) = columns.get_mut(header) {
+                    values.push(cell.trim().parse::<f64>().unwrap_or(f64::NAN));
+                }
+            }
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            run_id: manifest.run_id,
+            seed: manifest.seed,
+            config: manifest.config,
+            generations: rows.keys().copied().collect(),
+            columns,
+        })
+    }
+
+    pub fn column(&self, name: &str) -> Result<&[f64], String> {
+        self.columns
+            .get(name)
+            .map(Vec::as_slice)
+            .ok_or_else(|| format!("run {} has no column {name}", self.run_id))
+    }
+}
+
+fn load_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T, Box<dyn Error>> {
+    let file = File::open(path).map_err(|err| format!("cannot open {}: {err}", path.display()))?;
+    serde_json::from_reader(BufReader::new(file))
+        .map_err(|err| format!("cannot read {}: {err}", path.display()).into())
+}
+
+/// The config saved in `<dir>/manifest.json`.
+pub fn load_run_config(dir: &Path) -> Result<Config, Box<dyn Error>> {
+    let manifest: Manifest = load_json(&dir.join("manifest.json"))?;
+    let config = Config::from_manifest(&manifest.config)?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// The config of the run in `dir` and the brain saved in `champion`, by
+/// default `<dir>/champion.json`. Fails if the brain does not fit the run's
+/// sensors.
+pub fn load_champion(
+    dir: &Path,
+    champion: Option<&Path>,
+) -> Result<(Config, BrainSnapshot), Box<dyn Error>> {
+    let config = load_run_config(dir)?;
+    let champion_path = champion
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| dir.join("champion.json"));
+    let saved = SavedBrain::load(&champion_path)?;
+    saved.check_inputs(
+        &champion_path,
+        SensorSuite::from_config(&config).input_size(),
+    )?;
+    Ok((config, saved.brain))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_manifest_and_dedupes_generations() {
+        let dir = std::env::temp_dir().join(format!("evobrain_load_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("manifest.json"),
+            r#"{"run_id":"r1","seed":5,"timestamp":"0","config":{"mut_rate":0.1}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("generations.csv"),
+            "generation,fitness_best,activation_counts\n0,1.5,tanh:3\n1,2.0,\n1,2.5,\n",
+        )
+        .unwrap();
+        let run = RunData::load(&dir).expect("load run");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(run.run_id, "r1");
+        assert_eq!(run.seed, 5);
+        assert_eq!(run.generations, vec![0, 1]);
+        assert_eq!(run.column("fitness_best").unwrap(), &[1.5, 2.5]);
+        assert!(run.column("activation_counts").unwrap()[0].is_nan());
+        assert!(run.column("missing").is_err());
+    }
+}
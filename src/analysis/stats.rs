@@ -0,0 +1,203 @@
+/// Largest group size for which `mann_whitney` enumerates the exact null
+/// distribution instead of using the normal approximation.
+const EXACT_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MannWhitney {
+    /// U statistic of the first sample.
+    pub u: f64,
+    /// Two-sided p-value.
+    pub p: f64,
+    /// Vargha–Delaney A12: probability that a value from the first sample
+    /// beats one from the second, ties counting half.
+    pub a12: f64,
+    pub exact: bool,
+}
+
+/// Two-sided Mann–Whitney U test. Exact when both samples have at most
+/// `EXACT_LIMIT` values and there are no ties, otherwise the tie-corrected
+/// normal approximation with continuity correction. `None` if either sample
+/// is empty.
+pub fn mann_whitney(a: &[f64], b: &[f64]) -> Option<MannWhitney> {
+    let (n1, n2) = (a.len(), b.len());
+    if n1 == 0 || n2 == 0 {
+        return None;
+    }
+    let mut pooled: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&value| (value, true))
+        .chain(b.iter().map(|&value| (value, false)))
+        .collect();
+    pooled.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    let mut start = 0;
+    while start < pooled.len() {
+        let mut end = start + 1;
+        while end < pooled.len() && pooled[end].0 == pooled[start].0 {
+            end += 1;
+        }
+        // Ranks start + 1 ..= end share their average.
+        let rank = (start + 1 + end) as f64 / 2.0;
+        let in_a = pooled[start..end]
+            .iter()
+            .filter(|(_, from_a)| *from_a)
+            .count();
+        rank_sum_a += rank * in_a as f64;
+        let t = (end - start) as f64;
+        tie_term += t * t * t - t;
+        start = end;
+    }
+
+    let (n1f, n2f) = (n1 as f64, n2 as f64);
+    let u = rank_sum_a - n1f * (n1f + 1.0) / 2.0;
+    let a12 = u / (n1f * n2f);
+    let exact = tie_term == 0.0 && n1 <= EXACT_LIMIT && n2 <= EXACT_LIMIT;
+    let p = if exact {
+        exact_p(n1, n2, u.round() as usize)
+    } else {
+        let n = n1f + n2f;
+        let mean = n1f * n2f / 2.0;
+        let var = n1f * n2f / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+        if var <= 0.0 {
+            1.0
+        } else {
+            let z = ((u - mean).abs() - 0.5).max(0.0) / var.sqrt();
+            (2.0 * (1.0 - normal_cdf(z))).min(1.0)
+        }
+    };
+    Some(MannWhitney { u, p, a12, exact })
+}
+
+/// Two-sided p-value of U = `u` under the null, from the number of
+/// orderings of `n1` + `n2` values giving each U.
+fn exact_p(n1: usize, n2: usize, u: usize) -> f64 {
+    // counts[j][k]: orderings of i values from the first sample and j from
+    // the second with U = k, built up over i.
+    let mut counts: Vec<Vec<f64>> = vec![vec![1.0]; n2 + 1];
+    for i in 1..=n1 {
+        let mut next: Vec<Vec<f64>> = Vec::with_capacity(n2 + 1);
+        next.push(vec![1.0]);
+        for j in 1..=n2 {
+            // The largest value comes from the first sample (beating all j)
+            // or from the second.
+            let mut dist = vec![0.0; i * j + 1];
+            for (k, &count) in counts[j].iter().enumerate() {
+                dist[k + j] += count;
+            }
+            for (k, &count) in next[j - 1].iter().enumerate() {
+                dist[k] += count;
+            }
+            next.push(dist);
+        }
+        counts = next;
+    }
+    let dist = &counts[n2];
+    let total: f64 = dist.iter().sum();
+    let lower: f64 = dist[..=u.min(n1 * n2)].iter().sum::<f64>() / total;
+    let upper: f64 = dist[u.min(n1 * n2)..].iter().sum::<f64>() / total;
+    (2.0 * lower.min(upper)).min(1.0)
+}
+
+fn normal_cdf(z: f64) -> f64 {
+    0.5 * (1.0 + erf(z / std::f64::consts::SQRT_2))
+}
+
+/// Abramowitz & Stegun 7.1.26; absolute error below 1.5e-7.
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let y = 1.0 - poly * (-x * x).exp();
+    if x < 0.0 {
+        -y
+    } else {
+        y
+    }
+}
+
+/// Trapezoidal area under `values` over `generations`, skipping NaN points.
+pub fn area_under_curve(generations: &[u32], values: &[f64]) -> f64 {
+    let points: Vec<(f64, f64)> = generations
+        .iter()
+        .zip(values)
+        .filter(|(_, value)| value.is_finite())
+        .map(|(&gen, &value)| (gen as f64, value))
+        .collect();
+    points
+        .windows(2)
+        .map(|pair| (pair[1].0 - pair[0].0) * (pair[0].1 + pair[1].1) / 2.0)
+        .sum()
+}
+
+/// First generation at which the running best reaches `fraction` of the
+/// run's total improvement over its first value. Higher is assumed better.
+pub fn plateau_generation(generations: &[u32], values: &[f64], fraction: f64) -> Option<u32> {
+    let points: Vec<(u32, f64)> = generations
+        .iter()
+        .zip(values)
+        .filter(|(_, value)| value.is_finite())
+        .map(|(&gen, &value)| (gen, value))
+        .collect();
+    let first = points.first()?.1;
+    let best = points.iter().map(|&(_, value)| value).fold(first, f64::max);
+    let target = first + fraction * (best - first);
+    let mut running = f64::NEG_INFINITY;
+    points.iter().find_map(|&(gen, value)| {
+        running = running.max(value);
+        (running >= target).then_some(gen)
+    })
+}
+
+/// Mean and sample standard deviation of the finite values.
+pub fn mean_std(values: &[f64]) -> (f64, f64) {
+    let finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if finite.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    let n = finite.len() as f64;
+    let mean = finite.iter().sum::<f64>() / n;
+    if finite.len() < 2 {
+        return (mean, 0.0);
+    }
+    let var = finite.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, var.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mann_whitney_exact_and_approximate() {
+        // Complete separation of 4 vs 4: P(U = 16) = 1/70, two-sided 2/70.
+        let high = [5.0, 6.0, 7.0, 8.0];
+        let low = [1.0, 2.0, 3.0, 4.0];
+        let result = mann_whitney(&high, &low).unwrap();
+        assert!(result.exact);
+        assert_eq!(result.u, 16.0);
+        assert_eq!(result.a12, 1.0);
+        assert!((result.p - 2.0 / 70.0).abs() < 1e-12);
+
+        let same = mann_whitney(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]).unwrap();
+        assert!(!same.exact, "ties force the normal approximation");
+        assert_eq!(same.a12, 0.5);
+        assert!(same.p > 0.9);
+        assert!(mann_whitney(&[], &low).is_none());
+    }
+
+    #[test]
+    fn curve_summaries() {
+        let generations = [0, 1, 2, 4];
+        let values = [0.0, 5.0, 9.5, 10.0];
+        assert_eq!(area_under_curve(&generations, &values), 2.5 + 7.25 + 19.5);
+        assert_eq!(plateau_generation(&generations, &values, 0.95), Some(2));
+        assert_eq!(plateau_generation(&generations, &[f64::NAN; 4], 0.95), None);
+        let (mean, std) = mean_std(&[1.0, 3.0, f64::NAN]);
+        assert_eq!(mean, 2.0);
+        assert!((std - 2f64.sqrt()).abs() < 1e-12);
+    }
+}
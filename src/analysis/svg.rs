@@ -0,0 +1,195 @@
+use std::fmt::Write;
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 480.0;
+const MARGIN_LEFT: f64 = 70.0;
+const MARGIN_RIGHT: f64 = 180.0;
+const MARGIN_Y: f64 = 40.0;
+pub(crate) const PALETTE: [&str; 8] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+/// One group's curve: the mean line and, with more than one seed, the
+/// min–max band around it.
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub label: String,
+    /// `(generation, mean, min, max)`, sorted by generation.
+    pub points: Vec<(f64, f64, f64, f64)>,
+}
+
+/// Renders a line plot of `series` against generation as a standalone SVG.
+pub fn line_plot(title: &str, y_label: &str, series: &[Series]) -> String {
+    let all = series.iter().flat_map(|s| s.points.iter());
+    let (mut x_min, mut x_max) = (f64::INFINITY, f64::NEG_INFINITY);
+    let (mut y_min, mut y_max) = (f64::INFINITY, f64::NEG_INFINITY);
+    for &(x, _, lo, hi) in all {
+        x_min = x_min.min(x);
+        x_max = x_max.max(x);
+        y_min = y_min.min(lo);
+        y_max = y_max.max(hi);
+    }
+    if !x_min.is_finite() {
+        (x_min, x_max, y_min, y_max) = (0.0, 1.0, 0.0, 1.0);
+    }
+    if x_max == x_min {
+        x_max = x_min + 1.0;
+    }
+    if y_max == y_min {
+        y_min -= 0.5;
+        y_max += 0.5;
+    }
+    let plot_w = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_h = HEIGHT - 2.0 * MARGIN_Y;
+    let sx = |x: f64| MARGIN_LEFT + (x - x_min) / (x_max - x_min) * plot_w;
+    let sy = |y: f64| MARGIN_Y + (y_max - y) / (y_max - y_min) * plot_h;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" font-family="sans-serif" font-size="12">"#
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="22" text-anchor="middle" font-size="15">{}</text>"#,
+        MARGIN_LEFT + plot_w / 2.0,
+        escape(title)
+    );
+    for tick in ticks(x_min, x_max) {
+        let x = sx(tick);
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{x:.1}" y1="{:.1}" x2="{x:.1}" y2="{:.1}" stroke="#ddd"/><text x="{x:.1}" y="{:.1}" text-anchor="middle">{}</text>"##,
+            MARGIN_Y,
+            MARGIN_Y + plot_h,
+            MARGIN_Y + plot_h + 16.0,
+            format_tick(tick)
+        );
+    }
+    for tick in ticks(y_min, y_max) {
+        let y = sy(tick);
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{:.1}" y1="{y:.1}" x2="{:.1}" y2="{y:.1}" stroke="#ddd"/><text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"##,
+            MARGIN_LEFT,
+            MARGIN_LEFT + plot_w,
+            MARGIN_LEFT - 6.0,
+            y + 4.0,
+            format_tick(tick)
+        );
+    }
+    let _ = writeln!(
+        svg,
+        r#"<rect x="{MARGIN_LEFT}" y="{MARGIN_Y}" width="{plot_w}" height="{plot_h}" fill="none" stroke="black"/>"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">generation</text>"#,
+        MARGIN_LEFT + plot_w / 2.0,
+        HEIGHT - 6.0
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text transform="translate(16 {:.1}) rotate(-90)" text-anchor="middle">{}</text>"#,
+        MARGIN_Y + plot_h / 2.0,
+        escape(y_label)
+    );
+
+    for (idx, series) in series.iter().enumerate() {
+        let color = PALETTE[idx % PALETTE.len()];
+        if series.points.iter().any(|&(_, _, lo, hi)| hi > lo) {
+            let upper = series.points.iter().map(|&(x, _, _, hi)| (x, hi));
+            let lower = series.points.iter().rev().map(|&(x, _, lo, _)| (x, lo));
+            let _ = writeln!(
+                svg,
+                r#"<polygon points="{}" fill="{color}" fill-opacity="0.2" stroke="none"/>"#,
+                path(upper.chain(lower), &sx, &sy)
+            );
+        }
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{color}" stroke-width="2"/>"#,
+            path(series.points.iter().map(|&(x, y, _, _)| (x, y)), &sx, &sy)
+        );
+        let legend_y = MARGIN_Y + 10.0 + idx as f64 * 18.0;
+        let legend_x = MARGIN_LEFT + plot_w + 12.0;
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{legend_x}" y1="{legend_y}" x2="{}" y2="{legend_y}" stroke="{color}" stroke-width="3"/><text x="{}" y="{}">{}</text>"#,
+            legend_x + 18.0,
+            legend_x + 24.0,
+            legend_y + 4.0,
+            escape(&series.label)
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn path(
+    points: impl Iterator<Item = (f64, f64)>,
+    sx: &impl Fn(f64) -> f64,
+    sy: &impl Fn(f64) -> f64,
+) -> String {
+    points
+        .map(|(x, y)| format!("{:.1},{:.1}", sx(x), sy(y)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Roughly five round tick values covering `[min, max]`.
+fn ticks(min: f64, max: f64) -> Vec<f64> {
+    let raw = (max - min) / 5.0;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|&step| step >= raw)
+        .unwrap_or(10.0 * magnitude);
+    let mut tick = (min / step).ceil() * step;
+    let mut ticks = Vec::new();
+    while tick <= max + step * 1e-9 {
+        ticks.push(tick);
+        tick += step;
+    }
+    ticks
+}
+
+fn format_tick(value: f64) -> String {
+    if value.abs() >= 1e4 || (value != 0.0 && value.abs() < 1e-2) {
+        format!("{value:.1e}")
+    } else {
+        let text = format!("{value:.2}");
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plot_has_band_only_with_spread() {
+        let spread = Series {
+            label: "a<b".to_string(),
+            points: vec![(0.0, 1.0, 0.5, 1.5), (1.0, 2.0, 1.0, 3.0)],
+        };
+        let single = Series {
+            label: "single".to_string(),
+            points: vec![(0.0, 1.0, 1.0, 1.0), (1.0, 1.5, 1.5, 1.5)],
+        };
+        let svg = line_plot("fitness", "fitness_best", &[spread, single]);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<polygon").count(), 1);
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains("a&lt;b"));
+        assert_eq!(ticks(0.0, 10.0), vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+    }
+}
@@ -0,0 +1,96 @@
+use clap::ValueEnum;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::action::{ActionPolicy, ActionSpace, PolicyMode};
+use crate::brain::{Brain, GreedySeeker, RandomWalker, ShortestPathForager};
+use crate::config::Config;
+use crate::creature::Creature;
+use crate::fitness::fitness_of;
+use crate::metrics::collector::MetricsCollector;
+use crate::simulation::EpisodeRunner;
+use crate::world::World;
+
+/// Hand-written reference controllers that evolved brains are measured against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum Baseline {
+    Random,
+    Greedy,
+    ShortestPath,
+}
+
+impl Baseline {
+    pub fn brain(self, config: &Config, rng: &mut impl Rng) -> Box<dyn Brain> {
+        let actions = ActionSpace::from_config(config);
+        match self {
+            Baseline::Random => Box::new(RandomWalker::new(actions.len(), rng.gen())),
+            Baseline::Greedy => Box::new(GreedySeeker::new(
+                config.food_vision_radius,
+                config.distance_metric,
+                actions.actions(),
+            )),
+            Baseline::ShortestPath => Box::new(ShortestPathForager::new(actions.actions())),
+        }
+    }
+
+    pub fn population(self, size: usize, config: &Config, rng: &mut impl Rng) -> Vec<Creature> {
+        (0..size)
+            .map(|_| Creature::from_boxed(self.brain(config, rng), 0, 0, 0.0))
+            .collect()
+    }
+}
+
+/// Mean `compute_fitness` of each evaluated baseline for one generation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BaselineScores {
+    pub random: Option<f32>,
+    pub greedy: Option<f32>,
+    pub shortest_path: Option<f32>,
+}
+
+/// Runs every baseline for one episode on the world the evolved population
+/// just played. `world` and `rng` must be the state right before that
+/// population was placed, so the baselines start from the same positions and
+/// see the same food until their behaviour diverges. Baselines always act
+/// through an argmax policy and never touch the caller's RNG.
+pub struct BaselineEvaluator<'a> {
+    config: &'a Config,
+    runner: EpisodeRunner<'a>,
+}
+
+impl<'a> BaselineEvaluator<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        let mut policy = ActionPolicy::from_config(config);
+        policy.mode = PolicyMode::Argmax;
+        Self {
+            config,
+            runner: EpisodeRunner::new(config).with_policy(policy).quiet(),
+        }
+    }
+
+    pub fn evaluate(&mut self, gen: usize, world: &World, rng: &StdRng) -> BaselineScores {
+        let mut scores = BaselineScores::default();
+        for &baseline in &self.config.baselines {
+            let mut brain_rng = StdRng::seed_from_u64(self.config.seed ^ (gen as u64 + 1));
+            let mut population =
+                baseline.population(self.config.population, self.config, &mut brain_rng);
+            let mut world = world.clone();
+            let mut rng = rng.clone();
+            let mut collector = MetricsCollector::new();
+            self.runner
+                .run(gen, &mut population, &mut world, &mut rng, &mut collector);
+            let total: f32 = population
+                .iter()
+                .map(|creature| fitness_of(creature, self.config))
+                .sum();
+            let score = Some(total / population.len().max(1) as f32);
+            match baseline {
+                Baseline::Random => scores.random = score,
+                Baseline::Greedy => scores.greedy = score,
+                Baseline::ShortestPath => scores.shortest_path = score,
+            }
+        }
+        scores
+    }
+}
@@ -0,0 +1,189 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::action::Heading;
+use crate::genome::{Activation, Genome};
+use crate::neat::{GraphGenome, GraphNet, NodeKind};
+use crate::neural_net::{ForwardScratch, NeuralNet};
+use crate::world::World;
+
+pub mod baseline;
+pub mod diagram;
+
+pub use baseline::{GreedySeeker, RandomWalker, ShortestPathForager};
+
+/// A controller that maps sensor inputs to one score per action.
+///
+/// Evolved brains expose their genome through [`Brain::genome`] so evolution
+/// can mutate and recombine it; hand-written controllers return `None` and are
+/// passed on unchanged.
+pub trait Brain: fmt::Debug + Send {
+    fn forward_into<'s>(&mut self, inputs: &[f32], scratch: &'s mut ForwardScratch) -> &'s [f32];
+
+    /// Clears any per-episode state.
+    fn reset_state(&mut self) {}
+
+    /// Called before every step with the creature's position in the world.
+    /// Evolved brains only see their sensor inputs and ignore it; hand-written
+    /// baselines use it to perceive the world directly.
+    fn observe_world(&mut self, _world: &World, _position: (usize, usize), _heading: Heading) {}
+
+    fn param_count(&self) -> u32;
+
+    fn layer_count(&self) -> u32;
+
+    fn hidden_count(&self) -> u32 {
+        self.layer_count().saturating_sub(2)
+    }
+
+    fn hidden_activations(&self) -> Vec<Activation> {
+        Vec::new()
+    }
+
+    fn genome(&self) -> Option<BrainGenome<'_>> {
+        None
+    }
+
+    fn snapshot(&self) -> BrainSnapshot;
+
+    fn box_clone(&self) -> Box<dyn Brain>;
+}
+
+impl Clone for Box<dyn Brain> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BrainGenome<'a> {
+    Layered(&'a Genome),
+    Graph(&'a GraphGenome),
+}
+
+impl BrainGenome<'_> {
+    /// Number of NaN or infinite weights and biases.
+    pub fn non_finite_params(&self) -> u32 {
+        let count = match self {
+            BrainGenome::Layered(genome) => genome
+                .weights
+                .iter()
+                .filter(|weight| !weight.is_finite())
+                .count(),
+            BrainGenome::Graph(genome) => {
+                let weights = genome.connections.iter().map(|conn| conn.weight);
+                let biases = genome.nodes.iter().map(|node| node.bias);
+                weights
+                    .chain(biases)
+                    .filter(|value| !value.is_finite())
+                    .count()
+            }
+        };
+        count as u32
+    }
+}
+
+/// Serialisable form of any [`Brain`], e.g. for `champion.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum BrainSnapshot {
+    Layered { genome: Genome },
+    Graph { genome: GraphGenome },
+    RandomWalker { outputs: usize, seed: u64 },
+    GreedySeeker(GreedySeeker),
+    ShortestPath(ShortestPathForager),
+}
+
+impl BrainSnapshot {
+    pub fn into_brain(self) -> Box<dyn Brain> {
+        match self {
+            BrainSnapshot::Layered { genome } => Box::new(NeuralNet::new(genome)),
+            BrainSnapshot::Graph { genome } => Box::new(GraphNet::new(genome)),
+            BrainSnapshot::RandomWalker { outputs, seed } => {
+                Box::new(RandomWalker::new(outputs, seed))
+            }
+            BrainSnapshot::GreedySeeker(seeker) => Box::new(seeker),
+            BrainSnapshot::ShortestPath(forager) => Box::new(forager),
+        }
+    }
+}
+
+impl Brain for NeuralNet {
+    fn forward_into<'s>(&mut self, inputs: &[f32], scratch: &'s mut ForwardScratch) -> &'s [f32] {
+        NeuralNet::forward_into(self, inputs, scratch)
+    }
+
+    fn reset_state(&mut self) {
+        NeuralNet::reset_state(self);
+    }
+
+    fn param_count(&self) -> u32 {
+        NeuralNet::param_count(self)
+    }
+
+    fn layer_count(&self) -> u32 {
+        NeuralNet::layer_count(self)
+    }
+
+    fn hidden_count(&self) -> u32 {
+        NeuralNet::hidden_count(self)
+    }
+
+    fn hidden_activations(&self) -> Vec<Activation> {
+        self.genome.hidden_activations().collect()
+    }
+
+    fn genome(&self) -> Option<BrainGenome<'_>> {
+        Some(BrainGenome::Layered(&self.genome))
+    }
+
+    fn snapshot(&self) -> BrainSnapshot {
+        BrainSnapshot::Layered {
+            genome: self.genome.clone(),
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Brain> {
+        Box::new(self.clone())
+    }
+}
+
+impl Brain for GraphNet {
+    fn forward_into<'s>(&mut self, inputs: &[f32], scratch: &'s mut ForwardScratch) -> &'s [f32] {
+        GraphNet::forward_into(self, inputs, scratch)
+    }
+
+    fn param_count(&self) -> u32 {
+        self.genome.param_count() as u32
+    }
+
+    /// Depth of the longest enabled path.
+    fn layer_count(&self) -> u32 {
+        self.genome.depth() as u32
+    }
+
+    /// One entry per hidden node.
+    fn hidden_activations(&self) -> Vec<Activation> {
+        self.genome
+            .nodes
+            .iter()
+            .filter(|node| node.kind == NodeKind::Hidden)
+            .map(|node| node.activation)
+            .collect()
+    }
+
+    fn genome(&self) -> Option<BrainGenome<'_>> {
+        Some(BrainGenome::Graph(&self.genome))
+    }
+
+    fn snapshot(&self) -> BrainSnapshot {
+        BrainSnapshot::Graph {
+            genome: self.genome.clone(),
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Brain> {
+        Box::new(self.clone())
+    }
+}
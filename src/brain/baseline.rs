@@ -0,0 +1,280 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::{Brain, BrainSnapshot};
+use crate::action::{Action, Heading};
+use crate::config::DistanceMetric;
+use crate::neural_net::ForwardScratch;
+use crate::world::World;
+
+/// Scores every action uniformly at random, so an argmax policy walks
+/// randomly. Draws come from its own seeded RNG.
+#[derive(Debug, Clone)]
+pub struct RandomWalker {
+    outputs: usize,
+    seed: u64,
+    rng: StdRng,
+}
+
+impl RandomWalker {
+    pub fn new(outputs: usize, seed: u64) -> Self {
+        Self {
+            outputs,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Brain for RandomWalker {
+    fn forward_into<'s>(&mut self, _inputs: &[f32], scratch: &'s mut ForwardScratch) -> &'s [f32] {
+        let out = &mut scratch.next;
+        out.clear();
+        out.extend((0..self.outputs).map(|_| self.rng.gen_range(-1.0..=1.0)));
+        out
+    }
+
+    fn param_count(&self) -> u32 {
+        0
+    }
+
+    fn layer_count(&self) -> u32 {
+        0
+    }
+
+    fn snapshot(&self) -> BrainSnapshot {
+        BrainSnapshot::RandomWalker {
+            outputs: self.outputs,
+            seed: self.seed,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Brain> {
+        Box::new(self.clone())
+    }
+}
+
+/// Moves towards the nearest food it can see, using the same perception as the
+/// `food` sensor. Each action is scored by how well its step lines up with the
+/// food direction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GreedySeeker {
+    vision_radius: u32,
+    distance_metric: DistanceMetric,
+    actions: Vec<Action>,
+    #[serde(skip)]
+    target: (f32, f32),
+    #[serde(skip)]
+    heading: Heading,
+}
+
+impl GreedySeeker {
+    pub fn new(vision_radius: u32, distance_metric: DistanceMetric, actions: &[Action]) -> Self {
+        Self {
+            vision_radius,
+            distance_metric,
+            actions: actions.to_vec(),
+            target: (0.0, 0.0),
+            heading: Heading::default(),
+        }
+    }
+}
+
+impl Brain for GreedySeeker {
+    fn forward_into<'s>(&mut self, _inputs: &[f32], scratch: &'s mut ForwardScratch) -> &'s [f32] {
+        let (dx, dy) = self.target;
+        let score = |(ox, oy): (i32, i32)| ox as f32 * dx + oy as f32 * dy;
+        let out = &mut scratch.next;
+        out.clear();
+        for &action in &self.actions {
+            let value = match action {
+                Action::Forward => score(self.heading.offset()),
+                // Turning is worth half a step in the new direction.
+                Action::TurnLeft => 0.5 * score(self.heading.turn_left().offset()),
+                Action::TurnRight => 0.5 * score(self.heading.turn_right().offset()),
+                _ => action.offset().map_or(0.0, score),
+            };
+            out.push(value);
+        }
+        out
+    }
+
+    fn observe_world(&mut self, world: &World, position: (usize, usize), heading: Heading) {
+        let (x, y) = position;
+        self.target = world.food_direction(x, y, self.vision_radius, self.distance_metric);
+        self.heading = heading;
+    }
+
+    fn param_count(&self) -> u32 {
+        0
+    }
+
+    fn layer_count(&self) -> u32 {
+        0
+    }
+
+    fn snapshot(&self) -> BrainSnapshot {
+        BrainSnapshot::GreedySeeker(self.clone())
+    }
+
+    fn box_clone(&self) -> Box<dyn Brain> {
+        Box::new(self.clone())
+    }
+}
+
+/// Sees the whole world and heads for the food that is the fewest moves away
+/// with the available actions. The grid has no obstacles, so the shortest path
+/// length is the Chebyshev distance when diagonal moves exist and the
+/// Manhattan distance otherwise.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortestPathForager {
+    actions: Vec<Action>,
+    #[serde(skip)]
+    view: Option<ForagerView>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ForagerView {
+    position: (usize, usize),
+    target: (usize, usize),
+    heading: Heading,
+    bounds: (usize, usize),
+}
+
+impl ShortestPathForager {
+    pub fn new(actions: &[Action]) -> Self {
+        Self {
+            actions: actions.to_vec(),
+            view: None,
+        }
+    }
+
+    fn diagonal(&self) -> bool {
+        self.actions
+            .iter()
+            .any(|action| matches!(action.offset(), Some((dx, dy)) if dx != 0 && dy != 0))
+    }
+
+    fn distance(&self, from: (usize, usize), to: (usize, usize)) -> usize {
+        let dx = from.0.abs_diff(to.0);
+        let dy = from.1.abs_diff(to.1);
+        if self.diagonal() {
+            dx.max(dy)
+        } else {
+            dx + dy
+        }
+    }
+}
+
+fn step((x, y): (usize, usize), (dx, dy): (i32, i32), (w, h): (usize, usize)) -> (usize, usize) {
+    let nx = (x as i64 + dx as i64).clamp(0, w.saturating_sub(1) as i64) as usize;
+    let ny = (y as i64 + dy as i64).clamp(0, h.saturating_sub(1) as i64) as usize;
+    (nx, ny)
+}
+
+impl Brain for ShortestPathForager {
+    /// Scores are negated remaining path lengths; a turn costs one extra step.
+    fn forward_into<'s>(&mut self, _inputs: &[f32], scratch: &'s mut ForwardScratch) -> &'s [f32] {
+        let out = &mut scratch.next;
+        out.clear();
+        let Some(view) = self.view else {
+            out.resize(self.actions.len(), 0.0);
+            return out;
+        };
+        let remaining =
+            |offset| self.distance(step(view.position, offset, view.bounds), view.target);
+        for &action in &self.actions {
+            let steps = match action {
+                Action::Forward => remaining(view.heading.offset()),
+                Action::TurnLeft => remaining(view.heading.turn_left().offset()) + 1,
+                Action::TurnRight => remaining(view.heading.turn_right().offset()) + 1,
+                _ => remaining(action.offset().unwrap_or((0, 0))),
+            };
+            out.push(-(steps as f32));
+        }
+        out
+    }
+
+    fn observe_world(&mut self, world: &World, position: (usize, usize), heading: Heading) {
+        self.view = world
+            .food
+            .iter()
+            .copied()
+            .min_by_key(|&food| self.distance(position, food))
+            .map(|target| ForagerView {
+                position,
+                target,
+                heading,
+                bounds: (world.width, world.height),
+            });
+    }
+
+    fn param_count(&self) -> u32 {
+        0
+    }
+
+    fn layer_count(&self) -> u32 {
+        0
+    }
+
+    fn snapshot(&self) -> BrainSnapshot {
+        BrainSnapshot::ShortestPath(self.clone())
+    }
+
+    fn box_clone(&self) -> Box<dyn Brain> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::argmax;
+
+    fn world_with_food(food: Vec<(usize, usize)>) -> World {
+        World {
+            width: 10,
+            height: 10,
+            food,
+        }
+    }
+
+    #[test]
+    fn greedy_seeker_prefers_step_towards_food() {
+        let actions = [Action::Up, Action::Down, Action::Left, Action::Right];
+        let mut seeker = GreedySeeker::new(0, DistanceMetric::Euclidean, &actions);
+        let mut scratch = ForwardScratch::default();
+        seeker.observe_world(&world_with_food(vec![(5, 1)]), (4, 8), Heading::Up);
+        assert_eq!(argmax(seeker.forward_into(&[], &mut scratch)), 0);
+        seeker.vision_radius = 2;
+        seeker.observe_world(&world_with_food(vec![(5, 1)]), (4, 8), Heading::Up);
+        assert!(seeker
+            .forward_into(&[], &mut scratch)
+            .iter()
+            .all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn shortest_path_forager_turns_towards_food() {
+        let actions = [Action::TurnLeft, Action::TurnRight, Action::Forward];
+        let mut forager = ShortestPathForager::new(&actions);
+        let mut scratch = ForwardScratch::default();
+        let world = world_with_food(vec![(9, 9), (6, 2)]);
+        forager.observe_world(&world, (2, 2), Heading::Up);
+        assert_eq!(argmax(forager.forward_into(&[], &mut scratch)), 1);
+        forager.observe_world(&world, (2, 2), Heading::Right);
+        assert_eq!(argmax(forager.forward_into(&[], &mut scratch)), 2);
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_json() {
+        let mut walker = RandomWalker::new(4, 9);
+        let json = serde_json::to_string(&walker.snapshot()).unwrap();
+        let snapshot: BrainSnapshot = serde_json::from_str(&json).unwrap();
+        let mut restored = snapshot.into_brain();
+        let mut scratch = ForwardScratch::default();
+        let expected = walker.forward_into(&[], &mut scratch).to_vec();
+        assert_eq!(restored.forward_into(&[], &mut scratch), expected);
+    }
+}
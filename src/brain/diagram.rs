@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::analysis::svg::escape;
+use crate::brain::BrainSnapshot;
+use crate::genome::{context_size, Activation, Recurrence};
+use crate::neat::NodeKind;
+
+const COLUMN_GAP: f64 = 190.0;
+const ROW_GAP: f64 = 34.0;
+const RADIUS: f64 = 10.0;
+const MARGIN_X: f64 = 130.0;
+const MARGIN_TOP: f64 = 64.0;
+const MARGIN_BOTTOM: f64 = 24.0;
+const POSITIVE: &str = "#1f77b4";
+const NEGATIVE: &str = "#d62728";
+const ACTIVITY: &str = "#ff7f0e";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagramNode {
+    pub label: String,
+    pub kind: NodeKind,
+    pub column: usize,
+    pub bias: Option<f32>,
+    pub activation: Option<Activation>,
+}
+
+/// A weight between two nodes (indices into [`BrainDiagram::nodes`]).
+/// Recurrent edges read the source's value from the previous step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagramEdge {
+    pub from: usize,
+    pub to: usize,
+    pub weight: f32,
+    pub recurrent: bool,
+}
+
+/// The network of an evolved brain laid out in columns, inputs on the left
+/// and outputs on the right, for DOT and SVG export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrainDiagram {
+    pub nodes: Vec<DiagramNode>,
+    pub edges: Vec<DiagramEdge>,
+}
+
+impl BrainDiagram {
+    /// Layered brains give one column per layer and list their neurons layer
+    /// by layer; graph brains keep the genome's node order and place hidden
+    /// nodes by depth. `None` for hand-written brains.
+    pub fn of(brain: &BrainSnapshot, inputs: &[String], outputs: &[String]) -> Option<Self> {
+        let name = |names: &[String], idx: usize, fallback: &str| {
+            names
+                .get(idx)
+                .cloned()
+                .unwrap_or_else(|| format!("{fallback}{idx}"))
+        };
+        match brain {
+            BrainSnapshot::Layered { genome } => {
+                let layers = &genome.layers;
+                let last = layers.len().saturating_sub(1);
+                let ranges = genome.layer_ranges();
+                let row_len = |block: usize| {
+                    layers[block] + 1 + context_size(layers, genome.recurrence, block)
+                };
+                let offsets: Vec<usize> = layers
+                    .iter()
+                    .scan(0, |next, &size| {
+                        let start = *next;
+                        *next += size;
+                        Some(start)
+                    })
+                    .collect();
+
+                let mut nodes = Vec::new();
+                for (layer, &size) in layers.iter().enumerate() {
+                    for neuron in 0..size {
+                        let (label, kind) = if layer == 0 {
+                            (name(inputs, neuron, "in"), NodeKind::Input)
+                        } else if layer == last {
+                            (name(outputs, neuron, "out"), NodeKind::Output)
+                        } else {
+                            (format!("h{layer}.{neuron}"), NodeKind::Hidden)
+                        };
+                        let block = layer.checked_sub(1);
+                        nodes.push(DiagramNode {
+                            label,
+                            kind,
+                            column: layer,
+                            bias: block.map(|block| {
+                                genome.weights[ranges[block].start + neuron * row_len(block)]
+                            }),
+                            activation: block.map(|block| genome.activation(block)),
+                        });
+                    }
+                }
+
+                let mut edges = Vec::new();
+                for block in 0..last {
+                    let context = context_size(layers, genome.recurrence, block);
+                    // Elman context is the target layer's own previous
+                    // values, Jordan context the previous outputs.
+                    let context_start = match genome.recurrence {
+                        Recurrence::Jordan => offsets[last],
+                        _ => offsets[block + 1],
+                    };
+                    for target in 0..layers[block + 1] {
+                        let row = ranges[block].start + target * row_len(block);
+                        let to = offsets[block + 1] + target;
+                        for source in 0..layers[block] {
+                            edges.push(DiagramEdge {
+                                from: offsets[block] + source,
+                                to,
+                                weight: genome.weights[row + 1 + source],
+                                recurrent: false,
+                            });
+                        }
+                        for source in 0..context {
+                            edges.push(DiagramEdge {
+                                from: context_start + source,
+                                to,
+                                weight: genome.weights[row + 1 + layers[block] + source],
+                                recurrent: true,
+                            });
+                        }
+                    }
+                }
+                Some(Self { nodes, edges })
+            }
+            BrainSnapshot::Graph { genome } => {
+                let depths = genome.node_depths();
+                let hidden_column = |id: u32| depths.get(&id).copied().unwrap_or(0).max(1);
+                let output_column = genome
+                    .nodes
+                    .iter()
+                    .filter(|node| node.kind == NodeKind::Hidden)
+                    .map(|node| hidden_column(node.id) + 1)
+                    .max()
+                    .unwrap_or(1);
+                let index: HashMap<u32, usize> = genome
+                    .nodes
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, node)| (node.id, idx))
+                    .collect();
+                let nodes = genome
+                    .nodes
+                    .iter()
+                    .map(|node| {
+                        let id = node.id as usize;
+                        let (label, column) = match node.kind {
+                            NodeKind::Input => (name(inputs, id, "in"), 0),
+                            NodeKind::Output => {
+                                (name(outputs, id - genome.inputs, "out"), output_column)
+                            }
+                            NodeKind::Hidden => (format!("n{id}"), hidden_column(node.id)),
+                        };
+                        let evaluated = node.kind != NodeKind::Input;
+                        DiagramNode {
+                            label,
+                            kind: node.kind,
+                            column,
+                            bias: evaluated.then_some(node.bias),
+                            activation: evaluated.then_some(node.activation),
+                        }
+                    })
+                    .collect();
+                let edges = genome
+                    .connections
+                    .iter()
+                    .filter(|conn| conn.enabled)
+                    .map(|conn| DiagramEdge {
+                        from: index[&conn.from],
+                        to: index[&conn.to],
+                        weight: conn.weight,
+                        recurrent: false,
+                    })
+                    .collect();
+                Some(Self { nodes, edges })
+            }
+            _ => None,
+        }
+    }
+
+    fn max_weight(&self) -> f32 {
+        self.edges
+            .iter()
+            .map(|edge| edge.weight.abs())
+            .filter(|weight| weight.is_finite())
+            .fold(0.0, f32::max)
+    }
+
+    fn tooltip(&self, idx: usize, activity: Option<&[f32]>) -> String {
+        let node = &self.nodes[idx];
+        let mut text = node.label.clone();
+        if let Some(activation) = node.activation {
+            let _ = write!(text, " ({})", activation.name());
+        }
+        if let Some(bias) = node.bias {
+            let _ = write!(text, " bias {bias:.3}");
+        }
+        if let Some(value) = activity.and_then(|activity| activity.get(idx)) {
+            let _ = write!(text, " mean |activation| {value:.3}");
+        }
+        text
+    }
+
+    /// Graphviz source. Edge colour gives the weight's sign, pen width its
+    /// magnitude; recurrent edges are dashed. `activity` (one value per node)
+    /// shades the nodes.
+    pub fn to_dot(&self, activity: Option<&[f32]>) -> String {
+        let max_weight = self.max_weight().max(f32::EPSILON);
+        let max_activity = activity.map(max_finite).unwrap_or(0.0);
+        let mut dot = String::new();
+        let _ = writeln!(dot, "digraph brain {{");
+        let _ = writeln!(dot, "  rankdir=LR;");
+        let _ = writeln!(dot, "  node [shape=circle, label=\"\", width=0.3];");
+        let columns = self.nodes.iter().map(|node| node.column).max().unwrap_or(0);
+        for column in 0..=columns {
+            let _ = writeln!(dot, "  {{ rank=same;");
+            for (idx, node) in self.nodes.iter().enumerate() {
+                if node.column != column {
+                    continue;
+                }
+                let mut attrs = vec![format!(
+                    "tooltip=\"{}\"",
+                    dot_escape(&self.tooltip(idx, activity))
+                )];
+                if node.kind != NodeKind::Hidden {
+                    attrs.push("shape=box".to_string());
+                    attrs.push(format!("label=\"{}\"", dot_escape(&node.label)));
+                }
+                if let Some(value) = activity.and_then(|activity| activity.get(idx)) {
+                    let alpha = shade(*value, max_activity);
+                    attrs.push(format!(
+                        "style=filled, fillcolor=\"{ACTIVITY}{:02x}\"",
+                        (alpha * 255.0).round() as u8
+                    ));
+                }
+                let _ = writeln!(dot, "    n{idx} [{}];", attrs.join(", "));
+            }
+            let _ = writeln!(dot, "  }}");
+        }
+        for edge in &self.edges {
+            let strength = (edge.weight.abs() / max_weight).min(1.0);
+            let colour = if edge.weight < 0.0 {
+                NEGATIVE
+            } else {
+                POSITIVE
+            };
+            let style = if edge.recurrent {
+                ", style=dashed, constraint=false"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                dot,
+                "  n{} -> n{} [color=\"{colour}\", penwidth={:.2}, tooltip=\"{:.3}\"{style}];",
+                edge.from,
+                edge.to,
+                0.3 + 3.7 * strength,
+                edge.weight
+            );
+        }
+        let _ = writeln!(dot, "}}");
+        dot
+    }
+
+    /// Standalone SVG in the same encoding as [`BrainDiagram::to_dot`].
+    pub fn to_svg(&self, title: &str, activity: Option<&[f32]>) -> String {
+        let columns = self.nodes.iter().map(|node| node.column).max().unwrap_or(0) + 1;
+        let mut per_column = vec![0usize; columns];
+        let mut row = vec![0usize; self.nodes.len()];
+        for (idx, node) in self.nodes.iter().enumerate() {
+            row[idx] = per_column[node.column];
+            per_column[node.column] += 1;
+        }
+        let rows = per_column.iter().copied().max().unwrap_or(0).max(1);
+        let width = 2.0 * MARGIN_X + (columns - 1) as f64 * COLUMN_GAP;
+        let height = MARGIN_TOP + rows as f64 * ROW_GAP + MARGIN_BOTTOM;
+        let position = |idx: usize| {
+            let node = &self.nodes[idx];
+            let offset = (rows - per_column[node.column]) as f64 / 2.0;
+            (
+                MARGIN_X + node.column as f64 * COLUMN_GAP,
+                MARGIN_TOP + (offset + row[idx] as f64 + 0.5) * ROW_GAP,
+            )
+        };
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif" font-size="12">"#
+        );
+        let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="22" text-anchor="middle" font-size="15">{}</text>"#,
+            width / 2.0,
+            escape(title)
+        );
+        let legend = if activity.is_some() {
+            "blue positive, red negative, width by |weight|, dashed recurrent; fill by mean |activation|"
+        } else {
+            "blue positive, red negative, width by |weight|, dashed recurrent"
+        };
+        let _ = writeln!(
+            svg,
+            r##"<text x="{}" y="42" text-anchor="middle" fill="#555">{legend}</text>"##,
+            width / 2.0
+        );
+
+        // Weakest first so strong weights are drawn on top.
+        let max_weight = self.max_weight().max(f32::EPSILON);
+        let mut edges: Vec<&DiagramEdge> = self.edges.iter().collect();
+        edges.sort_by(|a, b| a.weight.abs().total_cmp(&b.weight.abs()));
+        for edge in edges {
+            let strength = (edge.weight.abs() / max_weight).min(1.0) as f64;
+            let colour = if edge.weight < 0.0 {
+                NEGATIVE
+            } else {
+                POSITIVE
+            };
+            let (x1, y1) = position(edge.from);
+            let (x2, y2) = position(edge.to);
+            let path = if !edge.recurrent {
+                format!("M{x1:.1},{y1:.1} L{x2:.1},{y2:.1}")
+            } else if edge.from == edge.to {
+                let top = y1 - RADIUS;
+                format!(
+                    "M{:.1},{top:.1} C{:.1},{:.1} {:.1},{:.1} {:.1},{top:.1}",
+                    x1 - 4.0,
+                    x1 - 18.0,
+                    top - 26.0,
+                    x1 + 18.0,
+                    top - 26.0,
+                    x1 + 4.0
+                )
+            } else {
+                // Bend sideways so the edge does not run through the nodes
+                // between its ends.
+                let (mx, my) = ((x1 + x2) / 2.0, (y1 + y2) / 2.0);
+                let (dx, dy) = (x2 - x1, y2 - y1);
+                let len = dx.hypot(dy).max(1.0);
+                let bend = 0.3 * len;
+                format!(
+                    "M{x1:.1},{y1:.1} Q{:.1},{:.1} {x2:.1},{y2:.1}",
+                    mx - dy / len * bend,
+                    my + dx / len * bend
+                )
+            };
+            let dash = if edge.recurrent {
+                r#" stroke-dasharray="4 3""#
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                svg,
+                r#"<path d="{path}" fill="none" stroke="{colour}" stroke-width="{:.2}" stroke-opacity="{:.2}"{dash}><title>{:.3}</title></path>"#,
+                0.3 + 3.7 * strength,
+                0.25 + 0.75 * strength,
+                edge.weight
+            );
+        }
+
+        let max_activity = activity.map(max_finite).unwrap_or(0.0);
+        for (idx, node) in self.nodes.iter().enumerate() {
+            let (x, y) = position(idx);
+            let (fill, opacity) = match activity.and_then(|activity| activity.get(idx)) {
+                Some(&value) => (ACTIVITY, shade(value, max_activity)),
+                None => ("white", 1.0),
+            };
+            let _ = writeln!(
+                svg,
+                r##"<circle cx="{x:.1}" cy="{y:.1}" r="{RADIUS}" fill="{fill}" fill-opacity="{opacity:.2}" stroke="#333"><title>{}</title></circle>"##,
+                escape(&self.tooltip(idx, activity))
+            );
+            let label = match node.kind {
+                NodeKind::Input => Some((x - RADIUS - 6.0, "end")),
+                NodeKind::Output => Some((x + RADIUS + 6.0, "start")),
+                NodeKind::Hidden => None,
+            };
+            if let Some((label_x, anchor)) = label {
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{label_x:.1}" y="{:.1}" text-anchor="{anchor}">{}</text>"#,
+                    y + 4.0,
+                    escape(&node.label)
+                );
+            }
+        }
+        let _ = writeln!(svg, "</svg>");
+        svg
+    }
+}
+
+fn max_finite(values: &[f32]) -> f32 {
+    values
+        .iter()
+        .copied()
+        .filter(|value| value.is_finite())
+        .fold(0.0, f32::max)
+}
+
+/// Fill opacity of a node with `value` when the most active node has `max`.
+fn shade(value: f32, max: f32) -> f64 {
+    if max > 0.0 && value.is_finite() {
+        (value / max).clamp(0.0, 1.0) as f64
+    } else {
+        0.0
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::Genome;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn layered_diagram_maps_every_weight() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let genome = Genome::random_with_recurrence(vec![2, 3, 2], Recurrence::Elman, &mut rng);
+        let brain = BrainSnapshot::Layered {
+            genome: genome.clone(),
+        };
+        let inputs = ["dx".to_string(), "dy".to_string()];
+        let diagram = BrainDiagram::of(&brain, &inputs, &["Up".to_string()]).unwrap();
+
+        assert_eq!(diagram.nodes.len(), 7);
+        assert_eq!(diagram.nodes[1].label, "dy");
+        assert_eq!(diagram.nodes[3].label, "h1.1");
+        assert_eq!(diagram.nodes[5].label, "Up");
+        assert_eq!(diagram.nodes[6].label, "out1");
+        // Every weight but the biases is an edge: 2*3 + 3*3 context + 3*2.
+        assert_eq!(diagram.edges.len(), genome.weights.len() - 5);
+        assert_eq!(diagram.edges.iter().filter(|e| e.recurrent).count(), 9);
+        assert_eq!(diagram.nodes[2].bias, Some(genome.weights[0]));
+        // Second hidden neuron's row: bias, 2 inputs, 3 context values.
+        let edge = &diagram.edges[5];
+        assert_eq!((edge.from, edge.to), (0, 3));
+        assert_eq!(edge.weight, genome.weights[6 + 1]);
+
+        let dot = diagram.to_dot(None);
+        assert!(dot.contains("n3 -> n3"));
+        assert!(dot.contains("label=\"Up\""));
+        let svg = diagram.to_svg("champion", Some(&[0.0; 7]));
+        assert_eq!(svg.matches("<circle").count(), 7);
+        assert!(svg.contains("stroke-dasharray"));
+    }
+
+    #[test]
+    fn graph_diagram_skips_disabled_connections() {
+        let mut rng = StdRng::seed_from_u64(8);
+        let mut genome = crate::neat::GraphGenome::random(2, 1, 1.0, Activation::Tanh, &mut rng);
+        genome.connections[0].enabled = false;
+        let enabled = genome.enabled_connections();
+        let diagram = BrainDiagram::of(&BrainSnapshot::Graph { genome }, &[], &[]).unwrap();
+        assert_eq!(diagram.edges.len(), enabled);
+        assert_eq!(diagram.nodes[2].label, "out0");
+        assert_eq!(diagram.nodes[2].column, 1);
+    }
+}
@@ -1,12 +1,20 @@
 use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
 
+use crate::action::{parse_action_costs, ActionSet, ActionSpace, PolicyMode};
+use crate::baselines::Baseline;
+use crate::evolution::nsga2::Objective;
+use crate::evolution::qd::{BehaviorDescriptor, BehaviorKind, SearchMode, MAX_NICHES};
+use crate::fitness::expr::FitnessExpr;
+use crate::genome::{Activation, Recurrence, WeightBounds};
 use crate::metrics::selection::parse_gen_selection;
+use crate::sensors::{SensorKind, SensorSuite};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
 pub enum SelectionMethod {
     Roulette,
     Tournament,
+    Nsga2,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
@@ -15,11 +23,22 @@ pub enum LoggingMode {
     Quick,
 }
 
+/// Extra streaming outputs written to `runs/<id>/` alongside the defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum SinkKind {
+    /// `metrics.ndjson`: one JSON record per line.
+    Ndjson,
+    /// `metrics.sqlite`; needs the `sqlite` build feature.
+    Sqlite,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
 pub enum FitnessMode {
     Classic,
     #[value(name = "efficient")]
     EfficientCollector,
+    #[value(name = "expr")]
+    Expression,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
@@ -28,6 +47,32 @@ pub enum BrainMode {
     Evolvable,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum Controller {
+    Evolved,
+    RandomWalker,
+    GreedySeeker,
+    ShortestPath,
+}
+
+impl Controller {
+    /// The hand-written baseline this controller stands for, if any.
+    pub fn baseline(self) -> Option<Baseline> {
+        match self {
+            Controller::Evolved => None,
+            Controller::RandomWalker => Some(Baseline::Random),
+            Controller::GreedySeeker => Some(Baseline::Greedy),
+            Controller::ShortestPath => Some(Baseline::ShortestPath),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum Encoding {
+    Layered,
+    Graph,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
 pub enum CrossoverMode {
     None,
@@ -41,13 +86,41 @@ pub enum ArchInherit {
     Random,
 }
 
+/// How creatures come and go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum EvolutionMode {
+    /// Whole populations play an episode each, then are replaced.
+    Generational,
+    /// One world that never resets: creatures are born when they have
+    /// energy to spare and die of starvation or age.
+    OpenEnded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum Reproduction {
+    Asexual,
+    /// With a ready mate within `--mate-radius`.
+    Sexual,
+}
+
+/// Where each island's migrants go under `--islands`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum Topology {
+    /// To the next island, the last one sending to the first.
+    Ring,
+    /// To every other island.
+    Full,
+    /// To one other island, drawn anew at every migration.
+    Random,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
 pub enum DistanceMetric {
     Euclidean,
     Manhattan,
 }
 
-#[derive(Parser, Debug, Clone, Serialize)]
+#[derive(Parser, Debug, Clone, Serialize, Deserialize)]
 #[command(author, version, about = "Headless evolutionary simulation")]
 pub struct Config {
     #[arg(long, default_value_t = 100)]
@@ -70,24 +143,92 @@ pub struct Config {
     pub food_energy: f32,
     #[arg(long, default_value_t = 42)]
     pub seed: u64,
-    #[arg(long, default_value_t = 4)]
-    pub input: usize,
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [SensorKind::Food, SensorKind::Energy, SensorKind::Noise]
+    )]
+    pub sensors: Vec<SensorKind>,
+    #[arg(long, default_value_t = 8)]
+    pub ray_count: u32,
+    #[arg(long, default_value_t = 10)]
+    pub ray_range: u32,
+    #[arg(long, default_value_t = 3)]
+    pub sense_foods: u32,
+    #[arg(long, default_value_t = 5)]
+    pub creature_radius: u32,
     #[arg(long, default_value_t = 8)]
     pub hidden: usize,
-    #[arg(long, default_value_t = 4)]
-    pub output: usize,
+    #[arg(long = "actions", value_enum, default_value_t = ActionSet::Four)]
+    pub action_set: ActionSet,
+    #[arg(long, default_value_t = false)]
+    pub allow_stay: bool,
+    #[arg(long, default_value = "")]
+    pub action_costs: String,
+    #[arg(long, value_enum, default_value_t = PolicyMode::Argmax)]
+    pub policy: PolicyMode,
+    #[arg(long, default_value_t = 1.0)]
+    pub temperature: f32,
+    #[arg(long, default_value_t = 0.1)]
+    pub epsilon: f32,
+    #[arg(long, default_value_t = 0.5)]
+    pub action_threshold: f32,
+    /// Sense for every creature at the start of a step, then evaluate the
+    /// brains that share an architecture as one batch. Creatures no longer
+    /// see the moves of those before them in the same step.
+    #[arg(long, default_value_t = false)]
+    pub batch_forward: bool,
     #[arg(long = "selection", value_enum, default_value_t = SelectionMethod::Roulette)]
     pub selection_method: SelectionMethod,
     #[arg(long, default_value_t = 5)]
     pub tournament_k: u32,
+    /// Objectives for `--selection nsga2`; a leading `-` minimises.
+    #[arg(
+        long,
+        value_parser = Objective::parse,
+        value_delimiter = ',',
+        allow_hyphen_values = true,
+        default_value = "food,efficiency,survival,-idle,-jitter"
+    )]
+    pub objectives: Vec<Objective>,
+    /// Hypervolume reference point, one value per objective in the counters'
+    /// own units. Defaults to zero for maximised objectives and the first
+    /// generation's worst value for minimised ones; it stays fixed for the
+    /// whole run either way.
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+    pub hv_reference: Vec<f32>,
+    /// Selection pressure: fitness, behavioural novelty, or a MAP-Elites grid.
+    #[arg(long, value_enum, default_value_t = SearchMode::Fitness)]
+    pub search: SearchMode,
+    /// Behaviour characterization for `--search novelty` and `map-elites`.
+    #[arg(long, value_enum, default_value_t = BehaviorKind::FinalPosition)]
+    pub behavior: BehaviorKind,
+    #[arg(long, default_value_t = 15)]
+    pub novelty_k: u32,
+    #[arg(long, default_value_t = 5)]
+    pub archive_add: u32,
+    #[arg(long, default_value_t = 2000)]
+    pub archive_max: u32,
+    #[arg(long, default_value_t = 8)]
+    pub map_bins: u32,
     #[arg(long, default_value_t = 0.1)]
     pub elite: f32,
     #[arg(long = "mut-rate", default_value_t = 0.05)]
     pub mut_rate: f32,
     #[arg(long = "mut-strength", default_value_t = 0.2)]
     pub mut_strength: f32,
+    /// Fraction every weight shrinks towards zero per mutation (L2 decay).
+    #[arg(long, default_value_t = 0.0)]
+    pub weight_decay: f32,
+    /// Clamp mutated weights to `[-weight-clamp, weight-clamp]`.
+    #[arg(long)]
+    pub weight_clamp: Option<f32>,
     #[arg(long = "fitness", value_enum, default_value_t = FitnessMode::Classic)]
     pub fitness_mode: FitnessMode,
+    /// Fitness formula for `--fitness expr`, e.g. `food*2 + log(survival) - 0.1*jitter`.
+    #[arg(long, value_parser = FitnessExpr::parse)]
+    pub fitness_expr: Option<FitnessExpr>,
     #[arg(long, default_value_t = 1.0)]
     pub fitness_food_weight: f32,
     #[arg(long, default_value_t = 1.0)]
@@ -110,6 +251,10 @@ pub struct Config {
     pub full_log_keep: String,
     #[arg(long = "run-id")]
     pub run_id: Option<String>,
+    /// Streams every generation, the lineage and the event log to these
+    /// outputs, regardless of `--log-gens`.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub sinks: Vec<SinkKind>,
     #[arg(long, default_value_t = 2)]
     pub quick_keep: u32,
     #[arg(long, default_value_t = 0)]
@@ -118,6 +263,30 @@ pub struct Config {
     pub distance_metric: DistanceMetric,
     #[arg(long = "brain", value_enum, default_value_t = BrainMode::Fixed)]
     pub brain_mode: BrainMode,
+    #[arg(long, value_enum, default_value_t = Recurrence::None)]
+    pub recurrence: Recurrence,
+    #[arg(long, value_enum, default_value_t = Controller::Evolved)]
+    pub controller: Controller,
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub baselines: Vec<Baseline>,
+    #[arg(long, value_enum, default_value_t = Encoding::Layered)]
+    pub encoding: Encoding,
+    #[arg(long, default_value_t = 1.0)]
+    pub graph_init_density: f32,
+    #[arg(long, default_value_t = 0.05)]
+    pub add_connection_rate: f32,
+    #[arg(long, default_value_t = 0.03)]
+    pub add_node_rate: f32,
+    #[arg(long, default_value_t = 0.01)]
+    pub toggle_rate: f32,
+    #[arg(long, value_enum, default_value_t = Activation::Tanh)]
+    pub hidden_activation: Activation,
+    #[arg(long, value_enum, default_value_t = Activation::Tanh)]
+    pub output_activation: Activation,
+    #[arg(long, default_value_t = 0.0)]
+    pub activation_mut_rate: f32,
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = Activation::ALL)]
+    pub activation_pool: Vec<Activation>,
     #[arg(long, default_value_t = 4)]
     pub max_hidden_layers: u32,
     #[arg(long, default_value_t = 4)]
@@ -140,15 +309,161 @@ pub struct Config {
     pub frames_dir: String,
     #[arg(long, default_value_t = 10)]
     pub progress: usize,
+    /// Abort with a checkpoint as soon as a fitness or weight is NaN or infinite.
+    #[arg(long, default_value_t = false)]
+    pub fail_fast: bool,
+    /// Replace console output with a live terminal dashboard.
+    #[arg(long, default_value_t = false)]
+    pub tui: bool,
+    /// Serve a read-only monitoring page and JSON API on this address, e.g.
+    /// `127.0.0.1:8080`.
+    #[arg(long)]
+    pub serve: Option<String>,
+    /// Generations (as for `--log-gens`) whose every step `--serve` offers
+    /// as frames.
+    #[arg(long)]
+    pub serve_frames: Option<String>,
+    /// Generations (as for `--log-gens`) whose best brain is saved as
+    /// `best_gen_<gen>.json` in the run directory.
+    #[arg(long)]
+    pub save_best: Option<String>,
+    /// Sub-populations of `--population` creatures each, evaluated in
+    /// worlds of their own and bred apart.
+    #[arg(long, default_value_t = 1)]
+    pub islands: usize,
+    /// Generations between two migrations when `--islands` is above 1.
+    #[arg(long, default_value_t = 10)]
+    pub migration_interval: usize,
+    /// Best creatures of each island copied to its destinations per migration.
+    #[arg(long, default_value_t = 2)]
+    pub migrants: usize,
+    #[arg(long, value_enum, default_value_t = Topology::Ring)]
+    pub topology: Topology,
+    /// Settings of one island, e.g. `1:mut-rate=0.2,food=50`; repeatable.
+    #[arg(long = "island-config")]
+    pub island_config: Vec<String>,
+    /// Under `open-ended`, each of the `--generations` is a window of
+    /// `--sample-every` steps, reported like a generation.
+    #[arg(long, value_enum, default_value_t = EvolutionMode::Generational)]
+    pub mode: EvolutionMode,
+    #[arg(long, value_enum, default_value_t = Reproduction::Asexual)]
+    pub reproduction: Reproduction,
+    /// Energy at which a creature reproduces under `--mode open-ended`.
+    #[arg(long, default_value_t = 80.0)]
+    pub birth_energy: f32,
+    /// Energy a birth takes from the parents, split between two under sexual
+    /// reproduction, and gives to the child.
+    #[arg(long, default_value_t = 40.0)]
+    pub birth_cost: f32,
+    /// Largest distance, in cells along either axis, between two mates.
+    #[arg(long, default_value_t = 3)]
+    pub mate_radius: usize,
+    /// Age in steps at which a creature dies under `--mode open-ended`.
+    #[arg(long, default_value_t = 1500)]
+    pub max_age: u32,
+    /// Random newcomers keep the population at this size or above.
+    #[arg(long, default_value_t = 20)]
+    pub min_population: usize,
+    /// No creature is born while the population is this large.
+    #[arg(long, default_value_t = 400)]
+    pub max_population: usize,
+    #[arg(long, default_value_t = 100)]
+    pub sample_every: usize,
 }
 
 impl Config {
+    /// Rebuilds a run's config from the `config` object of its
+    /// `manifest.json`. Fields missing from older manifests take their
+    /// defaults.
+    pub fn from_manifest(saved: &serde_json::Value) -> Result<Self, String> {
+        let defaults = Config::try_parse_from(["evobrain"]).map_err(|err| err.to_string())?;
+        let mut merged = serde_json::to_value(defaults).map_err(|err| err.to_string())?;
+        if let (Some(merged), Some(saved)) = (merged.as_object_mut(), saved.as_object()) {
+            for (name, value) in saved {
+                merged.insert(name.clone(), value.clone());
+            }
+        }
+        serde_json::from_value(merged).map_err(|err| format!("invalid manifest config: {err}"))
+    }
+
     pub fn nn_sizes(&self) -> (usize, usize, usize) {
-        (self.input, self.hidden, self.output)
+        (self.input_size(), self.hidden, self.output_size())
+    }
+
+    pub fn input_size(&self) -> usize {
+        SensorSuite::from_config(self).input_size()
+    }
+
+    pub fn output_size(&self) -> usize {
+        ActionSpace::from_config(self).len()
     }
 
     pub fn base_layers(&self) -> Vec<usize> {
-        vec![self.input, self.hidden, self.output]
+        vec![self.input_size(), self.hidden, self.output_size()]
+    }
+
+    pub fn weight_bounds(&self) -> WeightBounds {
+        WeightBounds {
+            decay: self.weight_decay,
+            clamp: self.weight_clamp,
+        }
+    }
+
+    /// One config per island: this one with the island's `--island-config`
+    /// settings applied.
+    pub fn island_configs(&self) -> Result<Vec<Config>, String> {
+        let mut base = self.clone();
+        base.islands = 1;
+        base.island_config.clear();
+        let mut configs = vec![base; self.islands.max(1)];
+        for spec in &self.island_config {
+            let (island, settings) = spec
+                .split_once(':')
+                .ok_or_else(|| format!("'{spec}' should look like ISLAND:KEY=VALUE,..."))?;
+            let index: usize = island
+                .trim()
+                .parse()
+                .map_err(|_| format!("'{island}' is not an island index"))?;
+            let count = configs.len();
+            let config = configs
+                .get_mut(index)
+                .ok_or_else(|| format!("island {index} does not exist ({count} islands)"))?;
+            for setting in settings.split(',') {
+                let (key, value) = setting
+                    .split_once('=')
+                    .ok_or_else(|| format!("'{setting}' should look like KEY=VALUE"))?;
+                config.set_island_option(key.trim(), value.trim())?;
+            }
+        }
+        for (index, config) in configs.iter().enumerate() {
+            config
+                .validate()
+                .map_err(|err| format!("island {index}: {err}"))?;
+        }
+        Ok(configs)
+    }
+
+    fn set_island_option(&mut self, key: &str, value: &str) -> Result<(), String> {
+        fn number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+            value
+                .parse()
+                .map_err(|_| format!("invalid value '{value}' for {key}"))
+        }
+        match key {
+            "population" => self.population = number(key, value)?,
+            "food" => self.food = number(key, value)?,
+            "food-energy" => self.food_energy = number(key, value)?,
+            "max-steps" => self.max_steps = number(key, value)?,
+            "elite" => self.elite = number(key, value)?,
+            "mut-rate" => self.mut_rate = number(key, value)?,
+            "mut-strength" => self.mut_strength = number(key, value)?,
+            "weight-decay" => self.weight_decay = number(key, value)?,
+            "tournament-k" => self.tournament_k = number(key, value)?,
+            "selection" => self.selection_method = SelectionMethod::from_str(value, true)?,
+            "crossover" => self.crossover_mode = CrossoverMode::from_str(value, true)?,
+            _ => return Err(format!("'{key}' cannot be set per island")),
+        }
+        Ok(())
     }
 
     pub fn validate(&self) -> Result<(), String> {
@@ -160,14 +475,120 @@ impl Config {
         if matches!(self.logging_mode, LoggingMode::Quick) && !matches!(self.quick_keep, 2 | 3) {
             return Err("quick-keep must be 2 or 3 when logging mode is quick".to_string());
         }
+        if matches!(self.selection_method, SelectionMethod::Nsga2) {
+            if self.objectives.is_empty() {
+                return Err("objectives must not be empty for nsga2 selection".to_string());
+            }
+            if self.tournament_k < 2 {
+                return Err("tournament-k must be at least 2 for nsga2 selection".to_string());
+            }
+            if !self.hv_reference.is_empty() && self.hv_reference.len() != self.objectives.len() {
+                return Err(format!(
+                    "hv-reference has {} values but there are {} objectives",
+                    self.hv_reference.len(),
+                    self.objectives.len()
+                ));
+            }
+        }
+        if self.search != SearchMode::Fitness {
+            if matches!(self.selection_method, SelectionMethod::Nsga2) {
+                return Err(
+                    "nsga2 selection cannot be combined with novelty or map-elites search"
+                        .to_string(),
+                );
+            }
+            if self.novelty_k == 0 {
+                return Err("novelty-k must be at least 1".to_string());
+            }
+            if self.map_bins == 0 {
+                return Err("map-bins must be at least 1".to_string());
+            }
+        }
+        if self.search == SearchMode::MapElites {
+            let dims = BehaviorDescriptor::from_config(self).dims();
+            let niches = (self.map_bins as f64).powi(dims as i32);
+            if niches > MAX_NICHES {
+                let behavior = self
+                    .behavior
+                    .to_possible_value()
+                    .map(|value| value.get_name().to_string())
+                    .unwrap_or_default();
+                return Err(format!(
+                    "map-bins {} makes {}^{dims} niches for the {behavior} behavior; at most {MAX_NICHES} are allowed",
+                    self.map_bins, self.map_bins
+                ));
+            }
+        }
+        if matches!(self.fitness_mode, FitnessMode::Expression) && self.fitness_expr.is_none() {
+            return Err("fitness-expr is required when fitness mode is expr".to_string());
+        }
         if self.layer_min_neurons == 0 || self.layer_max_neurons == 0 {
             return Err("layer-min-neurons and layer-max-neurons must be positive".to_string());
         }
         if self.layer_min_neurons > self.layer_max_neurons {
             return Err("layer-min-neurons cannot exceed layer-max-neurons".to_string());
         }
-        if self.input == 0 || self.output == 0 {
-            return Err("input and output layer sizes must be positive".to_string());
+        if self.sensors.is_empty() {
+            return Err("at least one sensor must be enabled".to_string());
+        }
+        for (idx, sensor) in self.sensors.iter().enumerate() {
+            if self.sensors[..idx].contains(sensor) {
+                return Err(format!(
+                    "sensor '{}' is listed more than once",
+                    sensor.name()
+                ));
+            }
+        }
+        if self.sensors.contains(&SensorKind::Rays) && self.ray_count == 0 {
+            return Err("ray-count must be positive when the rays sensor is enabled".to_string());
+        }
+        if self.sensors.contains(&SensorKind::NearestFoods) && self.sense_foods == 0 {
+            return Err(
+                "sense-foods must be positive when the nearest-foods sensor is enabled".to_string(),
+            );
+        }
+        if matches!(self.policy, PolicyMode::Softmax)
+            && (!self.temperature.is_finite() || self.temperature <= 0.0)
+        {
+            return Err("temperature must be positive when the softmax policy is used".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.epsilon) {
+            return Err("epsilon must be between 0 and 1".to_string());
+        }
+        if let Err(err) = parse_action_costs(&self.action_costs) {
+            return Err(format!("invalid action-costs spec: {err}"));
+        }
+        if !(0.0..1.0).contains(&self.weight_decay) {
+            return Err("weight-decay must be at least 0 and below 1".to_string());
+        }
+        if self
+            .weight_clamp
+            .is_some_and(|clamp| !clamp.is_finite() || clamp <= 0.0)
+        {
+            return Err("weight-clamp must be a positive number".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.activation_mut_rate) {
+            return Err("activation-mut-rate must be between 0 and 1".to_string());
+        }
+        for (name, rate) in [
+            ("graph-init-density", self.graph_init_density),
+            ("add-connection-rate", self.add_connection_rate),
+            ("add-node-rate", self.add_node_rate),
+            ("toggle-rate", self.toggle_rate),
+        ] {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(format!("{name} must be between 0 and 1"));
+            }
+        }
+        if self.controller == Controller::GreedySeeker && !self.sensors.contains(&SensorKind::Food)
+        {
+            return Err("the greedy-seeker controller needs the food sensor".to_string());
+        }
+        if self.encoding == Encoding::Graph && self.recurrence != Recurrence::None {
+            return Err("graph encoding does not support recurrence".to_string());
+        }
+        if self.activation_mut_rate > 0.0 && self.activation_pool.is_empty() {
+            return Err("activation-pool must not be empty when activations mutate".to_string());
         }
         if self.max_hidden_layers == 0 {
             return Err("max-hidden-layers must be at least 1".to_string());
@@ -183,6 +604,79 @@ impl Config {
         if let Err(err) = parse_full_log_keep(&self.full_log_keep) {
             return Err(format!("invalid full-log-keep value: {err}"));
         }
+        if let Some(ref spec) = self.serve_frames {
+            if self.serve.is_none() {
+                return Err("serve-frames needs --serve".to_string());
+            }
+            if let Err(err) = parse_gen_selection(spec) {
+                return Err(format!("invalid serve-frames spec: {err}"));
+            }
+        }
+        if let Some(ref spec) = self.save_best {
+            if let Err(err) = parse_gen_selection(spec) {
+                return Err(format!("invalid save-best spec: {err}"));
+            }
+        }
+        if self.islands == 0 {
+            return Err("islands must be at least 1".to_string());
+        }
+        if self.islands > 1 {
+            if self.search != SearchMode::Fitness {
+                return Err(
+                    "islands cannot be combined with novelty or map-elites search".to_string(),
+                );
+            }
+            if self.migration_interval == 0 {
+                return Err("migration-interval must be at least 1".to_string());
+            }
+        }
+        if self.mode == EvolutionMode::OpenEnded {
+            if self.islands > 1 {
+                return Err("islands need generational mode".to_string());
+            }
+            if self.search != SearchMode::Fitness {
+                return Err("novelty and map-elites search need generational mode".to_string());
+            }
+            if !self.baselines.is_empty() {
+                return Err("baselines need generational mode".to_string());
+            }
+            if self.batch_forward {
+                return Err("batch-forward needs generational mode".to_string());
+            }
+            if !self.birth_cost.is_finite() || self.birth_cost <= 0.0 {
+                return Err("birth-cost must be positive".to_string());
+            }
+            if self.birth_cost >= self.birth_energy {
+                return Err("birth-cost must be below birth-energy".to_string());
+            }
+            if self.birth_energy > self.max_energy {
+                return Err("birth-energy cannot exceed max-energy".to_string());
+            }
+            if self.max_age == 0 {
+                return Err("max-age must be at least 1".to_string());
+            }
+            if self.max_population == 0 || self.min_population > self.max_population {
+                return Err(
+                    "max-population must be positive and at least min-population".to_string(),
+                );
+            }
+            if self.sample_every == 0 {
+                return Err("sample-every must be at least 1".to_string());
+            }
+            if self.reproduction == Reproduction::Sexual
+                && matches!(self.crossover_mode, CrossoverMode::None)
+            {
+                return Err("sexual reproduction needs --crossover layer or blend".to_string());
+            }
+        }
+        if !self.island_config.is_empty() {
+            if let Err(err) = self.island_configs() {
+                return Err(format!("invalid island-config spec: {err}"));
+            }
+        }
+        if self.sinks.contains(&SinkKind::Sqlite) && !cfg!(feature = "sqlite") {
+            return Err("the sqlite sink needs a build with --features sqlite".to_string());
+        }
         Ok(())
     }
 }
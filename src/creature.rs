@@ -1,34 +1,50 @@
 use rand::Rng;
 
-use crate::neural_net::NeuralNet;
+use crate::action::{Action, ActionPolicy, ActionSpace, Heading};
+use crate::brain::Brain;
+use crate::neural_net::ForwardScratch;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Action {
-    Up,
-    Down,
-    Left,
-    Right,
-}
+/// Side length of the coarse grid `visit_counts` bins positions into.
+pub const VISIT_GRID: usize = 4;
 
 #[derive(Debug, Clone)]
 pub struct Creature {
+    /// Unique within a run; assigned by `EvolutionManager`.
+    pub id: u64,
+    /// Ids of the two parents, equal without crossover; `None` in the first
+    /// generation.
+    pub parents: Option<(u64, u64)>,
     pub x: usize,
     pub y: usize,
     pub energy: f32,
     pub age: u32,
     pub alive: bool,
-    pub brain: NeuralNet,
+    pub brain: Box<dyn Brain>,
     pub food_collected: u32,
     pub energy_spent: f32,
     pub survival_steps: u32,
     pub idle_steps: u32,
     pub jitter_score: u32,
+    /// Times each action was taken, indexed by `Action as usize`.
+    pub action_counts: [u32; Action::ALL.len()],
+    /// Steps spent in each cell of a `VISIT_GRID`×`VISIT_GRID` partition of
+    /// the world, row-major.
+    pub visit_counts: [u32; VISIT_GRID * VISIT_GRID],
+    pub heading: Heading,
+    /// Fitness of the last finished episode; see `fitness::evaluate_population`.
+    pub fitness: Option<f32>,
     last_action: Option<Action>,
 }
 
 impl Creature {
-    pub fn from_brain(brain: NeuralNet, x: usize, y: usize, energy: f32) -> Self {
+    pub fn from_brain(brain: impl Brain + 'static, x: usize, y: usize, energy: f32) -> Self {
+        Self::from_boxed(Box::new(brain), x, y, energy)
+    }
+
+    pub fn from_boxed(brain: Box<dyn Brain>, x: usize, y: usize, energy: f32) -> Self {
         Self {
+            id: 0,
+            parents: None,
             x,
             y,
             energy,
@@ -40,84 +56,97 @@ impl Creature {
             survival_steps: 0,
             idle_steps: 0,
             jitter_score: 0,
+            action_counts: [0; Action::ALL.len()],
+            visit_counts: [0; VISIT_GRID * VISIT_GRID],
+            heading: Heading::default(),
+            fitness: None,
             last_action: None,
         }
     }
 
-    pub fn perceive(&self, dx: f32, dy: f32, max_energy: f32, rng: &mut impl Rng) -> Vec<f32> {
-        let energy_norm = if max_energy > 0.0 {
-            (self.energy / max_energy).clamp(0.0, 1.0)
-        } else {
-            0.0
-        };
-        let noise = rng.gen_range(-1.0..=1.0);
-        vec![dx, dy, energy_norm, noise]
-    }
-
-    pub fn decide(&self, inputs: &[f32]) -> Action {
-        let outputs = self.brain.forward(inputs);
-        let mut best_idx = 0;
-        let mut best_val = outputs[0];
-        for (idx, &val) in outputs.iter().enumerate().skip(1) {
-            if val > best_val {
-                best_val = val;
-                best_idx = idx;
-            }
-        }
-        match best_idx {
-            0 => Action::Up,
-            1 => Action::Down,
-            2 => Action::Left,
-            _ => Action::Right,
-        }
+    pub fn decide(
+        &mut self,
+        inputs: &[f32],
+        actions: &ActionSpace,
+        policy: &ActionPolicy,
+        rng: &mut impl Rng,
+        scratch: &mut ForwardScratch,
+        chosen: &mut Vec<Action>,
+    ) {
+        let outputs = self.brain.forward_into(inputs, scratch);
+        actions.choose(outputs, policy, rng, chosen);
     }
 
-    pub fn act(&mut self, action: Action, world_w: usize, world_h: usize, move_cost: f32) {
+    /// Performs the actions chosen for one step in order. Each one is paid for
+    /// and counts towards jitter; age and idleness are tracked per step.
+    pub fn act(
+        &mut self,
+        chosen: &[Action],
+        world_w: usize,
+        world_h: usize,
+        actions: &ActionSpace,
+    ) {
         if !self.alive {
             return;
         }
         let start_x = self.x;
         let start_y = self.y;
-        if let Some(last) = self.last_action {
-            if last != action {
-                self.jitter_score = self.jitter_score.saturating_add(1);
-            }
-        }
-        self.last_action = Some(action);
-        match action {
-            Action::Up => {
-                if self.y > 0 {
-                    self.y -= 1;
+        for &action in chosen {
+            if let Some(last) = self.last_action {
+                if last != action {
+                    self.jitter_score = self.jitter_score.saturating_add(1);
                 }
             }
-            Action::Down => {
-                if self.y + 1 < world_h {
-                    self.y += 1;
+            self.last_action = Some(action);
+            self.action_counts[action as usize] += 1;
+            match action {
+                Action::TurnLeft => self.heading = self.heading.turn_left(),
+                Action::TurnRight => self.heading = self.heading.turn_right(),
+                Action::Forward => {
+                    let (dx, dy) = self.heading.offset();
+                    self.move_by(dx, dy, world_w, world_h);
                 }
-            }
-            Action::Left => {
-                if self.x > 0 {
-                    self.x -= 1;
-                }
-            }
-            Action::Right => {
-                if self.x + 1 < world_w {
-                    self.x += 1;
+                _ => {
+                    let (dx, dy) = action.offset().unwrap_or((0, 0));
+                    self.move_by(dx, dy, world_w, world_h);
                 }
             }
+            let cost = actions.cost(action);
+            self.energy -= cost;
+            self.energy_spent += cost;
         }
-        self.energy -= move_cost;
-        self.energy_spent += move_cost;
         self.age += 1;
         self.survival_steps += 1;
         if self.x == start_x && self.y == start_y {
             self.idle_steps = self.idle_steps.saturating_add(1);
         }
+        let cell_x = self.x * VISIT_GRID / world_w.max(1);
+        let cell_y = self.y * VISIT_GRID / world_h.max(1);
+        self.visit_counts[cell_y * VISIT_GRID + cell_x] += 1;
         if self.energy <= 0.0 {
             self.alive = false;
         }
     }
 
+    /// Moves by the given offset, clamping each axis to the world separately so
+    /// a diagonal move into a wall slides along it.
+    fn move_by(&mut self, dx: i32, dy: i32, world_w: usize, world_h: usize) {
+        if dx < 0 && self.x > 0 {
+            self.x -= 1;
+        } else if dx > 0 && self.x + 1 < world_w {
+            self.x += 1;
+        }
+        if dy < 0 && self.y > 0 {
+            self.y -= 1;
+        } else if dy > 0 && self.y + 1 < world_h {
+            self.y += 1;
+        }
+    }
+
+    pub fn last_action(&self) -> Option<Action> {
+        self.last_action
+    }
+
     pub fn fitness_classic(&self) -> f32 {
         self.age as f32 + self.energy.max(0.0)
     }
@@ -128,6 +157,9 @@ impl Creature {
         self.survival_steps = 0;
         self.idle_steps = 0;
         self.jitter_score = 0;
+        self.action_counts = [0; Action::ALL.len()];
+        self.visit_counts = [0; VISIT_GRID * VISIT_GRID];
+        self.fitness = None;
         self.last_action = None;
     }
 }
@@ -0,0 +1,194 @@
+use std::error::Error;
+use std::fs::{create_dir_all, write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use clap::Parser;
+
+use crate::ablation::Evaluator;
+use crate::action::ActionSpace;
+use crate::analysis::load::load_champion;
+use crate::brain::diagram::BrainDiagram;
+use crate::brain::{Brain, BrainSnapshot};
+use crate::genome::Activation;
+use crate::neat::GraphNet;
+use crate::neural_net::{ForwardScratch, NeuralNet};
+use crate::sensors::SensorSuite;
+
+/// `evobrain draw`: exports a saved brain as `brain.dot` and `brain.svg`,
+/// optionally shaded by how active each neuron was in a replayed episode.
+#[derive(Parser, Debug, Clone)]
+#[command(name = "evobrain draw")]
+pub struct DrawArgs {
+    /// Run directory containing `manifest.json` and `champion.json`.
+    pub run: PathBuf,
+    /// Brain to draw instead of `<run>/champion.json`.
+    #[arg(long)]
+    pub champion: Option<PathBuf>,
+    /// Where the diagrams go. Defaults to the run directory.
+    #[arg(long)]
+    pub out_dir: Option<PathBuf>,
+    /// Replay one episode and shade every neuron by its mean absolute value.
+    #[arg(long)]
+    pub trace: bool,
+    /// Seed of the replayed episode. Defaults to the run's seed.
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Copies of the brain in the replayed episode. Defaults to the run's
+    /// population size.
+    #[arg(long)]
+    pub copies: Option<usize>,
+}
+
+/// Summed absolute value of every diagram node over all traced steps.
+#[derive(Debug, Default)]
+struct Activity {
+    sums: Vec<f64>,
+    steps: u64,
+}
+
+impl Activity {
+    fn add(&mut self, values: &[f32]) {
+        if self.sums.len() < values.len() {
+            self.sums.resize(values.len(), 0.0);
+        }
+        for (sum, value) in self.sums.iter_mut().zip(values) {
+            *sum += value.abs() as f64;
+        }
+        self.steps += 1;
+    }
+
+    fn means(&self) -> Vec<f32> {
+        let steps = self.steps.max(1) as f64;
+        self.sums.iter().map(|sum| (sum / steps) as f32).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum TracedNet {
+    Layered(NeuralNet),
+    Graph(GraphNet),
+}
+
+impl TracedNet {
+    fn brain(&self) -> &dyn Brain {
+        match self {
+            TracedNet::Layered(net) => net,
+            TracedNet::Graph(net) => net,
+        }
+    }
+}
+
+/// Evaluates a layered or graph net and adds the value of every node, in
+/// [`BrainDiagram`] order, to a shared [`Activity`].
+#[derive(Debug, Clone)]
+struct Tracer {
+    net: TracedNet,
+    activity: Arc<Mutex<Activity>>,
+    trace: Vec<f32>,
+}
+
+impl Tracer {
+    fn new(brain: &BrainSnapshot, activity: Arc<Mutex<Activity>>) -> Option<Self> {
+        let net = match brain.clone() {
+            BrainSnapshot::Layered { genome } => TracedNet::Layered(NeuralNet::new(genome)),
+            BrainSnapshot::Graph { genome } => TracedNet::Graph(GraphNet::new(genome)),
+            _ => return None,
+        };
+        Some(Self {
+            net,
+            activity,
+            trace: Vec::new(),
+        })
+    }
+}
+
+impl Brain for Tracer {
+    fn forward_into<'s>(&mut self, inputs: &[f32], scratch: &'s mut ForwardScratch) -> &'s [f32] {
+        let mut activity = self.activity.lock().unwrap_or_else(PoisonError::into_inner);
+        match &mut self.net {
+            TracedNet::Layered(net) => {
+                self.trace.clear();
+                let outputs = net.forward_traced(inputs, scratch, &mut self.trace);
+                activity.add(&self.trace);
+                outputs
+            }
+            TracedNet::Graph(net) => {
+                net.forward_into(inputs, scratch);
+                // Node values stay behind in the scratch, one slot per node.
+                activity.add(&scratch.prev);
+                &scratch.next
+            }
+        }
+    }
+
+    fn reset_state(&mut self) {
+        if let TracedNet::Layered(net) = &mut self.net {
+            net.reset_state();
+        }
+    }
+
+    fn param_count(&self) -> u32 {
+        self.net.brain().param_count()
+    }
+
+    fn layer_count(&self) -> u32 {
+        self.net.brain().layer_count()
+    }
+
+    fn hidden_count(&self) -> u32 {
+        self.net.brain().hidden_count()
+    }
+
+    fn hidden_activations(&self) -> Vec<Activation> {
+        self.net.brain().hidden_activations()
+    }
+
+    fn snapshot(&self) -> BrainSnapshot {
+        self.net.brain().snapshot()
+    }
+
+    fn box_clone(&self) -> Box<dyn Brain> {
+        Box::new(self.clone())
+    }
+}
+
+pub fn run_draw(args: &DrawArgs) -> Result<(), Box<dyn Error>> {
+    let (config, brain) = load_champion(&args.run, args.champion.as_deref())?;
+    let inputs = SensorSuite::from_config(&config).input_names();
+    let outputs = ActionSpace::from_config(&config).names();
+    let diagram = BrainDiagram::of(&brain, &inputs, &outputs)
+        .ok_or("only evolved brains (layered or graph) can be drawn")?;
+
+    let activity = if args.trace {
+        let shared = Arc::new(Mutex::new(Activity::default()));
+        let tracer = Tracer::new(&brain, shared.clone()).ok_or("brain cannot be traced")?;
+        let copies = args.copies.unwrap_or(config.population).max(1);
+        let seed = args.seed.unwrap_or(config.seed);
+        let fitness = Evaluator::new(&config, 1, copies, seed).fitness(|_| tracer.box_clone());
+        println!(
+            "Replayed episode with seed {seed}: mean fitness {:.3}",
+            fitness[0]
+        );
+        let means = shared
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .means();
+        Some(means)
+    } else {
+        None
+    };
+
+    let out_dir = args.out_dir.clone().unwrap_or_else(|| args.run.clone());
+    create_dir_all(&out_dir)?;
+    let title = match &args.champion {
+        Some(path) => path.display().to_string(),
+        None => format!("champion of {}", args.run.display()),
+    };
+    let dot_path = out_dir.join("brain.dot");
+    let svg_path = out_dir.join("brain.svg");
+    write(&dot_path, diagram.to_dot(activity.as_deref()))?;
+    write(&svg_path, diagram.to_svg(&title, activity.as_deref()))?;
+    println!("Wrote {} and {}", dot_path.display(), svg_path.display());
+    Ok(())
+}
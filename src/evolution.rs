@@ -1,107 +1,317 @@
 use rand::seq::index::sample;
 use rand::Rng;
 
-use crate::config::{ArchInherit, BrainMode, Config, CrossoverMode, SelectionMethod};
+pub mod islands;
+pub mod nsga2;
+pub mod qd;
+
+use crate::brain::{Brain, BrainGenome};
+use crate::config::{ArchInherit, BrainMode, Config, CrossoverMode, Encoding, SelectionMethod};
 use crate::creature::Creature;
-use crate::fitness::compute_fitness;
-use crate::genome::{genome_size_from_layers, layer_weight_ranges, Genome};
+use crate::fitness::ranked_fitness;
+use crate::genome::{genome_size_for, layer_activations, Activation, Genome};
 use crate::metrics::collector::MetricsCollector;
+use crate::neat::{crossover_graphs, GraphGenome, GraphMutation, GraphNet, InnovationTracker};
 use crate::neural_net::NeuralNet;
 
+use self::nsga2::Nsga2Ranking;
+use self::qd::{ArchiveStats, SearchState};
+
 #[derive(Debug, Clone)]
 pub struct EvolutionManager {
     pub population_size: usize,
     pub elite_fraction: f32,
     pub mutation_rate: f32,
     pub mutation_strength: f32,
+    /// Innovation numbers for graph genomes, shared by the whole run.
+    pub innovations: InnovationTracker,
+    /// Novelty archive or MAP-Elites grid when `--search` is not `fitness`.
+    pub search: SearchState,
+    /// Id handed to the next creature created.
+    pub next_id: u64,
 }
 
 impl EvolutionManager {
-    pub fn next_generation(
-        &self,
+    pub fn new(config: &Config) -> Self {
+        Self {
+            population_size: config.population,
+            elite_fraction: config.elite,
+            mutation_rate: config.mut_rate,
+            mutation_strength: config.mut_strength,
+            innovations: InnovationTracker::new(config.input_size(), config.output_size()),
+            search: SearchState::from_config(config),
+            next_id: 0,
+        }
+    }
+
+    /// Feeds the evaluated population into the search archive, if any. Call
+    /// once per generation before `next_generation`.
+    pub fn record_generation(
+        &mut self,
+        population: &[Creature],
+        config: &Config,
+    ) -> Option<ArchiveStats> {
+        self.search.record(population, config)
+    }
+
+    /// Gives every creature a fresh id, for the initial population.
+    pub fn assign_ids(&mut self, population: &mut [Creature]) {
+        for creature in population {
+            creature.id = self.next_id;
+            self.next_id += 1;
+        }
+    }
+
+    /// Per-creature score that elitism and tournaments rank by: fitness, or
+    /// novelty under `--search novelty`.
+    fn selection_scores(&self, population: &[Creature], config: &Config) -> Vec<f32> {
+        match &self.search {
+            SearchState::Novelty { scores, .. } if scores.len() == population.len() => {
+                scores.clone()
+            }
+            _ => population
+                .iter()
+                .map(|creature| ranked_fitness(creature, config))
+                .collect(),
+        }
+    }
+
+    pub fn next_generation<R: Rng>(
+        &mut self,
         old_population: &[Creature],
         config: &Config,
-        rng: &mut impl Rng,
+        rng: &mut R,
         collector: &mut MetricsCollector,
     ) -> Vec<Creature> {
-        let mut sorted = old_population.to_vec();
-        sorted.sort_by(|a, b| {
-            compute_fitness(b, config)
-                .partial_cmp(&compute_fitness(a, config))
-                .unwrap()
-        });
+        if matches!(config.selection_method, SelectionMethod::Nsga2) {
+            return self.next_generation_nsga2(old_population, config, rng, collector);
+        }
+        let scores = self.selection_scores(old_population, config);
+        let mut order: Vec<usize> = (0..old_population.len()).collect();
+        order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
         let elite_count = ((self.population_size as f32) * self.elite_fraction)
             .ceil()
             .max(1.0) as usize;
-        let elite_count = elite_count.min(sorted.len());
-        let elites = &sorted[..elite_count];
+        let elite_count = elite_count.min(order.len());
+        let elites: Vec<&Creature> = order[..elite_count]
+            .iter()
+            .map(|&idx| &old_population[idx])
+            .collect();
+        // Cloned so breeding can borrow `self` mutably.
+        let map_elites: Option<Vec<Creature>> = match &self.search {
+            SearchState::MapElites(grid) if !grid.is_empty() => Some(
+                grid.elites()
+                    .map(|(_, elite)| elite.creature.clone())
+                    .collect(),
+            ),
+            _ => None,
+        };
+        let parents = Parents {
+            population: old_population,
+            scores: &scores,
+            elites: &elites,
+        };
         let mut next = Vec::with_capacity(self.population_size);
         for _ in 0..self.population_size {
-            let parent_a = select_parent(config, old_population, elites, rng);
+            let select = |rng: &mut R| match &map_elites {
+                Some(grid) => &grid[rng.gen_range(0..grid.len())],
+                None => parents.select(config, rng),
+            };
+            let parent_a = select(rng);
             let parent_b = if matches!(config.crossover_mode, CrossoverMode::None) {
                 parent_a
             } else {
-                select_parent(config, old_population, elites, rng)
+                select(rng)
             };
-            let mut genome = if matches!(config.crossover_mode, CrossoverMode::None) {
-                parent_a.brain.genome.clone()
+            next.push(self.offspring(parent_a, parent_b, config, rng));
+            collector.on_reproduction();
+        }
+        next
+    }
+
+    /// `--selection nsga2`. `old_population` holds the parents and offspring
+    /// of the last generation, evaluated in the same episode. The better half
+    /// by front rank, then crowding distance, survives unchanged, so elites
+    /// come from the first front, and the rest is bred from the survivors by
+    /// crowded tournaments. `--elite` does not apply.
+    fn next_generation_nsga2(
+        &mut self,
+        old_population: &[Creature],
+        config: &Config,
+        rng: &mut impl Rng,
+        collector: &mut MetricsCollector,
+    ) -> Vec<Creature> {
+        let ranking = Nsga2Ranking::new(old_population, &config.objectives);
+        let survivors =
+            ranking.truncate(self.population_size.div_ceil(2).min(old_population.len()));
+        let mut next: Vec<Creature> = survivors
+            .iter()
+            .map(|&idx| old_population[idx].clone())
+            .collect();
+        let k = config.tournament_k as usize;
+        while next.len() < self.population_size && !survivors.is_empty() {
+            let parent_a = &old_population[ranking.crowded_tournament(&survivors, k, rng)];
+            let parent_b = if matches!(config.crossover_mode, CrossoverMode::None) {
+                parent_a
             } else {
-                crossover_genomes(parent_a, parent_b, config, rng)
+                &old_population[ranking.crowded_tournament(&survivors, k, rng)]
             };
-            genome.mutate(self.mutation_rate, self.mutation_strength, rng);
-            if matches!(config.brain_mode, BrainMode::Evolvable) {
-                mutate_architecture(&mut genome, config, rng);
-            }
-            let brain = NeuralNet::new(genome);
-            next.push(Creature::from_brain(brain, 0, 0, 0.0));
+            next.push(self.offspring(parent_a, parent_b, config, rng));
             collector.on_reproduction();
         }
         next
     }
+
+    /// A child with a fresh id and a bred, mutated brain; pass the same
+    /// creature twice when there is no crossover.
+    pub fn offspring(
+        &mut self,
+        parent_a: &Creature,
+        parent_b: &Creature,
+        config: &Config,
+        rng: &mut impl Rng,
+    ) -> Creature {
+        let brain = match parent_a.brain.genome() {
+            Some(BrainGenome::Layered(_)) => self.breed_layered(parent_a, parent_b, config, rng),
+            Some(BrainGenome::Graph(_)) => self.breed_graph(parent_a, parent_b, config, rng),
+            None => parent_a.brain.box_clone(),
+        };
+        let mut child = Creature::from_boxed(brain, 0, 0, 0.0);
+        child.id = self.next_id;
+        child.parents = Some((parent_a.id, parent_b.id));
+        self.next_id += 1;
+        child
+    }
+
+    fn breed_layered(
+        &self,
+        parent_a: &Creature,
+        parent_b: &Creature,
+        config: &Config,
+        rng: &mut impl Rng,
+    ) -> Box<dyn Brain> {
+        let mut genome = if matches!(config.crossover_mode, CrossoverMode::None) {
+            layered_genome(parent_a).clone()
+        } else {
+            crossover_genomes(parent_a, parent_b, config, rng)
+        };
+        genome.mutate(self.mutation_rate, self.mutation_strength, rng);
+        if matches!(config.brain_mode, BrainMode::Evolvable) {
+            mutate_architecture(&mut genome, config, rng);
+            mutate_activations(&mut genome, config, rng);
+        }
+        genome.regularize(config.weight_bounds());
+        Box::new(NeuralNet::new(genome))
+    }
+
+    /// Graph genomes always evolve their structure, so `--brain` does not
+    /// apply; `--arch-inherit` picks the parent that contributes it.
+    fn breed_graph(
+        &mut self,
+        parent_a: &Creature,
+        parent_b: &Creature,
+        config: &Config,
+        rng: &mut impl Rng,
+    ) -> Box<dyn Brain> {
+        let mut genome = if matches!(config.crossover_mode, CrossoverMode::None) {
+            graph_genome(parent_a).clone()
+        } else {
+            let (primary, secondary) = order_by_arch_inherit(parent_a, parent_b, config, rng);
+            let blend = matches!(config.crossover_mode, CrossoverMode::Blend);
+            crossover_graphs(graph_genome(primary), graph_genome(secondary), blend, rng)
+        };
+        let params = GraphMutation {
+            weight_rate: self.mutation_rate,
+            weight_strength: self.mutation_strength,
+            add_connection_rate: config.add_connection_rate,
+            add_node_rate: config.add_node_rate,
+            toggle_rate: config.toggle_rate,
+            activation_rate: config.activation_mut_rate,
+            activation_pool: &config.activation_pool,
+            hidden_activation: config.hidden_activation,
+        };
+        genome.mutate(&params, &mut self.innovations, rng);
+        genome.regularize(config.weight_bounds());
+        Box::new(GraphNet::new(genome))
+    }
+}
+
+fn layered_genome(creature: &Creature) -> &Genome {
+    match creature.brain.genome() {
+        Some(BrainGenome::Layered(genome)) => genome,
+        _ => panic!("layered operator applied to a non-layered brain"),
+    }
+}
+
+fn graph_genome(creature: &Creature) -> &GraphGenome {
+    match creature.brain.genome() {
+        Some(BrainGenome::Graph(genome)) => genome,
+        _ => panic!("graph operator applied to a non-graph brain"),
+    }
 }
 
 pub fn random_population(size: usize, config: &Config, rng: &mut impl Rng) -> Vec<Creature> {
+    if let Some(baseline) = config.controller.baseline() {
+        return baseline.population(size, config, rng);
+    }
+    if config.encoding == Encoding::Graph {
+        let (inputs, outputs) = (config.input_size(), config.output_size());
+        return (0..size)
+            .map(|_| {
+                let genome = GraphGenome::random(
+                    inputs,
+                    outputs,
+                    config.graph_init_density,
+                    config.output_activation,
+                    rng,
+                );
+                Creature::from_brain(GraphNet::new(genome), 0, 0, 0.0)
+            })
+            .collect();
+    }
     let layers = config.base_layers();
     (0..size)
         .map(|_| {
-            let genome = Genome::random(layers.clone(), rng);
+            let mut genome = Genome::random_with_recurrence(layers.clone(), config.recurrence, rng);
+            genome.activations =
+                layer_activations(&layers, config.hidden_activation, config.output_activation);
             let brain = NeuralNet::new(genome);
             Creature::from_brain(brain, 0, 0, 0.0)
         })
         .collect()
 }
 
-fn select_parent<'a>(
-    config: &Config,
+struct Parents<'a> {
     population: &'a [Creature],
-    elites: &'a [Creature],
-    rng: &mut impl Rng,
-) -> &'a Creature {
-    match config.selection_method {
-        SelectionMethod::Roulette => &elites[rng.gen_range(0..elites.len())],
-        SelectionMethod::Tournament => tournament_select(population, config, rng),
+    scores: &'a [f32],
+    elites: &'a [&'a Creature],
+}
+
+impl<'a> Parents<'a> {
+    fn select(&self, config: &Config, rng: &mut impl Rng) -> &'a Creature {
+        match config.selection_method {
+            SelectionMethod::Roulette => self.elites[rng.gen_range(0..self.elites.len())],
+            SelectionMethod::Tournament => {
+                &self.population[tournament_select(self.scores, config, rng)]
+            }
+            SelectionMethod::Nsga2 => unreachable!("nsga2 breeds in next_generation_nsga2"),
+        }
     }
 }
 
-fn tournament_select<'a>(
-    population: &'a [Creature],
-    config: &Config,
-    rng: &mut impl Rng,
-) -> &'a Creature {
-    let k = config.tournament_k.max(2).min(population.len() as u32) as usize;
-    let mut best = None;
-    for idx in sample(rng, population.len(), k).iter() {
-        let candidate = &population[idx];
-        let fitness = compute_fitness(candidate, config);
+fn tournament_select(scores: &[f32], config: &Config, rng: &mut impl Rng) -> usize {
+    let k = config.tournament_k.max(2).min(scores.len() as u32) as usize;
+    let mut best: Option<usize> = None;
+    for idx in sample(rng, scores.len(), k).iter() {
         let replace = match best {
-            Some((_, best_fit)) => fitness > best_fit,
+            Some(best_idx) => scores[idx] > scores[best_idx],
             None => true,
         };
         if replace {
-            best = Some((candidate, fitness));
+            best = Some(idx);
         }
     }
-    best.expect("population must be non-empty").0
+    best.expect("population must be non-empty")
 }
 
 fn crossover_genomes(
@@ -112,39 +322,35 @@ fn crossover_genomes(
 ) -> Genome {
     let (arch_parent, other_parent) = match config.brain_mode {
         BrainMode::Fixed => (parent_a, parent_b),
-        BrainMode::Evolvable => match config.arch_inherit {
-            ArchInherit::Random => {
-                if rng.gen::<bool>() {
-                    (parent_a, parent_b)
-                } else {
-                    (parent_b, parent_a)
-                }
-            }
-            ArchInherit::Fitter => {
-                if compute_fitness(parent_a, config) >= compute_fitness(parent_b, config) {
-                    (parent_a, parent_b)
-                } else {
-                    (parent_b, parent_a)
-                }
-            }
-        },
+        BrainMode::Evolvable => order_by_arch_inherit(parent_a, parent_b, config, rng),
     };
-    let layers = arch_parent.brain.genome.layers.clone();
+    let arch_genome = layered_genome(arch_parent);
+    let other_genome = layered_genome(other_parent);
+    let layers = arch_genome.layers.clone();
+    let recurrence = arch_genome.recurrence;
     let mut genome = Genome {
         layers: layers.clone(),
-        weights: vec![0.0; genome_size_from_layers(&layers)],
+        weights: vec![0.0; genome_size_for(&layers, recurrence)],
+        recurrence,
+        activations: (0..layers.len() - 1)
+            .map(|block| arch_genome.activation(block))
+            .collect(),
     };
-    let child_ranges = layer_weight_ranges(&layers);
-    let a_ranges = layer_weight_ranges(&arch_parent.brain.genome.layers);
-    let b_ranges = layer_weight_ranges(&other_parent.brain.genome.layers);
+    let child_ranges = genome.layer_ranges();
+    let a_ranges = arch_genome.layer_ranges();
+    let b_ranges = other_genome.layer_ranges();
     for (idx, child_range) in child_ranges.iter().enumerate() {
-        let layer_match_a = same_layer_shape(&layers, &arch_parent.brain.genome.layers, idx);
-        let layer_match_b = same_layer_shape(&layers, &other_parent.brain.genome.layers, idx);
+        // Recurrent context makes a block's length depend on more than the two
+        // layer sizes, so the ranges have to agree as well.
+        let layer_match_a = same_layer_shape(&layers, &arch_genome.layers, idx)
+            && a_ranges[idx].len() == child_range.len();
+        let layer_match_b = same_layer_shape(&layers, &other_genome.layers, idx)
+            && b_ranges.get(idx).map(|range| range.len()) == Some(child_range.len());
         let both_match = layer_match_a && layer_match_b;
         if matches!(config.crossover_mode, CrossoverMode::Blend) && both_match {
             let alpha = rng.gen::<f32>();
-            let a_slice = &arch_parent.brain.genome.weights[a_ranges[idx].clone()];
-            let b_slice = &other_parent.brain.genome.weights[b_ranges[idx].clone()];
+            let a_slice = &arch_genome.weights[a_ranges[idx].clone()];
+            let b_slice = &other_genome.weights[b_ranges[idx].clone()];
             for (offset, weight) in genome.weights[child_range.clone()].iter_mut().enumerate() {
                 *weight = alpha * a_slice[offset] + (1.0 - alpha) * b_slice[offset];
             }
@@ -164,12 +370,13 @@ fn crossover_genomes(
         };
         match source {
             LayerSource::Arch => {
-                let src = &arch_parent.brain.genome.weights[a_ranges[idx].clone()];
+                let src = &arch_genome.weights[a_ranges[idx].clone()];
                 genome.weights[child_range.clone()].copy_from_slice(src);
             }
             LayerSource::Other => {
-                let src = &other_parent.brain.genome.weights[b_ranges[idx].clone()];
+                let src = &other_genome.weights[b_ranges[idx].clone()];
                 genome.weights[child_range.clone()].copy_from_slice(src);
+                genome.activations[idx] = other_genome.activation(idx);
             }
             LayerSource::Random => {
                 for weight in &mut genome.weights[child_range.clone()] {
@@ -181,6 +388,24 @@ fn crossover_genomes(
     genome
 }
 
+/// Returns the parents with the one that passes on its architecture first.
+fn order_by_arch_inherit<'a>(
+    parent_a: &'a Creature,
+    parent_b: &'a Creature,
+    config: &Config,
+    rng: &mut impl Rng,
+) -> (&'a Creature, &'a Creature) {
+    let a_first = match config.arch_inherit {
+        ArchInherit::Random => rng.gen::<bool>(),
+        ArchInherit::Fitter => ranked_fitness(parent_a, config) >= ranked_fitness(parent_b, config),
+    };
+    if a_first {
+        (parent_a, parent_b)
+    } else {
+        (parent_b, parent_a)
+    }
+}
+
 fn same_layer_shape(child_layers: &[usize], parent_layers: &[usize], idx: usize) -> bool {
     if idx + 1 >= child_layers.len() || idx + 1 >= parent_layers.len() {
         return false;
@@ -205,15 +430,20 @@ fn mutate_architecture(genome: &mut Genome, config: &Config, rng: &mut impl Rng)
     ops.push(ArchMutation::Resize);
     let choice = ops[rng.gen_range(0..ops.len())];
     let mut layers = genome.layers.clone();
+    let mut activations: Vec<Activation> = (0..layers.len() - 1)
+        .map(|block| genome.activation(block))
+        .collect();
     match choice {
         ArchMutation::Add => {
             let insert_idx = rng.gen_range(1..layers.len() - 1);
             let size = rng.gen_range(config.layer_min_neurons..=config.layer_max_neurons) as usize;
             layers.insert(insert_idx, size);
+            activations.insert(insert_idx - 1, config.hidden_activation);
         }
         ArchMutation::Remove => {
             let remove_idx = rng.gen_range(1..layers.len() - 1);
             layers.remove(remove_idx);
+            activations.remove(remove_idx - 1);
         }
         ArchMutation::Resize => {
             let layer_idx = rng.gen_range(1..layers.len() - 1);
@@ -228,6 +458,24 @@ fn mutate_architecture(genome: &mut Genome, config: &Config, rng: &mut impl Rng)
     }
     if layers != genome.layers {
         genome.reset_for_layers(layers, rng);
+        genome.activations = activations;
+    }
+}
+
+/// Swaps hidden-layer activations for random ones from the pool. The output
+/// activation stays as configured so the action policies see a stable range.
+fn mutate_activations(genome: &mut Genome, config: &Config, rng: &mut impl Rng) {
+    if config.activation_mut_rate <= 0.0 || config.activation_pool.is_empty() {
+        return;
+    }
+    let blocks = genome.layers.len().saturating_sub(1);
+    if genome.activations.len() != blocks {
+        genome.activations = (0..blocks).map(|block| genome.activation(block)).collect();
+    }
+    for activation in genome.activations.iter_mut().take(blocks.saturating_sub(1)) {
+        if rng.gen::<f32>() < config.activation_mut_rate {
+            *activation = config.activation_pool[rng.gen_range(0..config.activation_pool.len())];
+        }
     }
 }
 
@@ -248,61 +496,28 @@ enum ArchMutation {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::genome::{genome_size_from_layers, Recurrence};
+    use clap::Parser;
     use rand::rngs::StdRng;
     use rand::SeedableRng;
 
+    fn config(args: &[&str]) -> Config {
+        let mut argv = vec!["evobrain"];
+        argv.extend_from_slice(args);
+        Config::try_parse_from(argv).unwrap()
+    }
+
     #[test]
     fn tournament_select_picks_best_in_sample() {
         let mut rng = StdRng::seed_from_u64(1);
-        let config = Config {
-            generations: 1,
-            population: 3,
-            width: 1,
-            height: 1,
-            food: 0,
-            max_steps: 1,
-            max_energy: 10.0,
-            move_cost: 1.0,
-            food_energy: 1.0,
-            seed: 1,
-            input: 4,
-            hidden: 2,
-            output: 4,
-            selection_method: SelectionMethod::Tournament,
-            tournament_k: 3,
-            elite: 0.2,
-            mut_rate: 0.1,
-            mut_strength: 0.1,
-            fitness_mode: crate::config::FitnessMode::Classic,
-            fitness_food_weight: 1.0,
-            fitness_efficiency_weight: 1.0,
-            fitness_survival_weight: 0.1,
-            fitness_idle_weight: 0.5,
-            fitness_jitter_weight: 0.2,
-            idle_tolerance: 10,
-            logging_mode: crate::config::LoggingMode::Full,
-            log_gens: "all".to_string(),
-            full_log_gens: None,
-            full_log_keep: "10".to_string(),
-            run_id: None,
-            quick_keep: 2,
-            food_vision_radius: 0,
-            distance_metric: crate::config::DistanceMetric::Euclidean,
-            brain_mode: BrainMode::Fixed,
-            max_hidden_layers: 4,
-            layer_min_neurons: 4,
-            layer_max_neurons: 64,
-            crossover_mode: CrossoverMode::None,
-            arch_inherit: ArchInherit::Fitter,
-            out: "unused.csv".to_string(),
-            run_metadata: "unused.json".to_string(),
-            dump_frames: false,
-            frame_every: 1,
-            frames_dir: "frames".to_string(),
-            progress: 0,
-        };
+        let config = config(&[
+            "--population=3",
+            "--hidden=2",
+            "--selection=tournament",
+            "--tournament-k=3",
+        ]);
         let layers = config.base_layers();
-        let mut pop = vec![
+        let mut pop = [
             Creature::from_brain(
                 NeuralNet::new(Genome::random(layers.clone(), &mut rng)),
                 0,
@@ -325,68 +540,80 @@ mod tests {
         pop[0].age = 1;
         pop[1].age = 5;
         pop[2].age = 3;
-        let selected = tournament_select(&pop, &config, &mut rng);
-        assert_eq!(selected.age, 5);
+        let scores: Vec<f32> = pop
+            .iter()
+            .map(|creature| ranked_fitness(creature, &config))
+            .collect();
+        let selected = tournament_select(&scores, &config, &mut rng);
+        assert_eq!(pop[selected].age, 5);
+    }
+
+    #[test]
+    fn nan_fitness_loses_tournaments() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let config = config(&[
+            "--population=3",
+            "--hidden=2",
+            "--selection=tournament",
+            "--tournament-k=3",
+        ]);
+        let mut pop = random_population(3, &config, &mut rng);
+        for (creature, fitness) in pop.iter_mut().zip([1.0, f32::NAN, 3.0]) {
+            creature.fitness = Some(fitness);
+        }
+        let scores: Vec<f32> = pop
+            .iter()
+            .map(|creature| ranked_fitness(creature, &config))
+            .collect();
+        assert_eq!(tournament_select(&scores, &config, &mut rng), 2);
+    }
+
+    #[test]
+    fn nsga2_keeps_the_better_half_and_breeds_the_rest() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let config = config(&[
+            "--population=4",
+            "--hidden=2",
+            "--selection=nsga2",
+            "--objectives=food,-idle",
+        ]);
+        let mut evolution = EvolutionManager::new(&config);
+        let mut pop = random_population(4, &config, &mut rng);
+        evolution.assign_ids(&mut pop);
+        // Ids 1 and 3 form the first front.
+        for (creature, (food, idle)) in pop.iter_mut().zip([(1, 5), (4, 2), (2, 6), (6, 3)]) {
+            creature.food_collected = food;
+            creature.idle_steps = idle;
+        }
+        let next = evolution.next_generation(&pop, &config, &mut rng, &mut MetricsCollector::new());
+        let ids: Vec<u64> = next.iter().map(|creature| creature.id).collect();
+        assert_eq!(ids, vec![1, 3, 4, 5]);
+        for child in &next[2..] {
+            let (parent, _) = child.parents.unwrap();
+            assert!(parent == 1 || parent == 3);
+        }
     }
 
     #[test]
     fn crossover_handles_shape_mismatch() {
         let mut rng = StdRng::seed_from_u64(2);
-        let mut config = Config {
-            generations: 1,
-            population: 2,
-            width: 1,
-            height: 1,
-            food: 0,
-            max_steps: 1,
-            max_energy: 10.0,
-            move_cost: 1.0,
-            food_energy: 1.0,
-            seed: 1,
-            input: 4,
-            hidden: 2,
-            output: 4,
-            selection_method: SelectionMethod::Roulette,
-            tournament_k: 3,
-            elite: 0.2,
-            mut_rate: 0.1,
-            mut_strength: 0.1,
-            fitness_mode: crate::config::FitnessMode::Classic,
-            fitness_food_weight: 1.0,
-            fitness_efficiency_weight: 1.0,
-            fitness_survival_weight: 0.1,
-            fitness_idle_weight: 0.5,
-            fitness_jitter_weight: 0.2,
-            idle_tolerance: 10,
-            logging_mode: crate::config::LoggingMode::Full,
-            log_gens: "all".to_string(),
-            full_log_gens: None,
-            full_log_keep: "10".to_string(),
-            run_id: None,
-            quick_keep: 2,
-            food_vision_radius: 0,
-            distance_metric: crate::config::DistanceMetric::Euclidean,
-            brain_mode: BrainMode::Evolvable,
-            max_hidden_layers: 4,
-            layer_min_neurons: 2,
-            layer_max_neurons: 8,
-            crossover_mode: CrossoverMode::Blend,
-            arch_inherit: ArchInherit::Random,
-            out: "unused.csv".to_string(),
-            run_metadata: "unused.json".to_string(),
-            dump_frames: false,
-            frame_every: 1,
-            frames_dir: "frames".to_string(),
-            progress: 0,
-        };
+        let mut config = config(&[
+            "--population=2",
+            "--hidden=2",
+            "--brain=evolvable",
+            "--layer-min-neurons=2",
+            "--layer-max-neurons=8",
+            "--crossover=blend",
+            "--arch-inherit=random",
+        ]);
         let genome_a = Genome::random(vec![4, 2, 4], &mut rng);
         let genome_b = Genome::random(vec![4, 3, 3, 4], &mut rng);
         let parent_a = Creature::from_brain(NeuralNet::new(genome_a), 0, 0, 0.0);
         let parent_b = Creature::from_brain(NeuralNet::new(genome_b), 0, 0, 0.0);
         let child = crossover_genomes(&parent_a, &parent_b, &config, &mut rng);
         assert!(
-            child.layers == parent_a.brain.genome.layers
-                || child.layers == parent_b.brain.genome.layers
+            child.layers == layered_genome(&parent_a).layers
+                || child.layers == layered_genome(&parent_b).layers
         );
         assert_eq!(child.weights.len(), genome_size_from_layers(&child.layers));
         config.arch_inherit = ArchInherit::Fitter;
@@ -395,5 +622,16 @@ mod tests {
             child_b.weights.len(),
             genome_size_from_layers(&child_b.layers)
         );
+
+        let elman_a = Genome::random_with_recurrence(vec![4, 8, 4, 4], Recurrence::Elman, &mut rng);
+        let elman_b = Genome::random_with_recurrence(vec![4, 8, 4], Recurrence::Elman, &mut rng);
+        let parent_a = Creature::from_brain(NeuralNet::new(elman_a), 0, 0, 0.0);
+        let parent_b = Creature::from_brain(NeuralNet::new(elman_b), 0, 0, 0.0);
+        config.arch_inherit = ArchInherit::Random;
+        for _ in 0..8 {
+            let child = crossover_genomes(&parent_a, &parent_b, &config, &mut rng);
+            assert_eq!(child.recurrence, Recurrence::Elman);
+            assert_eq!(child.weights.len(), child.expected_size());
+        }
     }
 }
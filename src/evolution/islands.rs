@@ -0,0 +1,279 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use rand::Rng;
+
+use crate::config::{Config, Topology};
+use crate::creature::Creature;
+use crate::evolution::qd::{ArchiveExport, ArchiveStats};
+use crate::evolution::{random_population, EvolutionManager};
+use crate::fitness::ranked_fitness;
+use crate::metrics::collector::MetricsCollector;
+use crate::metrics::report::IslandSummary;
+use crate::neat::InnovationTracker;
+
+/// One sub-population: its settings, its breeding state and where its
+/// creatures sit in the run's population.
+#[derive(Debug)]
+pub struct Island<'a> {
+    pub config: &'a Config,
+    pub evolution: EvolutionManager,
+    pub range: Range<usize>,
+}
+
+/// The `--islands` model. Every island's creatures live in one population,
+/// island after island, so reports and champions cover all of them, but each
+/// island is evaluated in its own world and bred from its own creatures.
+/// Every `--migration-interval` generations the best `--migrants` of each
+/// island join the parents of its `--topology` destinations, replacing their
+/// worst. Ids and graph innovation numbers are shared by all islands, so
+/// migrants stay comparable wherever they breed.
+#[derive(Debug)]
+pub struct Islands<'a> {
+    islands: Vec<Island<'a>>,
+    topology: Topology,
+    interval: usize,
+    migrants: usize,
+    next_id: u64,
+    innovations: InnovationTracker,
+}
+
+impl<'a> Islands<'a> {
+    /// One island per entry of `configs` (see `Config::island_configs`), and
+    /// their random initial population.
+    pub fn new(
+        config: &Config,
+        configs: &'a [Config],
+        rng: &mut impl Rng,
+    ) -> (Self, Vec<Creature>) {
+        let mut population = Vec::new();
+        let mut islands = Vec::with_capacity(configs.len());
+        let mut next_id = 0;
+        for island_config in configs {
+            let mut creatures = random_population(island_config.population, island_config, rng);
+            let mut evolution = EvolutionManager::new(island_config);
+            evolution.next_id = next_id;
+            evolution.assign_ids(&mut creatures);
+            next_id = evolution.next_id;
+            let start = population.len();
+            population.extend(creatures);
+            islands.push(Island {
+                config: island_config,
+                evolution,
+                range: start..population.len(),
+            });
+        }
+        let islands = Self {
+            islands,
+            topology: config.topology,
+            interval: config.migration_interval,
+            migrants: config.migrants,
+            next_id,
+            innovations: InnovationTracker::new(config.input_size(), config.output_size()),
+        };
+        (islands, population)
+    }
+
+    pub fn len(&self) -> usize {
+        self.islands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.islands.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Island<'a>> {
+        self.islands.iter()
+    }
+
+    /// Feeds each island's evaluated creatures into its search archive. Only
+    /// a lone island can keep one (see `Config::validate`), so at most one
+    /// island has stats to return.
+    pub fn record_generation(&mut self, population: &[Creature]) -> Option<ArchiveStats> {
+        let mut stats = None;
+        for island in &mut self.islands {
+            let recorded = island
+                .evolution
+                .record_generation(&population[island.range.clone()], island.config);
+            stats = stats.or(recorded);
+        }
+        stats
+    }
+
+    pub fn export_archive(&self) -> Option<ArchiveExport> {
+        self.islands
+            .iter()
+            .find_map(|island| island.evolution.search.export(island.config))
+    }
+
+    pub fn summaries(&self, population: &[Creature]) -> Vec<IslandSummary> {
+        self.islands
+            .iter()
+            .enumerate()
+            .map(|(idx, island)| {
+                IslandSummary::of(idx as u32, &population[island.range.clone()], island.config)
+            })
+            .collect()
+    }
+
+    /// Breeds the generation after `gen`, migrating first if one is due.
+    /// Returns the new population and the number of creatures that migrated.
+    pub fn next_generation(
+        &mut self,
+        gen: usize,
+        population: &[Creature],
+        rng: &mut impl Rng,
+        collector: &mut MetricsCollector,
+    ) -> (Vec<Creature>, usize) {
+        let migrating = self.islands.len() > 1 && (gen + 1).is_multiple_of(self.interval.max(1));
+        let (parents, migrated) = if migrating {
+            let (pools, migrated) = self.migrate(population, rng);
+            (pools.into_iter().map(Cow::Owned).collect(), migrated)
+        } else {
+            let slices = self
+                .islands
+                .iter()
+                .map(|island| Cow::Borrowed(&population[island.range.clone()]))
+                .collect::<Vec<_>>();
+            (slices, 0)
+        };
+        let mut next = Vec::with_capacity(population.len());
+        for (idx, pool) in parents.iter().enumerate() {
+            let start = next.len();
+            next.extend(self.breed(idx, pool, rng, collector));
+            self.islands[idx].range = start..next.len();
+        }
+        (next, migrated)
+    }
+
+    /// Runs one island's `EvolutionManager` on the shared ids and innovations.
+    fn breed(
+        &mut self,
+        idx: usize,
+        parents: &[Creature],
+        rng: &mut impl Rng,
+        collector: &mut MetricsCollector,
+    ) -> Vec<Creature> {
+        let island = &mut self.islands[idx];
+        island.evolution.next_id = self.next_id;
+        std::mem::swap(&mut island.evolution.innovations, &mut self.innovations);
+        let children = island
+            .evolution
+            .next_generation(parents, island.config, rng, collector);
+        std::mem::swap(&mut island.evolution.innovations, &mut self.innovations);
+        self.next_id = island.evolution.next_id;
+        children
+    }
+
+    /// Each island's parents after migration: its own creatures with the
+    /// worst replaced by copies of the best of the islands sending to it.
+    fn migrate(&self, population: &[Creature], rng: &mut impl Rng) -> (Vec<Vec<Creature>>, usize) {
+        let count = self.islands.len();
+        let mut pools: Vec<Vec<Creature>> = self
+            .islands
+            .iter()
+            .map(|island| population[island.range.clone()].to_vec())
+            .collect();
+        let mut arrivals: Vec<Vec<Creature>> = vec![Vec::new(); count];
+        for (source, island) in self.islands.iter().enumerate() {
+            let order = ranking(&pools[source], island.config);
+            let emigrants = &order[..self.migrants.min(order.len())];
+            for dest in destinations(self.topology, source, count, rng) {
+                arrivals[dest].extend(emigrants.iter().map(|&idx| pools[source][idx].clone()));
+            }
+        }
+        let mut migrated = 0;
+        for ((pool, island), incoming) in pools.iter_mut().zip(&self.islands).zip(arrivals) {
+            let order = ranking(pool, island.config);
+            for (&slot, creature) in order.iter().rev().zip(incoming) {
+                pool[slot] = creature;
+                migrated += 1;
+            }
+        }
+        (pools, migrated)
+    }
+}
+
+/// Indices of `pool`, best first; ties keep their order.
+fn ranking(pool: &[Creature], config: &Config) -> Vec<usize> {
+    let scores: Vec<f32> = pool
+        .iter()
+        .map(|creature| ranked_fitness(creature, config))
+        .collect();
+    let mut order: Vec<usize> = (0..pool.len()).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+    order
+}
+
+/// Islands that `source` sends its migrants to, out of `count` (at least 2).
+fn destinations(topology: Topology, source: usize, count: usize, rng: &mut impl Rng) -> Vec<usize> {
+    match topology {
+        Topology::Ring => vec![(source + 1) % count],
+        Topology::Full => (0..count).filter(|&dest| dest != source).collect(),
+        Topology::Random => {
+            let dest = rng.gen_range(0..count - 1);
+            vec![if dest >= source { dest + 1 } else { dest }]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn config(args: &[&str]) -> Config {
+        let mut argv = vec!["evobrain", "--population", "4", "--hidden", "2"];
+        argv.extend_from_slice(args);
+        Config::try_parse_from(argv).unwrap()
+    }
+
+    #[test]
+    fn island_configs_apply_their_settings() {
+        let config = config(&["--islands", "3", "--island-config", "2:mut-rate=0.3,food=7"]);
+        let configs = config.island_configs().unwrap();
+        assert_eq!(configs.len(), 3);
+        assert_eq!(configs[0].mut_rate, config.mut_rate);
+        assert_eq!((configs[2].mut_rate, configs[2].food), (0.3, 7));
+
+        for bad in ["3:food=7", "1:seed=7", "1:food=lots", "food=7"] {
+            let config = self::config(&["--islands", "3", "--island-config", bad]);
+            assert!(config.validate().is_err(), "{bad} should be rejected");
+        }
+    }
+
+    #[test]
+    fn ring_migration_replaces_the_worst_with_the_best_of_the_previous_island() {
+        let config = config(&["--islands", "3", "--migrants", "1"]);
+        let configs = config.island_configs().unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let (islands, mut population) = Islands::new(&config, &configs, &mut rng);
+        for (idx, creature) in population.iter_mut().enumerate() {
+            creature.fitness = Some((idx % 4) as f32);
+        }
+
+        let (pools, migrated) = islands.migrate(&population, &mut rng);
+        assert_eq!(migrated, 3);
+        let ids = |pool: &[Creature]| pool.iter().map(|creature| creature.id).collect::<Vec<_>>();
+        // Island 1 (ids 4..8) loses its worst, id 4, to island 0's best, id 3.
+        assert_eq!(ids(&pools[1]), vec![3, 5, 6, 7]);
+        assert_eq!(ids(&pools[0]), vec![11, 1, 2, 3]);
+    }
+
+    #[test]
+    fn breeding_keeps_ids_unique_across_islands() {
+        let config = config(&["--islands", "2", "--migration-interval", "1"]);
+        let configs = config.island_configs().unwrap();
+        let mut rng = StdRng::seed_from_u64(2);
+        let (mut islands, population) = Islands::new(&config, &configs, &mut rng);
+        let (next, migrated) =
+            islands.next_generation(0, &population, &mut rng, &mut MetricsCollector::new());
+        assert_eq!(migrated, 4);
+        let ids: Vec<u64> = next.iter().map(|creature| creature.id).collect();
+        assert_eq!(ids, (8..16).collect::<Vec<_>>());
+        assert_eq!(islands.iter().nth(1).unwrap().range, 4..8);
+    }
+}
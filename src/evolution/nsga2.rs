@@ -0,0 +1,452 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use rand::seq::index::sample;
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::creature::Creature;
+use crate::fitness::expr::Var;
+
+/// One NSGA-II objective: a creature counter, maximised unless written with a
+/// leading `-` (e.g. `-jitter`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Objective {
+    pub var: Var,
+    pub maximize: bool,
+}
+
+impl Objective {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let (maximize, name) = match spec.strip_prefix('-') {
+            Some(name) => (false, name),
+            None => (true, spec.strip_prefix('+').unwrap_or(spec)),
+        };
+        let var = Var::from_name(&name.to_ascii_lowercase())
+            .ok_or_else(|| format!("unknown objective '{name}'"))?;
+        Ok(Self { var, maximize })
+    }
+
+    /// The counter value oriented so that larger is always better.
+    pub fn value(&self, creature: &Creature) -> f32 {
+        let value = self.var.read(creature);
+        if self.maximize {
+            value
+        } else {
+            -value
+        }
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.maximize { "" } else { "-" };
+        write!(f, "{sign}{}", self.var.names()[0])
+    }
+}
+
+impl Serialize for Objective {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Objective {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let spec = String::deserialize(deserializer)?;
+        Objective::parse(&spec).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Pareto rank (0 = non-dominated) and crowding distance of every creature.
+#[derive(Debug, Clone)]
+pub struct Nsga2Ranking {
+    pub points: Vec<Vec<f32>>,
+    pub fronts: Vec<Vec<usize>>,
+    pub rank: Vec<usize>,
+    pub crowding: Vec<f32>,
+}
+
+impl Nsga2Ranking {
+    pub fn new(population: &[Creature], objectives: &[Objective]) -> Self {
+        let points: Vec<Vec<f32>> = population
+            .iter()
+            .map(|creature| objectives.iter().map(|obj| obj.value(creature)).collect())
+            .collect();
+        let fronts = non_dominated_sort(&points);
+        let mut rank = vec![0; points.len()];
+        let mut crowding = vec![0.0; points.len()];
+        for (level, front) in fronts.iter().enumerate() {
+            for (&idx, distance) in front.iter().zip(crowding_distance(&points, front)) {
+                rank[idx] = level;
+                crowding[idx] = distance;
+            }
+        }
+        Self {
+            points,
+            fronts,
+            rank,
+            crowding,
+        }
+    }
+
+    /// NSGA-II's crowded comparison: lower rank wins, then larger crowding.
+    pub fn better(&self, a: usize, b: usize) -> bool {
+        match self.rank[a].cmp(&self.rank[b]) {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            Ordering::Equal => self.crowding[a] > self.crowding[b],
+        }
+    }
+
+    pub fn pareto_front(&self) -> &[usize] {
+        self.fronts.first().map_or(&[], Vec::as_slice)
+    }
+
+    /// The hypervolume reference a run uses unless `--hv-reference` is set,
+    /// in the maximising orientation: zero for maximised objectives, whose
+    /// counters cannot go below it, and the population's worst value for
+    /// minimised ones.
+    pub fn default_reference(&self) -> Vec<f32> {
+        let dims = self.points.first().map_or(0, Vec::len);
+        (0..dims)
+            .map(|dim| {
+                self.points
+                    .iter()
+                    .map(|point| point[dim])
+                    .fold(0.0, f32::min)
+            })
+            .collect()
+    }
+
+    /// Hypervolume of the first front above `reference`, which is in the
+    /// maximising orientation.
+    pub fn hypervolume(&self, reference: &[f32]) -> f64 {
+        let front: Vec<Vec<f32>> = self
+            .pareto_front()
+            .iter()
+            .map(|&idx| self.points[idx].clone())
+            .collect();
+        hypervolume(&front, reference)
+    }
+
+    /// NSGA-II's environmental selection: the `count` best creatures, whole
+    /// fronts first and then the least crowded of the front that does not fit.
+    pub fn truncate(&self, count: usize) -> Vec<usize> {
+        let mut kept = Vec::with_capacity(count);
+        for front in &self.fronts {
+            let room = count - kept.len();
+            if front.len() <= room {
+                kept.extend_from_slice(front);
+                continue;
+            }
+            let mut order = front.clone();
+            order.sort_by(|&a, &b| self.crowding[b].total_cmp(&self.crowding[a]));
+            kept.extend_from_slice(&order[..room]);
+            break;
+        }
+        kept
+    }
+
+    /// The winner of a crowded comparison among `k` of `candidates`.
+    pub fn crowded_tournament(&self, candidates: &[usize], k: usize, rng: &mut impl Rng) -> usize {
+        let k = k.clamp(1, candidates.len());
+        sample(rng, candidates.len(), k)
+            .iter()
+            .map(|pick| candidates[pick])
+            .reduce(|best, idx| if self.better(idx, best) { idx } else { best })
+            .expect("candidates must be non-empty")
+    }
+}
+
+/// Converts objective values between the counters' own units and the
+/// maximising orientation; the conversion is its own inverse.
+pub fn orient(objectives: &[Objective], values: &[f32]) -> Vec<f32> {
+    values
+        .iter()
+        .zip(objectives)
+        .map(|(&value, obj)| if obj.maximize { value } else { -value })
+        .collect()
+}
+
+/// The first front of a generation as written to `pareto_gen_XXXX.json`.
+/// Values and the reference point are in the counters' own units, so
+/// minimised objectives are not negated here.
+#[derive(Debug, Clone, Serialize)]
+pub struct ParetoFront {
+    pub generation: u32,
+    pub objectives: Vec<Objective>,
+    pub reference: Vec<f32>,
+    pub hypervolume: f64,
+    pub members: Vec<ParetoMember>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParetoMember {
+    pub id: u64,
+    pub values: Vec<f32>,
+}
+
+impl ParetoFront {
+    /// `ranking` must come from `population`; members carry creature ids.
+    /// `reference` is in the maximising orientation and should stay the same
+    /// for a whole run, so hypervolumes compare across generations.
+    pub fn new(
+        generation: u32,
+        population: &[Creature],
+        ranking: &Nsga2Ranking,
+        objectives: &[Objective],
+        reference: &[f32],
+    ) -> Self {
+        Self {
+            generation,
+            objectives: objectives.to_vec(),
+            reference: orient(objectives, reference),
+            hypervolume: ranking.hypervolume(reference),
+            members: ranking
+                .pareto_front()
+                .iter()
+                .map(|&idx| ParetoMember {
+                    id: population[idx].id,
+                    values: orient(objectives, &ranking.points[idx]),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn dominates(a: &[f32], b: &[f32]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x >= y) && a.iter().zip(b).any(|(x, y)| x > y)
+}
+
+/// Deb's fast non-dominated sort; returns indices grouped by front.
+pub fn non_dominated_sort(points: &[Vec<f32>]) -> Vec<Vec<usize>> {
+    let n = points.len();
+    let mut dominated_by = vec![Vec::new(); n];
+    let mut domination_count = vec![0usize; n];
+    for i in 0..n {
+        for j in i + 1..n {
+            if dominates(&points[i], &points[j]) {
+                dominated_by[i].push(j);
+                domination_count[j] += 1;
+            } else if dominates(&points[j], &points[i]) {
+                dominated_by[j].push(i);
+                domination_count[i] += 1;
+            }
+        }
+    }
+    let mut fronts = Vec::new();
+    let mut current: Vec<usize> = (0..n).filter(|&i| domination_count[i] == 0).collect();
+    while !current.is_empty() {
+        let mut next = Vec::new();
+        for &i in &current {
+            for &j in &dominated_by[i] {
+                domination_count[j] -= 1;
+                if domination_count[j] == 0 {
+                    next.push(j);
+                }
+            }
+        }
+        next.sort_unstable();
+        fronts.push(current);
+        current = next;
+    }
+    fronts
+}
+
+/// Crowding distance of each member of `front`, in the same order. Boundary
+/// points get infinity.
+pub fn crowding_distance(points: &[Vec<f32>], front: &[usize]) -> Vec<f32> {
+    let mut distance = vec![0.0f32; front.len()];
+    if front.len() <= 2 {
+        distance.fill(f32::INFINITY);
+        return distance;
+    }
+    let dims = points[front[0]].len();
+    let columns = (0..dims).map(|dim| {
+        front
+            .iter()
+            .map(|&idx| points[idx][dim])
+            .collect::<Vec<f32>>()
+    });
+    let mut order: Vec<usize> = (0..front.len()).collect();
+    for column in columns {
+        order.sort_by(|&a, &b| column[a].total_cmp(&column[b]));
+        let (first, last) = (order[0], order[order.len() - 1]);
+        distance[first] = f32::INFINITY;
+        distance[last] = f32::INFINITY;
+        let span = column[last] - column[first];
+        if span <= 0.0 {
+            continue;
+        }
+        for window in order.windows(3) {
+            distance[window[1]] += (column[window[2]] - column[window[0]]) / span;
+        }
+    }
+    distance
+}
+
+/// Exact hypervolume of `points` (maximising) above `reference`, computed by
+/// slicing one objective at a time (HSO). Points below the reference in any
+/// objective are left out; points on it count.
+pub fn hypervolume(points: &[Vec<f32>], reference: &[f32]) -> f64 {
+    let points: Vec<Vec<f64>> = points
+        .iter()
+        .filter(|point| point.iter().zip(reference).all(|(p, r)| p >= r))
+        .map(|point| point.iter().map(|&value| value as f64).collect())
+        .collect();
+    let reference: Vec<f64> = reference.iter().map(|&value| value as f64).collect();
+    slice_volume(points, &reference, 0)
+}
+
+fn slice_volume(mut points: Vec<Vec<f64>>, reference: &[f64], dim: usize) -> f64 {
+    if points.is_empty() {
+        return 0.0;
+    }
+    if dim + 1 == reference.len() {
+        let top = points
+            .iter()
+            .map(|point| point[dim])
+            .fold(f64::NEG_INFINITY, f64::max);
+        return top - reference[dim];
+    }
+    points.sort_by(|a, b| b[dim].total_cmp(&a[dim]));
+    let mut volume = 0.0;
+    let mut slab: Vec<Vec<f64>> = Vec::with_capacity(points.len());
+    for (idx, point) in points.iter().enumerate() {
+        let floor = points.get(idx + 1).map_or(reference[dim], |next| next[dim]);
+        // Points dominated in the remaining objectives add nothing to a slab.
+        if !slab.iter().any(|kept| {
+            kept[dim + 1..]
+                .iter()
+                .zip(&point[dim + 1..])
+                .all(|(k, p)| k >= p)
+        }) {
+            slab.retain(|kept| {
+                !point[dim + 1..]
+                    .iter()
+                    .zip(&kept[dim + 1..])
+                    .all(|(p, k)| p >= k)
+            });
+            slab.push(point.clone());
+        }
+        let height = point[dim] - floor;
+        if height > 0.0 {
+            volume += height * slice_volume(slab.clone(), reference, dim + 1);
+        }
+    }
+    volume
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::config::Config;
+    use crate::evolution::random_population;
+
+    #[test]
+    fn sorts_into_fronts_with_crowding() {
+        let points = vec![
+            vec![1.0, 5.0],
+            vec![2.0, 4.0],
+            vec![3.0, 3.0],
+            vec![1.0, 1.0],
+            vec![2.0, 2.0],
+        ];
+        let fronts = non_dominated_sort(&points);
+        assert_eq!(fronts, vec![vec![0, 1, 2], vec![4], vec![3]]);
+        let crowding = crowding_distance(&points, &fronts[0]);
+        assert!(crowding[0].is_infinite() && crowding[2].is_infinite());
+        assert!((crowding[1] - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn truncation_keeps_whole_fronts_then_the_least_crowded() {
+        let config = Config::try_parse_from(["evobrain"]).unwrap();
+        let mut rng = StdRng::seed_from_u64(6);
+        let mut population = random_population(6, &config, &mut rng);
+        // Front 0 is ids 0..3, front 1 is ids 3..6 with id 4 in its middle.
+        for (creature, (food, survival)) in
+            population
+                .iter_mut()
+                .zip([(1, 9), (5, 5), (9, 1), (1, 4), (2, 3), (4, 1)])
+        {
+            creature.food_collected = food;
+            creature.survival_steps = survival;
+        }
+        let objectives = vec![
+            Objective::parse("food").unwrap(),
+            Objective::parse("survival").unwrap(),
+        ];
+        let ranking = Nsga2Ranking::new(&population, &objectives);
+        assert_eq!(ranking.truncate(3), vec![0, 1, 2]);
+        let mut kept = ranking.truncate(5);
+        kept.sort_unstable();
+        assert_eq!(kept, vec![0, 1, 2, 3, 5]);
+        let winner = ranking.crowded_tournament(&[1, 4], 2, &mut rng);
+        assert_eq!(winner, 1);
+    }
+
+    #[test]
+    fn hypervolume_matches_hand_computed_areas() {
+        let front = vec![vec![1.0, 3.0], vec![2.0, 2.0], vec![3.0, 1.0]];
+        assert!((hypervolume(&front, &[0.0, 0.0]) - 6.0).abs() < 1e-9);
+        let cube = vec![vec![2.0, 2.0, 2.0], vec![1.0, 1.0, 3.0]];
+        assert!((hypervolume(&cube, &[0.0, 0.0, 0.0]) - 9.0).abs() < 1e-9);
+        assert_eq!(hypervolume(&front, &[3.0, 3.0]), 0.0);
+        // (1, 3) lies on the reference: kept, but it spans no area.
+        assert!((hypervolume(&front, &[1.0, 0.0]) - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn front_members_carry_creature_ids() {
+        let config = Config::try_parse_from(["evobrain"]).unwrap();
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut population = random_population(3, &config, &mut rng);
+        for ((creature, id), food) in population.iter_mut().zip([40, 41, 42]).zip([1, 5, 3]) {
+            creature.id = id;
+            creature.food_collected = food;
+        }
+        let objectives = vec![Objective::parse("food").unwrap()];
+        let ranking = Nsga2Ranking::new(&population, &objectives);
+        let front = ParetoFront::new(7, &population, &ranking, &objectives, &[0.0]);
+        let ids: Vec<u64> = front.members.iter().map(|member| member.id).collect();
+        assert_eq!(ids, vec![41]);
+        assert_eq!(front.hypervolume, 5.0);
+    }
+
+    #[test]
+    fn default_reference_is_zero_or_the_worst_minimised_value() {
+        let config = Config::try_parse_from(["evobrain"]).unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut population = random_population(2, &config, &mut rng);
+        for (creature, (food, idle)) in population.iter_mut().zip([(3, 4), (5, 9)]) {
+            creature.food_collected = food;
+            creature.idle_steps = idle;
+        }
+        let objectives = vec![
+            Objective::parse("food").unwrap(),
+            Objective::parse("-idle").unwrap(),
+        ];
+        let ranking = Nsga2Ranking::new(&population, &objectives);
+        let reference = ranking.default_reference();
+        assert_eq!(orient(&objectives, &reference), vec![0.0, 9.0]);
+    }
+
+    #[test]
+    fn objectives_parse_direction() {
+        let objective = Objective::parse("-jitter").unwrap();
+        assert!(!objective.maximize);
+        assert_eq!(objective.to_string(), "-jitter");
+        assert_eq!(
+            Objective::parse("survival_steps").unwrap().var,
+            Var::Survival
+        );
+        assert!(Objective::parse("speed").is_err());
+    }
+}
@@ -0,0 +1,438 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::action::ActionSpace;
+use crate::brain::BrainSnapshot;
+use crate::config::Config;
+use crate::creature::{Creature, VISIT_GRID};
+use crate::fitness::fitness_of;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum SearchMode {
+    Fitness,
+    Novelty,
+    MapElites,
+}
+
+/// How a creature's episode is summarised for novelty and MAP-Elites. Every
+/// descriptor component lies in `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum BehaviorKind {
+    /// Normalised final `(x, y)`.
+    FinalPosition,
+    /// Share of steps spent in each cell of the coarse visit grid.
+    VisitedCells,
+    /// Share of each action in the action set.
+    ActionFreq,
+}
+
+/// Most niches a MAP-Elites grid may have. Beyond this almost every niche
+/// stays empty and coverage says nothing.
+pub const MAX_NICHES: f64 = 100_000.0;
+
+pub struct BehaviorDescriptor {
+    kind: BehaviorKind,
+    width: usize,
+    height: usize,
+    actions: ActionSpace,
+}
+
+impl BehaviorDescriptor {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            kind: config.behavior,
+            width: config.width,
+            height: config.height,
+            actions: ActionSpace::from_config(config),
+        }
+    }
+
+    /// Length of the vectors `describe` returns.
+    pub fn dims(&self) -> usize {
+        match self.kind {
+            BehaviorKind::FinalPosition => 2,
+            BehaviorKind::VisitedCells => VISIT_GRID * VISIT_GRID,
+            BehaviorKind::ActionFreq => self.actions.len(),
+        }
+    }
+
+    pub fn describe(&self, creature: &Creature) -> Vec<f32> {
+        let share = |counts: &mut dyn Iterator<Item = u32>| -> Vec<f32> {
+            let counts: Vec<u32> = counts.collect();
+            let total = counts.iter().sum::<u32>().max(1) as f32;
+            counts.iter().map(|&count| count as f32 / total).collect()
+        };
+        match self.kind {
+            BehaviorKind::FinalPosition => vec![
+                creature.x as f32 / self.width.saturating_sub(1).max(1) as f32,
+                creature.y as f32 / self.height.saturating_sub(1).max(1) as f32,
+            ],
+            BehaviorKind::VisitedCells => share(&mut creature.visit_counts.iter().copied()),
+            BehaviorKind::ActionFreq => share(
+                &mut self
+                    .actions
+                    .actions()
+                    .iter()
+                    .map(|&action| creature.action_counts[action as usize]),
+            ),
+        }
+    }
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Behaviours of past individuals that novelty is measured against. Each
+/// generation the most novel individuals are added; the oldest entries are
+/// dropped once `max` is reached.
+#[derive(Debug, Clone)]
+pub struct NoveltyArchive {
+    k: usize,
+    add_per_generation: usize,
+    max: usize,
+    entries: VecDeque<Vec<f32>>,
+}
+
+impl NoveltyArchive {
+    pub fn new(k: usize, add_per_generation: usize, max: usize) -> Self {
+        Self {
+            k,
+            add_per_generation,
+            max,
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Mean distance to the `k` nearest neighbours among the other members of
+    /// `behaviors` and the archive.
+    pub fn novelty(&self, behaviors: &[Vec<f32>]) -> Vec<f32> {
+        let mut distances = Vec::with_capacity(behaviors.len() + self.entries.len());
+        behaviors
+            .iter()
+            .enumerate()
+            .map(|(idx, behavior)| {
+                distances.clear();
+                distances.extend(
+                    behaviors
+                        .iter()
+                        .enumerate()
+                        .filter(|&(other, _)| other != idx)
+                        .map(|(_, other)| distance(behavior, other)),
+                );
+                distances.extend(self.entries.iter().map(|other| distance(behavior, other)));
+                let k = self.k.min(distances.len());
+                if k == 0 {
+                    return 0.0;
+                }
+                distances.select_nth_unstable_by(k - 1, f32::total_cmp);
+                distances[..k].iter().sum::<f32>() / k as f32
+            })
+            .collect()
+    }
+
+    pub fn record(&mut self, behaviors: &[Vec<f32>], novelty: &[f32]) {
+        let mut order: Vec<usize> = (0..behaviors.len()).collect();
+        order.sort_by(|&a, &b| novelty[b].total_cmp(&novelty[a]));
+        for &idx in order.iter().take(self.add_per_generation) {
+            self.entries.push_back(behaviors[idx].clone());
+        }
+        while self.entries.len() > self.max {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Vec<f32>> {
+        self.entries.iter()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Elite {
+    pub creature: Creature,
+    pub fitness: f32,
+    pub behavior: Vec<f32>,
+}
+
+/// MAP-Elites archive: the fittest individual seen in each behaviour niche.
+/// Niches split every descriptor dimension into `bins` equal intervals.
+#[derive(Debug, Clone)]
+pub struct MapElites {
+    bins: usize,
+    dims: usize,
+    cells: BTreeMap<Vec<usize>, Elite>,
+    /// Lowest fitness ever offered, the zero of `qd_score`.
+    min_fitness: Option<f32>,
+}
+
+impl MapElites {
+    pub fn new(bins: usize) -> Self {
+        Self {
+            bins,
+            dims: 0,
+            cells: BTreeMap::new(),
+            min_fitness: None,
+        }
+    }
+
+    pub fn cell(&self, behavior: &[f32]) -> Vec<usize> {
+        behavior
+            .iter()
+            .map(|&value| ((value.clamp(0.0, 1.0) * self.bins as f32) as usize).min(self.bins - 1))
+            .collect()
+    }
+
+    /// Returns whether the creature became the elite of its niche.
+    pub fn insert(&mut self, creature: &Creature, fitness: f32, behavior: Vec<f32>) -> bool {
+        self.dims = behavior.len();
+        self.min_fitness = Some(self.min_fitness.map_or(fitness, |min| min.min(fitness)));
+        let cell = self.cell(&behavior);
+        if self
+            .cells
+            .get(&cell)
+            .is_some_and(|elite| elite.fitness >= fitness)
+        {
+            return false;
+        }
+        self.cells.insert(
+            cell,
+            Elite {
+                creature: creature.clone(),
+                fitness,
+                behavior,
+            },
+        );
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Filled niches as a fraction of all `bins^dims` niches.
+    pub fn coverage(&self) -> f64 {
+        if self.dims == 0 {
+            return 0.0;
+        }
+        self.cells.len() as f64 / (self.bins as f64).powi(self.dims as i32)
+    }
+
+    /// Sum over all filled niches of the elite's fitness above the lowest
+    /// fitness offered so far, so filling a niche never lowers the score.
+    pub fn qd_score(&self) -> f64 {
+        let offset = self.min_fitness.unwrap_or(0.0) as f64;
+        self.cells
+            .values()
+            .map(|elite| elite.fitness as f64 - offset)
+            .sum()
+    }
+
+    pub fn elites(&self) -> impl Iterator<Item = (&Vec<usize>, &Elite)> {
+        self.cells.iter()
+    }
+}
+
+/// Archive statistics for one generation's report.
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveStats {
+    pub size: u32,
+    pub novelty_mean: Option<f32>,
+    pub coverage: Option<f64>,
+    pub qd_score: Option<f64>,
+}
+
+/// Per-run state of the non-fitness search modes.
+#[derive(Debug, Clone)]
+pub enum SearchState {
+    Fitness,
+    Novelty {
+        archive: NoveltyArchive,
+        /// Novelty of the last recorded population, used for selection.
+        scores: Vec<f32>,
+    },
+    MapElites(MapElites),
+}
+
+impl SearchState {
+    pub fn from_config(config: &Config) -> Self {
+        match config.search {
+            SearchMode::Fitness => SearchState::Fitness,
+            SearchMode::Novelty => SearchState::Novelty {
+                archive: NoveltyArchive::new(
+                    config.novelty_k as usize,
+                    config.archive_add as usize,
+                    config.archive_max as usize,
+                ),
+                scores: Vec::new(),
+            },
+            SearchMode::MapElites => {
+                SearchState::MapElites(MapElites::new(config.map_bins as usize))
+            }
+        }
+    }
+
+    /// Feeds an evaluated population into the archive.
+    pub fn record(&mut self, population: &[Creature], config: &Config) -> Option<ArchiveStats> {
+        if matches!(self, SearchState::Fitness) {
+            return None;
+        }
+        let descriptor = BehaviorDescriptor::from_config(config);
+        let behaviors: Vec<Vec<f32>> = population
+            .iter()
+            .map(|creature| descriptor.describe(creature))
+            .collect();
+        match self {
+            SearchState::Fitness => None,
+            SearchState::Novelty { archive, scores } => {
+                *scores = archive.novelty(&behaviors);
+                archive.record(&behaviors, scores);
+                let mean = scores.iter().sum::<f32>() / scores.len().max(1) as f32;
+                Some(ArchiveStats {
+                    size: archive.len() as u32,
+                    novelty_mean: Some(mean),
+                    ..ArchiveStats::default()
+                })
+            }
+            SearchState::MapElites(grid) => {
+                for (creature, behavior) in population.iter().zip(behaviors) {
+                    let fitness = fitness_of(creature, config);
+                    if fitness.is_finite() {
+                        grid.insert(creature, fitness, behavior);
+                    }
+                }
+                Some(ArchiveStats {
+                    size: grid.len() as u32,
+                    novelty_mean: None,
+                    coverage: Some(grid.coverage()),
+                    qd_score: Some(grid.qd_score()),
+                })
+            }
+        }
+    }
+
+    pub fn export(&self, config: &Config) -> Option<ArchiveExport> {
+        match self {
+            SearchState::Fitness => None,
+            SearchState::Novelty { archive, .. } => Some(ArchiveExport::Novelty {
+                behavior: config.behavior,
+                entries: archive.entries().cloned().collect(),
+            }),
+            SearchState::MapElites(grid) => Some(ArchiveExport::MapElites {
+                behavior: config.behavior,
+                bins: config.map_bins,
+                coverage: grid.coverage(),
+                qd_score: grid.qd_score(),
+                cells: grid
+                    .elites()
+                    .map(|(cell, elite)| ExportedElite {
+                        cell: cell.clone(),
+                        fitness: elite.fitness,
+                        behavior: elite.behavior.clone(),
+                        brain: elite.creature.brain.snapshot(),
+                    })
+                    .collect(),
+            }),
+        }
+    }
+}
+
+/// Contents of `archive.json`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ArchiveExport {
+    Novelty {
+        behavior: BehaviorKind,
+        entries: Vec<Vec<f32>>,
+    },
+    MapElites {
+        behavior: BehaviorKind,
+        bins: u32,
+        coverage: f64,
+        qd_score: f64,
+        cells: Vec<ExportedElite>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedElite {
+    pub cell: Vec<usize>,
+    pub fitness: f32,
+    pub behavior: Vec<f32>,
+    pub brain: BrainSnapshot,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn novelty_rewards_outliers_and_archive_is_bounded() {
+        let behaviors = vec![
+            vec![0.0, 0.0],
+            vec![0.1, 0.0],
+            vec![0.0, 0.1],
+            vec![1.0, 1.0],
+        ];
+        let mut archive = NoveltyArchive::new(2, 2, 3);
+        let novelty = archive.novelty(&behaviors);
+        let most_novel = (0..4).max_by(|&a, &b| novelty[a].total_cmp(&novelty[b]));
+        assert_eq!(most_novel, Some(3));
+        archive.record(&behaviors, &novelty);
+        assert_eq!(archive.entries().next(), Some(&vec![1.0, 1.0]));
+        archive.record(&behaviors, &novelty);
+        assert_eq!(archive.len(), 3);
+    }
+
+    #[test]
+    fn map_elites_keeps_best_per_niche() {
+        let mut grid = MapElites::new(4);
+        assert_eq!(grid.cell(&[0.0, 1.0]), vec![0, 3]);
+        assert_eq!(grid.cell(&[0.3, 0.26]), vec![1, 1]);
+        let creature = crate::brain::RandomWalker::new(4, 1);
+        let creature = Creature::from_brain(creature, 0, 0, 0.0);
+        assert!(grid.insert(&creature, 1.0, vec![0.1, 0.1]));
+        assert!(!grid.insert(&creature, 0.5, vec![0.2, 0.2]));
+        assert!(grid.insert(&creature, 2.0, vec![0.2, 0.2]));
+        assert!(grid.insert(&creature, 0.5, vec![0.9, 0.9]));
+        assert_eq!(grid.len(), 2);
+        // Offset by the lowest fitness offered, 0.5.
+        assert!((grid.qd_score() - 1.5).abs() < 1e-9);
+        assert!(grid.insert(&creature, -1.0, vec![0.9, 0.1]));
+        assert!((grid.qd_score() - 4.5).abs() < 1e-9);
+        assert!((grid.coverage() - 3.0 / 16.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn map_elites_rejects_grids_too_large_to_fill() {
+        use clap::Parser;
+
+        let config = |args: &[&str]| {
+            let mut argv = vec!["evobrain", "--search=map-elites"];
+            argv.extend_from_slice(args);
+            Config::try_parse_from(argv).unwrap()
+        };
+        assert!(config(&["--behavior=visited-cells"]).validate().is_err());
+        assert!(config(&["--behavior=visited-cells", "--map-bins=2"])
+            .validate()
+            .is_ok());
+        assert!(config(&["--behavior=action-freq"]).validate().is_ok());
+    }
+}
@@ -1,12 +1,45 @@
 use crate::config::{Config, FitnessMode};
 use crate::creature::Creature;
 
+pub mod expr;
+
 const EFFICIENCY_EPS: f32 = 1e-6;
 
 pub fn compute_fitness(creature: &Creature, config: &Config) -> f32 {
     match config.fitness_mode {
         FitnessMode::Classic => creature.fitness_classic(),
         FitnessMode::EfficientCollector => compute_efficient_fitness(creature, config),
+        FitnessMode::Expression => config
+            .fitness_expr
+            .as_ref()
+            .expect("fitness-expr should be validated")
+            .eval(creature),
+    }
+}
+
+/// Computes every creature's fitness once, after its episode, and stores it
+/// on the creature for sorting, selection and reporting to read.
+pub fn evaluate_population(population: &mut [Creature], config: &Config) {
+    for creature in population {
+        creature.fitness = Some(compute_fitness(creature, config));
+    }
+}
+
+/// The stored fitness, or a fresh computation for a creature that has not
+/// been evaluated.
+pub fn fitness_of(creature: &Creature, config: &Config) -> f32 {
+    creature
+        .fitness
+        .unwrap_or_else(|| compute_fitness(creature, config))
+}
+
+/// Fitness as selection sees it: NaN ranks below every other value.
+pub fn ranked_fitness(creature: &Creature, config: &Config) -> f32 {
+    let fitness = fitness_of(creature, config);
+    if fitness.is_nan() {
+        f32::NEG_INFINITY
+    } else {
+        fitness
     }
 }
 
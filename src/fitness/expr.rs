@@ -0,0 +1,475 @@
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::creature::Creature;
+
+use super::EFFICIENCY_EPS;
+
+/// A fitness formula such as `food*2 + log(survival) - 0.1*jitter`, parsed
+/// once and evaluated per creature without allocating.
+///
+/// Supports numbers, `+ - * / ^`, unary minus, parentheses, the functions
+/// `log sqrt abs exp` and `min max` (two arguments), and the variables listed
+/// in [`Var::ALL`].
+#[derive(Clone, PartialEq)]
+pub struct FitnessExpr {
+    source: String,
+    root: Node,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Var {
+    Food,
+    EnergySpent,
+    Survival,
+    Idle,
+    Jitter,
+    Age,
+    Energy,
+    Efficiency,
+}
+
+impl Var {
+    pub const ALL: [Var; 8] = [
+        Var::Food,
+        Var::EnergySpent,
+        Var::Survival,
+        Var::Idle,
+        Var::Jitter,
+        Var::Age,
+        Var::Energy,
+        Var::Efficiency,
+    ];
+
+    /// The short name plus the creature field it reads, when they differ.
+    pub fn names(self) -> &'static [&'static str] {
+        match self {
+            Var::Food => &["food", "food_collected"],
+            Var::EnergySpent => &["energy_spent"],
+            Var::Survival => &["survival", "survival_steps"],
+            Var::Idle => &["idle", "idle_steps"],
+            Var::Jitter => &["jitter", "jitter_score"],
+            Var::Age => &["age"],
+            Var::Energy => &["energy"],
+            Var::Efficiency => &["efficiency"],
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Var> {
+        Var::ALL
+            .iter()
+            .copied()
+            .find(|var| var.names().contains(&name))
+    }
+
+    pub fn read(self, creature: &Creature) -> f32 {
+        match self {
+            Var::Food => creature.food_collected as f32,
+            Var::EnergySpent => creature.energy_spent,
+            Var::Survival => creature.survival_steps as f32,
+            Var::Idle => creature.idle_steps as f32,
+            Var::Jitter => creature.jitter_score as f32,
+            Var::Age => creature.age as f32,
+            Var::Energy => creature.energy,
+            Var::Efficiency => {
+                creature.food_collected as f32 / (creature.energy_spent + EFFICIENCY_EPS)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Func {
+    Log,
+    Sqrt,
+    Abs,
+    Exp,
+    Min,
+    Max,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<(Func, usize)> {
+        match name {
+            "log" => Some((Func::Log, 1)),
+            "sqrt" => Some((Func::Sqrt, 1)),
+            "abs" => Some((Func::Abs, 1)),
+            "exp" => Some((Func::Exp, 1)),
+            "min" => Some((Func::Min, 2)),
+            "max" => Some((Func::Max, 2)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Num(f32),
+    Var(Var),
+    Neg(Box<Node>),
+    Bin(BinOp, Box<Node>, Box<Node>),
+    Call(Func, Vec<Node>),
+}
+
+impl Node {
+    fn eval(&self, read: &impl Fn(Var) -> f32) -> f32 {
+        match self {
+            Node::Num(value) => *value,
+            Node::Var(var) => read(*var),
+            Node::Neg(inner) => -inner.eval(read),
+            Node::Bin(op, lhs, rhs) => {
+                let (a, b) = (lhs.eval(read), rhs.eval(read));
+                match op {
+                    BinOp::Add => a + b,
+                    BinOp::Sub => a - b,
+                    BinOp::Mul => a * b,
+                    BinOp::Div => a / b,
+                    BinOp::Pow => a.powf(b),
+                }
+            }
+            Node::Call(func, args) => {
+                let a = args[0].eval(read);
+                match func {
+                    Func::Log => a.ln(),
+                    Func::Sqrt => a.sqrt(),
+                    Func::Abs => a.abs(),
+                    Func::Exp => a.exp(),
+                    Func::Min => a.min(args[1].eval(read)),
+                    Func::Max => a.max(args[1].eval(read)),
+                }
+            }
+        }
+    }
+
+    /// Folds subtrees that do not read any variable into constants.
+    fn fold(self) -> Node {
+        match self {
+            Node::Neg(inner) => match inner.fold() {
+                Node::Num(value) => Node::Num(-value),
+                inner => Node::Neg(Box::new(inner)),
+            },
+            Node::Bin(op, lhs, rhs) => {
+                let node = Node::Bin(op, Box::new(lhs.fold()), Box::new(rhs.fold()));
+                node.constant()
+            }
+            Node::Call(func, args) => {
+                Node::Call(func, args.into_iter().map(Node::fold).collect()).constant()
+            }
+            node => node,
+        }
+    }
+
+    fn constant(self) -> Node {
+        let is_const = match &self {
+            Node::Bin(_, lhs, rhs) => {
+                matches!(**lhs, Node::Num(_)) && matches!(**rhs, Node::Num(_))
+            }
+            Node::Call(_, args) => args.iter().all(|arg| matches!(arg, Node::Num(_))),
+            _ => false,
+        };
+        if !is_const {
+            return self;
+        }
+        Node::Num(self.eval(&|_| unreachable!("constant subtree reads no variables")))
+    }
+}
+
+impl FitnessExpr {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let root = parser.expr()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected '{token}' in fitness expression"));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            root: root.fold(),
+        })
+    }
+
+    pub fn eval(&self, creature: &Creature) -> f32 {
+        self.root.eval(&|var| var.read(creature))
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl fmt::Debug for FitnessExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FitnessExpr({:?})", self.source)
+    }
+}
+
+impl Serialize for FitnessExpr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for FitnessExpr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        FitnessExpr::parse(&source).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f32),
+    Ident(String),
+    Op(char),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Num(value) => write!(f, "{value}"),
+            Token::Ident(name) => write!(f, "{name}"),
+            Token::Op(op) => write!(f, "{op}"),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(start, ch)) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+        } else if ch.is_ascii_digit() || ch == '.' {
+            let mut end = start;
+            while let Some(&(idx, c)) = chars.peek() {
+                if c.is_ascii_digit() || c == '.' {
+                    end = idx + c.len_utf8();
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let text = &source[start..end];
+            let value = text
+                .parse::<f32>()
+                .map_err(|_| format!("invalid number '{text}' in fitness expression"))?;
+            tokens.push(Token::Num(value));
+        } else if ch.is_ascii_alphabetic() || ch == '_' {
+            let mut end = start;
+            while let Some(&(idx, c)) = chars.peek() {
+                if c.is_ascii_alphanumeric() || c == '_' {
+                    end = idx + c.len_utf8();
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token::Ident(source[start..end].to_ascii_lowercase()));
+        } else if "+-*/^(),".contains(ch) {
+            tokens.push(Token::Op(ch));
+            chars.next();
+        } else {
+            return Err(format!("unexpected character '{ch}' in fitness expression"));
+        }
+    }
+    if tokens.is_empty() {
+        return Err("fitness expression is empty".to_string());
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the grammar
+///
+/// ```text
+/// expr   = term (('+' | '-') term)*
+/// term   = unary (('*' | '/') unary)*
+/// unary  = '-' unary | power
+/// power  = atom ('^' unary)?
+/// atom   = number | var | func '(' expr (',' expr)* ')' | '(' expr ')'
+/// ```
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: char) -> Result<(), String> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            match self.peek() {
+                Some(token) => Err(format!("expected '{op}' but found '{token}'")),
+                None => Err(format!("expected '{op}' at end of fitness expression")),
+            }
+        }
+    }
+
+    fn expr(&mut self) -> Result<Node, String> {
+        let mut node = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                BinOp::Add
+            } else if self.eat('-') {
+                BinOp::Sub
+            } else {
+                return Ok(node);
+            };
+            node = Node::Bin(op, Box::new(node), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                BinOp::Mul
+            } else if self.eat('/') {
+                BinOp::Div
+            } else {
+                return Ok(node);
+            };
+            node = Node::Bin(op, Box::new(node), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.eat('-') {
+            return Ok(Node::Neg(Box::new(self.unary()?)));
+        }
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(Node::Bin(
+                BinOp::Pow,
+                Box::new(base),
+                Box::new(self.unary()?),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some(Token::Num(value)) => Ok(Node::Num(value)),
+            Some(Token::Op('(')) => {
+                let node = self.expr()?;
+                self.expect(')')?;
+                Ok(node)
+            }
+            Some(Token::Ident(name)) => {
+                if let Some((func, arity)) = Func::from_name(&name) {
+                    self.expect('(')?;
+                    let mut args = vec![self.expr()?];
+                    while self.eat(',') {
+                        args.push(self.expr()?);
+                    }
+                    self.expect(')')?;
+                    if args.len() != arity {
+                        return Err(format!(
+                            "{name}() takes {arity} argument(s), got {}",
+                            args.len()
+                        ));
+                    }
+                    return Ok(Node::Call(func, args));
+                }
+                Var::from_name(&name).map(Node::Var).ok_or_else(|| {
+                    let known: Vec<&str> = Var::ALL.iter().map(|var| var.names()[0]).collect();
+                    format!(
+                        "unknown variable '{name}' in fitness expression (known: {})",
+                        known.join(", ")
+                    )
+                })
+            }
+            Some(token) => Err(format!("unexpected '{token}' in fitness expression")),
+            None => Err("fitness expression ends unexpectedly".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::Genome;
+    use crate::neural_net::NeuralNet;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn creature() -> Creature {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut creature = Creature::from_brain(
+            NeuralNet::new(Genome::random(vec![1, 1], &mut rng)),
+            0,
+            0,
+            4.0,
+        );
+        creature.food_collected = 5;
+        creature.survival_steps = 20;
+        creature.jitter_score = 10;
+        creature.energy_spent = 2.0;
+        creature
+    }
+
+    #[test]
+    fn evaluates_with_precedence_and_functions() {
+        let creature = creature();
+        let expr = FitnessExpr::parse("food*2 + log(survival) - 0.1*jitter").unwrap();
+        let expected = 10.0 + 20f32.ln() - 1.0;
+        assert!((expr.eval(&creature) - expected).abs() < 1e-5);
+        let expr = FitnessExpr::parse("-2^2 + max(energy, 1) * (food_collected - 3)").unwrap();
+        assert_eq!(expr.eval(&creature), -4.0 + 4.0 * 2.0);
+        let expr = FitnessExpr::parse("efficiency").unwrap();
+        assert!((expr.eval(&creature) - 2.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn folds_constants() {
+        let expr = FitnessExpr::parse("2 * 3 + sqrt(16)").unwrap();
+        assert_eq!(expr.root, Node::Num(10.0));
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for source in [
+            "",
+            "food +",
+            "foo * 2",
+            "log(1, 2)",
+            "(food",
+            "food food",
+            "3 $ 4",
+        ] {
+            assert!(
+                FitnessExpr::parse(source).is_err(),
+                "{source:?} should fail"
+            );
+        }
+    }
+}
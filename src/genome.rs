@@ -1,17 +1,139 @@
+use clap::ValueEnum;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+/// Context units fed back into the network on the next step.
+///
+/// * `Elman`: every hidden layer sees its own activations from the previous step.
+/// * `Jordan`: the first hidden layer sees the previous step's outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+pub enum Recurrence {
+    #[default]
+    None,
+    Elman,
+    Jordan,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+pub enum Activation {
+    #[default]
+    Tanh,
+    Relu,
+    Sigmoid,
+    #[value(name = "leaky-relu")]
+    LeakyRelu,
+    Identity,
+    Sin,
+    Gaussian,
+}
+
+impl Activation {
+    pub const ALL: [Activation; 7] = [
+        Activation::Tanh,
+        Activation::Relu,
+        Activation::Sigmoid,
+        Activation::LeakyRelu,
+        Activation::Identity,
+        Activation::Sin,
+        Activation::Gaussian,
+    ];
+
+    pub fn apply(self, x: f32) -> f32 {
+        match self {
+            Activation::Tanh => x.tanh(),
+            Activation::Relu => x.max(0.0),
+            Activation::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Activation::LeakyRelu => {
+                if x > 0.0 {
+                    x
+                } else {
+                    0.01 * x
+                }
+            }
+            Activation::Identity => x,
+            Activation::Sin => x.sin(),
+            Activation::Gaussian => (-x * x).exp(),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Activation::Tanh => "tanh",
+            Activation::Relu => "relu",
+            Activation::Sigmoid => "sigmoid",
+            Activation::LeakyRelu => "leaky-relu",
+            Activation::Identity => "identity",
+            Activation::Sin => "sin",
+            Activation::Gaussian => "gaussian",
+        }
+    }
+}
+
+/// Keeps weights bounded after mutation: shrink towards zero by `decay`
+/// (L2 weight decay), then clamp to `[-clamp, clamp]`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WeightBounds {
+    pub decay: f32,
+    pub clamp: Option<f32>,
+}
+
+impl WeightBounds {
+    pub fn apply(&self, weight: &mut f32) {
+        *weight *= 1.0 - self.decay;
+        if let Some(limit) = self.clamp {
+            *weight = weight.clamp(-limit, limit);
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Genome {
     pub layers: Vec<usize>,
     pub weights: Vec<f32>,
+    #[serde(default)]
+    pub recurrence: Recurrence,
+    /// Activation of every non-input layer. Missing entries (e.g. genomes
+    /// saved before activations were configurable) fall back to `tanh`.
+    #[serde(default)]
+    pub activations: Vec<Activation>,
 }
 
 impl Genome {
     pub fn random(layers: Vec<usize>, rng: &mut impl Rng) -> Self {
-        let size = genome_size_from_layers(&layers);
+        Self::random_with_recurrence(layers, Recurrence::None, rng)
+    }
+
+    pub fn random_with_recurrence(
+        layers: Vec<usize>,
+        recurrence: Recurrence,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let size = genome_size_for(&layers, recurrence);
         let weights = (0..size).map(|_| rng.gen_range(-1.0..=1.0)).collect();
-        Self { layers, weights }
+        Self {
+            layers,
+            weights,
+            recurrence,
+            activations: Vec::new(),
+        }
+    }
+
+    /// Activation applied to `layers[block + 1]`.
+    pub fn activation(&self, block: usize) -> Activation {
+        self.activations.get(block).copied().unwrap_or_default()
+    }
+
+    /// Activation of every hidden layer, the genes evolution may change.
+    pub fn hidden_activations(&self) -> impl Iterator<Item = Activation> + '_ {
+        (0..self.layers.len().saturating_sub(2)).map(|block| self.activation(block))
+    }
+
+    pub fn expected_size(&self) -> usize {
+        genome_size_for(&self.layers, self.recurrence)
+    }
+
+    pub fn layer_ranges(&self) -> Vec<std::ops::Range<usize>> {
+        layer_weight_ranges_for(&self.layers, self.recurrence)
     }
 
     pub fn mutate(&mut self, rate: f32, strength: f32, rng: &mut impl Rng) {
@@ -34,32 +156,106 @@ impl Genome {
         }
     }
 
+    pub fn regularize(&mut self, bounds: WeightBounds) {
+        self.weights
+            .iter_mut()
+            .for_each(|weight| bounds.apply(weight));
+    }
+
+    /// Silences neuron `neuron` of hidden layer `layer` (an index into
+    /// `layers`) by zeroing every weight that reads its value, including its
+    /// Elman context.
+    pub fn knock_out(&mut self, layer: usize, neuron: usize) {
+        let ranges = self.layer_ranges();
+        let rows = |block: usize| {
+            let row_len =
+                self.layers[block] + 1 + context_size(&self.layers, self.recurrence, block);
+            (ranges[block].start, row_len, self.layers[block + 1])
+        };
+        let mut targets = Vec::new();
+        if layer + 1 < self.layers.len() {
+            targets.push((layer, 1 + neuron));
+        }
+        if layer > 0
+            && self.recurrence == Recurrence::Elman
+            && context_size(&self.layers, self.recurrence, layer - 1) > 0
+        {
+            targets.push((layer - 1, 1 + self.layers[layer - 1] + neuron));
+        }
+        for (block, offset) in targets {
+            let (start, row_len, row_count) = rows(block);
+            for row in 0..row_count {
+                self.weights[start + row * row_len + offset] = 0.0;
+            }
+        }
+    }
+
     pub fn reset_for_layers(&mut self, layers: Vec<usize>, rng: &mut impl Rng) {
-        let size = genome_size_from_layers(&layers);
+        let size = genome_size_for(&layers, self.recurrence);
         self.layers = layers;
         self.weights = (0..size).map(|_| rng.gen_range(-1.0..=1.0)).collect();
     }
 }
 
+pub fn layer_activations(
+    layers: &[usize],
+    hidden: Activation,
+    output: Activation,
+) -> Vec<Activation> {
+    let blocks = layers.len().saturating_sub(1);
+    (0..blocks)
+        .map(|block| if block + 1 == blocks { output } else { hidden })
+        .collect()
+}
+
 pub fn genome_size(input: usize, hidden: usize, output: usize) -> usize {
     genome_size_from_layers(&[input, hidden, output])
 }
 
 pub fn genome_size_from_layers(layers: &[usize]) -> usize {
+    genome_size_for(layers, Recurrence::None)
+}
+
+pub fn genome_size_for(layers: &[usize], recurrence: Recurrence) -> usize {
     if layers.len() < 2 {
         return 0;
     }
-    layers.windows(2).map(|pair| (pair[0] + 1) * pair[1]).sum()
+    (0..layers.len() - 1)
+        .map(|block| block_len(layers, recurrence, block))
+        .sum()
 }
 
 pub fn layer_weight_ranges(layers: &[usize]) -> Vec<std::ops::Range<usize>> {
+    layer_weight_ranges_for(layers, Recurrence::None)
+}
+
+pub fn layer_weight_ranges_for(
+    layers: &[usize],
+    recurrence: Recurrence,
+) -> Vec<std::ops::Range<usize>> {
     let mut ranges = Vec::with_capacity(layers.len().saturating_sub(1));
     let mut start = 0;
-    for pair in layers.windows(2) {
-        let len = (pair[0] + 1) * pair[1];
-        let end = start + len;
+    for block in 0..layers.len().saturating_sub(1) {
+        let end = start + block_len(layers, recurrence, block);
         ranges.push(start..end);
         start = end;
     }
     ranges
 }
+
+/// Number of context values feeding the neurons of `layers[block + 1]`.
+/// Each neuron's weight row is laid out as `[bias, inputs.., context..]`.
+pub fn context_size(layers: &[usize], recurrence: Recurrence, block: usize) -> usize {
+    let target = block + 1;
+    let is_hidden = target + 1 < layers.len();
+    match recurrence {
+        Recurrence::None => 0,
+        Recurrence::Elman if is_hidden => layers[target],
+        Recurrence::Jordan if block == 0 && is_hidden => layers.last().copied().unwrap_or(0),
+        Recurrence::Elman | Recurrence::Jordan => 0,
+    }
+}
+
+fn block_len(layers: &[usize], recurrence: Recurrence, block: usize) -> usize {
+    (layers[block] + 1 + context_size(layers, recurrence, block)) * layers[block + 1]
+}
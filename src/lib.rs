@@ -1,10 +1,19 @@
+pub mod ablation;
+pub mod action;
+pub mod analysis;
+pub mod baselines;
+pub mod brain;
 pub mod config;
 pub mod creature;
+pub mod draw;
 pub mod evolution;
 pub mod fitness;
 pub mod frame_dump;
 pub mod genome;
 pub mod metrics;
+pub mod neat;
 pub mod neural_net;
+pub mod policy_map;
+pub mod sensors;
 pub mod simulation;
 pub mod world;
@@ -1,55 +1,63 @@
 use std::error::Error;
-use std::fs::File;
-use std::io::{BufWriter, Write};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
+use evobrain::ablation::{run_ablation, AblateArgs};
+use evobrain::analysis::{run_analysis, AnalyzeArgs};
 use evobrain::config::Config;
-use evobrain::metrics::GenerationMetrics;
-use evobrain::simulation::run_simulation;
+use evobrain::draw::{run_draw, DrawArgs};
+use evobrain::metrics::writer::RunIdentity;
+use evobrain::policy_map::{run_policy_map, PolicyArgs};
+use evobrain::simulation::{open_sinks, run_simulation_with_sinks};
+
+/// `evobrain [options]` runs a simulation; the subcommands work on finished
+/// runs.
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Headless evolutionary simulation")]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    config: Config,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Summarize, compare and plot finished run directories.
+    Analyze(AnalyzeArgs),
+    /// Measure what a saved champion relies on by knocking parts of it out.
+    Ablate(AblateArgs),
+    /// Export a saved brain as `brain.dot` and `brain.svg`.
+    Draw(DrawArgs),
+    /// Map the action saved brains pick over food directions and energy levels.
+    Policy(PolicyArgs),
+}
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let config = Config::parse();
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Analyze(args)) => return run_analysis(&args),
+        Some(Command::Ablate(args)) => return run_ablation(&args),
+        Some(Command::Draw(args)) => return run_draw(&args),
+        Some(Command::Policy(args)) => return run_policy_map(&args),
+        None => {}
+    }
+    let config = cli.config;
     if let Err(message) = config.validate() {
         return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, message).into());
     }
-    let result = run_simulation(&config);
-    write_csv(&config.out, &result.metrics)?;
-    write_metadata(&config.run_metadata, &config, result.total_generations)?;
-    Ok(())
-}
-
-fn write_csv(path: &str, metrics: &[GenerationMetrics]) -> Result<(), Box<dyn Error>> {
-    let file = File::create(path)?;
-    let writer = BufWriter::new(file);
-    let mut wtr = csv::Writer::from_writer(writer);
-    for metric in metrics {
-        wtr.serialize(metric)?;
+    let identity = RunIdentity::from_config(&config);
+    let sinks = open_sinks(&config, &identity);
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let result = run_simulation_with_sinks(&config, &mut rng, &identity, sinks);
+    if !result.sink_errors.is_empty() {
+        return Err(std::io::Error::other(result.sink_errors.join("; ")).into());
     }
-    wtr.flush()?;
-    Ok(())
-}
-
-fn write_metadata(
-    path: &str,
-    config: &Config,
-    total_generations: usize,
-) -> Result<(), Box<dyn Error>> {
-    #[derive(serde::Serialize)]
-    struct RunMetadata<'a> {
-        config: &'a Config,
-        generations: usize,
-        nn_sizes: (usize, usize, usize),
+    if let Some(reason) = result.aborted {
+        return Err(std::io::Error::other(format!("run aborted at {reason}")).into());
     }
-
-    let metadata = RunMetadata {
-        config,
-        generations: total_generations,
-        nn_sizes: config.nn_sizes(),
-    };
-    let file = File::create(path)?;
-    let mut writer = BufWriter::new(file);
-    serde_json::to_writer_pretty(&mut writer, &metadata)?;
-    writer.write_all(b"\n")?;
     Ok(())
 }
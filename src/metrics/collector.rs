@@ -2,6 +2,9 @@
 pub struct MetricsCollector {
     food_eaten_total: u32,
     reproductions_total: u32,
+    /// World cells any creature has stood on this episode, row-major.
+    visited: Vec<bool>,
+    visited_count: u32,
 }
 
 impl MetricsCollector {
@@ -17,6 +20,18 @@ impl MetricsCollector {
         self.reproductions_total = self.reproductions_total.saturating_add(1);
     }
 
+    pub fn on_visit(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        if self.visited.len() != width * height {
+            self.visited = vec![false; width * height];
+            self.visited_count = 0;
+        }
+        let cell = &mut self.visited[y * width + x];
+        if !*cell {
+            *cell = true;
+            self.visited_count += 1;
+        }
+    }
+
     pub fn food_eaten_total(&self) -> u32 {
         self.food_eaten_total
     }
@@ -25,8 +40,18 @@ impl MetricsCollector {
         self.reproductions_total
     }
 
+    /// Fraction of world cells visited by at least one creature.
+    pub fn visited_cell_coverage(&self) -> f32 {
+        if self.visited.is_empty() {
+            return 0.0;
+        }
+        self.visited_count as f32 / self.visited.len() as f32
+    }
+
     pub fn reset(&mut self) {
         self.food_eaten_total = 0;
         self.reproductions_total = 0;
+        self.visited.fill(false);
+        self.visited_count = 0;
     }
 }
@@ -0,0 +1,103 @@
+use std::error::Error;
+use std::net::SocketAddr;
+
+use crate::config::{Config, EvolutionMode};
+use crate::metrics::events::{EventKind, RunEvent};
+use crate::metrics::report::GenerationReport;
+use crate::metrics::sink::MetricsSink;
+
+/// The plain terminal output of a run: a progress line every `--progress`
+/// generations and extinctions on stdout, checkpoints and errors on stderr.
+/// Under `--tui` the dashboard owns stdout, so only stderr is written.
+#[derive(Debug)]
+pub struct ConsoleSink {
+    progress: usize,
+    open_ended: bool,
+    extinctions: bool,
+    serving: Option<SocketAddr>,
+}
+
+impl ConsoleSink {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            progress: if config.tui { 0 } else { config.progress },
+            open_ended: config.mode == EvolutionMode::OpenEnded,
+            extinctions: !config.tui,
+            serving: None,
+        }
+    }
+
+    /// Announces the `--serve` address when the run starts.
+    pub fn serving(mut self, addr: SocketAddr) -> Self {
+        self.serving = Some(addr);
+        self
+    }
+
+    fn progress_line(&self, generation: u32, survivors: u32) -> Option<String> {
+        let done = generation as usize + 1;
+        if self.progress == 0 || !done.is_multiple_of(self.progress) {
+            return None;
+        }
+        Some(if self.open_ended {
+            format!("Window {done} complete, {survivors} alive")
+        } else {
+            format!("Generation {done} complete")
+        })
+    }
+}
+
+impl MetricsSink for ConsoleSink {
+    fn name(&self) -> &'static str {
+        "console"
+    }
+
+    fn record(&mut self, report: &GenerationReport) -> Result<(), Box<dyn Error>> {
+        if let Some(line) = self.progress_line(report.generation, report.survivors) {
+            println!("{line}");
+        }
+        Ok(())
+    }
+
+    fn record_event(&mut self, event: &RunEvent) -> Result<(), Box<dyn Error>> {
+        match event.kind {
+            EventKind::Start => {
+                if let Some(addr) = self.serving {
+                    eprintln!("Serving run on http://{addr}");
+                }
+            }
+            EventKind::Extinction if self.extinctions => println!("{}", event.message),
+            EventKind::Checkpoint => eprintln!("Checkpoint written to {}", event.message),
+            EventKind::Error => eprintln!("{}", event.message),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn progress_lines_follow_the_mode_and_interval() {
+        let config = Config::try_parse_from(["evobrain", "--progress=2"]).unwrap();
+        let console = ConsoleSink::new(&config);
+        assert_eq!(console.progress_line(0, 5), None);
+        assert_eq!(
+            console.progress_line(1, 5).as_deref(),
+            Some("Generation 2 complete")
+        );
+
+        let config =
+            Config::try_parse_from(["evobrain", "--progress=1", "--mode=open-ended"]).unwrap();
+        assert_eq!(
+            ConsoleSink::new(&config).progress_line(2, 7).as_deref(),
+            Some("Window 3 complete, 7 alive")
+        );
+
+        let config = Config::try_parse_from(["evobrain", "--progress=1", "--tui"]).unwrap();
+        assert_eq!(ConsoleSink::new(&config).progress_line(0, 5), None);
+    }
+}
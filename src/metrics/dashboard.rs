@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::{stdout, Write};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::metrics::report::GenerationReport;
+use crate::metrics::sink::{MetricsSink, RunSummary};
+use crate::world::WorldSnapshot;
+
+const SPARK: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const CHART_WIDTH: usize = 60;
+const BAR_WIDTH: usize = 30;
+const MAX_ARCHITECTURES: usize = 6;
+const WORLD_MAX_COLS: usize = 60;
+const WORLD_MAX_ROWS: usize = 20;
+/// Redraws are throttled so fast runs are not slowed down by the terminal.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// `--tui`: redraws a live view of the run on stdout — progress and ETA,
+/// sparklines of fitness, survivors and food, the architecture histogram and
+/// the world at the end of the latest episode. Only reads reports, so results
+/// are unchanged.
+#[derive(Debug)]
+pub struct DashboardSink {
+    total_generations: usize,
+    started: Instant,
+    last_draw: Option<Instant>,
+    drawn_latest: bool,
+    run_id: String,
+    generation: u32,
+    best: Vec<f32>,
+    mean: Vec<f32>,
+    survivors: Vec<f32>,
+    food: Vec<f32>,
+    architectures: BTreeMap<String, u32>,
+    population: u32,
+    world: Option<WorldSnapshot>,
+}
+
+impl DashboardSink {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            total_generations: config.generations,
+            started: Instant::now(),
+            last_draw: None,
+            drawn_latest: false,
+            run_id: String::new(),
+            generation: 0,
+            best: Vec::new(),
+            mean: Vec::new(),
+            survivors: Vec::new(),
+            food: Vec::new(),
+            architectures: BTreeMap::new(),
+            population: 0,
+            world: None,
+        }
+    }
+
+    fn render(&self, elapsed: Duration) -> String {
+        let done = self.best.len();
+        let rate = done as f64 / elapsed.as_secs_f64().max(1e-9);
+        let remaining = self.total_generations.saturating_sub(done);
+        let eta = Duration::from_secs_f64(remaining as f64 / rate.max(1e-9));
+
+        let mut frame = String::new();
+        let _ = writeln!(
+            frame,
+            "evobrain {}  generation {}/{} ({:.1}%)  {:.1} gen/s  elapsed {}  ETA {}",
+            self.run_id,
+            self.generation + 1,
+            self.total_generations,
+            100.0 * done as f64 / self.total_generations.max(1) as f64,
+            rate,
+            format_duration(elapsed),
+            format_duration(eta)
+        );
+        let _ = writeln!(frame);
+        for (label, values) in [
+            ("best fitness", &self.best),
+            ("mean fitness", &self.mean),
+            ("survivors", &self.survivors),
+            ("food eaten", &self.food),
+        ] {
+            let latest = values.last().copied().unwrap_or(f32::NAN);
+            let _ = writeln!(
+                frame,
+                "{label:<13} {:<CHART_WIDTH$} {latest:.2}",
+                sparkline(values, CHART_WIDTH)
+            );
+        }
+
+        let _ = writeln!(
+            frame,
+            "\narchitectures ({} unique)",
+            self.architectures.len()
+        );
+        let mut architectures: Vec<(&String, &u32)> = self.architectures.iter().collect();
+        architectures.sort_by(|a, b| b.1.cmp(a.1));
+        for (label, &count) in architectures.iter().take(MAX_ARCHITECTURES) {
+            let len = (count as usize * BAR_WIDTH).div_ceil(self.population.max(1) as usize);
+            let _ = writeln!(
+                frame,
+                "  {label:<16} {:<BAR_WIDTH$} {count}",
+                "█".repeat(len.min(BAR_WIDTH))
+            );
+        }
+
+        if let Some(world) = &self.world {
+            let _ = writeln!(
+                frame,
+                "\nworld after generation {} (@ alive, x dead, * food)",
+                self.generation + 1
+            );
+            frame.push_str(&render_world(world, WORLD_MAX_COLS, WORLD_MAX_ROWS));
+        }
+        frame
+    }
+
+    fn draw(&mut self, now: Instant) -> Result<(), Box<dyn Error>> {
+        let frame = self.render(now - self.started);
+        let mut out = stdout().lock();
+        // Home the cursor and clear the screen before each frame.
+        write!(out, "\x1b[H\x1b[2J{frame}")?;
+        out.flush()?;
+        self.last_draw = Some(now);
+        self.drawn_latest = true;
+        Ok(())
+    }
+}
+
+impl MetricsSink for DashboardSink {
+    fn name(&self) -> &'static str {
+        "dashboard"
+    }
+
+    fn record_world(&mut self, world: &WorldSnapshot) -> Result<(), Box<dyn Error>> {
+        self.world = Some(world.clone());
+        Ok(())
+    }
+
+    fn record(&mut self, report: &GenerationReport) -> Result<(), Box<dyn Error>> {
+        if self.run_id.is_empty() {
+            self.run_id = report.run_id.clone();
+        }
+        self.generation = report.generation;
+        self.best.push(report.fitness_best);
+        self.mean.push(report.fitness_mean);
+        self.survivors.push(report.survivors as f32);
+        self.food.push(report.food_eaten_total as f32);
+        self.architectures = report.architecture_counts.clone();
+        self.population = report.population_size;
+        self.drawn_latest = false;
+
+        let now = Instant::now();
+        if self
+            .last_draw
+            .is_none_or(|last| now - last >= REDRAW_INTERVAL)
+        {
+            self.draw(now)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, summary: &RunSummary<'_>) -> Result<(), Box<dyn Error>> {
+        if !self.drawn_latest {
+            self.draw(Instant::now())?;
+        }
+        match summary.aborted {
+            Some(reason) => println!("\nAborted: {reason}"),
+            None => println!("\nFinished {} generations", summary.total_generations),
+        }
+        Ok(())
+    }
+}
+
+/// One block character per bucket of `values`, scaled between their finite
+/// minimum and maximum; buckets average their values.
+fn sparkline(values: &[f32], width: usize) -> String {
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+    let buckets = values.len().min(width);
+    let averages: Vec<f32> = (0..buckets)
+        .map(|bucket| {
+            let start = bucket * values.len() / buckets;
+            let end = ((bucket + 1) * values.len() / buckets).max(start + 1);
+            let finite: Vec<f32> = values[start..end]
+                .iter()
+                .copied()
+                .filter(|v| v.is_finite())
+                .collect();
+            if finite.is_empty() {
+                f32::NAN
+            } else {
+                finite.iter().sum::<f32>() / finite.len() as f32
+            }
+        })
+        .collect();
+    let finite = averages.iter().copied().filter(|v| v.is_finite());
+    let min = finite.clone().fold(f32::INFINITY, f32::min);
+    let max = finite.fold(f32::NEG_INFINITY, f32::max);
+    averages
+        .iter()
+        .map(|&value| {
+            if !value.is_finite() {
+                ' '
+            } else if max <= min {
+                SPARK[SPARK.len() / 2]
+            } else {
+                let level = (value - min) / (max - min) * (SPARK.len() - 1) as f32;
+                SPARK[level.round() as usize]
+            }
+        })
+        .collect()
+}
+
+/// Draws the world scaled down to at most `max_cols`×`max_rows` characters.
+/// A living creature wins over food, which wins over a dead creature.
+fn render_world(world: &WorldSnapshot, max_cols: usize, max_rows: usize) -> String {
+    let cols = world.width.clamp(1, max_cols);
+    let rows = world.height.clamp(1, max_rows);
+    let cell = |x: usize, y: usize| {
+        (
+            (x * cols / world.width.max(1)).min(cols - 1),
+            (y * rows / world.height.max(1)).min(rows - 1),
+        )
+    };
+    let mut grid = vec![(0u8, ' '); cols * rows];
+    let mut mark = |x: usize, y: usize, priority: u8, glyph: char| {
+        let (cx, cy) = cell(x, y);
+        let slot = &mut grid[cy * cols + cx];
+        if priority > slot.0 {
+            *slot = (priority, glyph);
+        }
+    };
+    for &(x, y, alive) in &world.creatures {
+        if alive {
+            mark(x, y, 3, '@');
+        } else {
+            mark(x, y, 1, 'x');
+        }
+    }
+    for &(x, y) in &world.food {
+        mark(x, y, 2, '*');
+    }
+
+    let border = format!("+{}+\n", "-".repeat(cols));
+    let mut out = border.clone();
+    for row in grid.chunks(cols) {
+        out.push('|');
+        out.extend(row.iter().map(|&(_, glyph)| glyph));
+        out.push_str("|\n");
+    }
+    out.push_str(&border);
+    out
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparkline_scales_and_buckets() {
+        assert_eq!(sparkline(&[0.0, 7.0], 10), "▁█");
+        assert_eq!(sparkline(&[1.0, 1.0, f32::NAN], 10), "▅▅ ");
+        let long: Vec<f32> = (0..100).map(|v| v as f32).collect();
+        let line = sparkline(&long, 10);
+        assert_eq!(line.chars().count(), 10);
+        assert!(line.starts_with('▁') && line.ends_with('█'));
+    }
+
+    #[test]
+    fn world_is_scaled_with_priorities() {
+        let world = WorldSnapshot {
+            width: 4,
+            height: 2,
+            food: vec![(0, 0), (3, 1)],
+            creatures: vec![(0, 0, false), (3, 1, true), (1, 0, false)],
+        };
+        assert_eq!(render_world(&world, 2, 2), "+--+\n|* |\n| @|\n+--+\n");
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+
+use crate::brain::BrainGenome;
+use crate::creature::Creature;
+use crate::genome::{Genome, Recurrence};
+use crate::neat::compatibility;
+
+/// Largest group whose pairwise distances are all computed; bigger groups are
+/// thinned to every n-th member so reports stay cheap for large populations.
+const MAX_DISTANCE_GROUP: usize = 100;
+
+/// Label grouping creatures whose genomes can be compared gene by gene:
+/// layer sizes for layered genomes (e.g. `6-8-4+elman`), hidden node count for
+/// graph genomes.
+pub fn architecture_label(creature: &Creature) -> String {
+    match creature.brain.genome() {
+        Some(BrainGenome::Layered(genome)) => {
+            let sizes: Vec<String> = genome.layers.iter().map(usize::to_string).collect();
+            let suffix = match genome.recurrence {
+                Recurrence::None => "",
+                Recurrence::Elman => "+elman",
+                Recurrence::Jordan => "+jordan",
+            };
+            format!("{}{suffix}", sizes.join("-"))
+        }
+        Some(BrainGenome::Graph(genome)) => format!("graph-h{}", genome.hidden_count()),
+        None => "hand-written".to_string(),
+    }
+}
+
+/// Root-mean-square weight difference of two genomes with the same layout.
+fn layered_distance(a: &Genome, b: &Genome) -> f32 {
+    let n = a.weights.len().max(1) as f32;
+    let sum: f32 = a
+        .weights
+        .iter()
+        .zip(&b.weights)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum();
+    (sum / n).sqrt()
+}
+
+/// Distance between two creatures of the same architecture group, or `None`
+/// when their brains have no comparable genome.
+pub fn genome_distance(a: &Creature, b: &Creature) -> Option<f32> {
+    match (a.brain.genome()?, b.brain.genome()?) {
+        (BrainGenome::Layered(a), BrainGenome::Layered(b))
+            if a.weights.len() == b.weights.len() =>
+        {
+            Some(layered_distance(a, b))
+        }
+        (BrainGenome::Graph(a), BrainGenome::Graph(b)) => Some(compatibility(a, b)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diversity {
+    pub architecture_counts: BTreeMap<String, u32>,
+    /// Mean pairwise genome distance within each architecture group of at
+    /// least two members.
+    pub distance_by_arch: BTreeMap<String, f32>,
+    /// Mean over all compared pairs, across groups.
+    pub distance_mean: Option<f32>,
+}
+
+impl Diversity {
+    pub fn of(population: &[Creature]) -> Self {
+        let mut groups: BTreeMap<String, Vec<&Creature>> = BTreeMap::new();
+        for creature in population {
+            groups
+                .entry(architecture_label(creature))
+                .or_default()
+                .push(creature);
+        }
+        let mut diversity = Self::default();
+        let (mut total, mut pairs) = (0.0f64, 0usize);
+        for (label, members) in groups {
+            diversity
+                .architecture_counts
+                .insert(label.clone(), members.len() as u32);
+            let stride = members.len().div_ceil(MAX_DISTANCE_GROUP);
+            let sample: Vec<&Creature> = members.into_iter().step_by(stride.max(1)).collect();
+            let (mut group_total, mut group_pairs) = (0.0f64, 0usize);
+            for (idx, a) in sample.iter().enumerate() {
+                for b in &sample[idx + 1..] {
+                    if let Some(distance) = genome_distance(a, b) {
+                        group_total += distance as f64;
+                        group_pairs += 1;
+                    }
+                }
+            }
+            if group_pairs > 0 {
+                diversity
+                    .distance_by_arch
+                    .insert(label, (group_total / group_pairs as f64) as f32);
+                total += group_total;
+                pairs += group_pairs;
+            }
+        }
+        diversity.distance_mean = (pairs > 0).then(|| (total / pairs as f64) as f32);
+        diversity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neural_net::NeuralNet;
+
+    fn creature(layers: Vec<usize>, weight: f32) -> Creature {
+        let size = crate::genome::genome_size_from_layers(&layers);
+        let genome = Genome {
+            layers,
+            weights: vec![weight; size],
+            recurrence: Recurrence::None,
+            activations: Vec::new(),
+        };
+        Creature::from_brain(NeuralNet::new(genome), 0, 0, 0.0)
+    }
+
+    #[test]
+    fn groups_by_architecture_and_averages_distances() {
+        let population = [
+            creature(vec![2, 3, 2], 0.0),
+            creature(vec![2, 3, 2], 1.0),
+            creature(vec![2, 3, 2], 2.0),
+            creature(vec![2, 4, 2], 0.5),
+        ];
+        let diversity = Diversity::of(&population);
+        assert_eq!(diversity.architecture_counts.len(), 2);
+        assert_eq!(diversity.architecture_counts["2-3-2"], 3);
+        // Pairs at distance 1, 1 and 2.
+        let mean = diversity.distance_by_arch["2-3-2"];
+        assert!((mean - 4.0 / 3.0).abs() < 1e-6);
+        assert!(!diversity.distance_by_arch.contains_key("2-4-2"));
+        assert_eq!(diversity.distance_mean, Some(mean));
+    }
+}
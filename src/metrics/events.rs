@@ -0,0 +1,54 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Start,
+    Checkpoint,
+    /// Every creature died before `max_steps`.
+    Extinction,
+    /// Creatures copied between `--islands`.
+    Migration,
+    Error,
+    Finish,
+}
+
+impl EventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventKind::Start => "start",
+            EventKind::Checkpoint => "checkpoint",
+            EventKind::Extinction => "extinction",
+            EventKind::Migration => "migration",
+            EventKind::Error => "error",
+            EventKind::Finish => "finish",
+        }
+    }
+}
+
+/// One line of the run event log.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunEvent {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub kind: EventKind,
+    pub generation: Option<u32>,
+    pub message: String,
+}
+
+impl RunEvent {
+    pub fn now(kind: EventKind, generation: Option<u32>, message: impl Into<String>) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0);
+        Self {
+            timestamp_ms,
+            kind,
+            generation,
+            message: message.into(),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::error::Error;
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::evolution::nsga2::ParetoFront;
+use crate::metrics::events::RunEvent;
+use crate::metrics::report::GenerationReport;
+use crate::metrics::sink::{LineageRecord, MetricsSink};
+
+/// A line of the NDJSON stream, tagged by its `record` field.
+#[derive(Serialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum Record<'a> {
+    Generation(&'a GenerationReport),
+    ParetoFront(&'a ParetoFront),
+    Lineage(&'a LineageRecord),
+    Event(&'a RunEvent),
+}
+
+/// Appends every generation report, Pareto front, lineage entry and run event
+/// to one newline-delimited JSON file, flushed after each generation.
+#[derive(Debug)]
+pub struct NdjsonSink {
+    writer: BufWriter<File>,
+}
+
+impl NdjsonSink {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    fn write(&mut self, record: Record<'_>) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer(&mut self.writer, &record)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+impl MetricsSink for NdjsonSink {
+    fn name(&self) -> &'static str {
+        "ndjson"
+    }
+
+    fn record(&mut self, report: &GenerationReport) -> Result<(), Box<dyn Error>> {
+        self.write(Record::Generation(report))?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn record_pareto_front(&mut self, front: &ParetoFront) -> Result<(), Box<dyn Error>> {
+        self.write(Record::ParetoFront(front))
+    }
+
+    fn record_lineage(&mut self, lineage: &[LineageRecord]) -> Result<(), Box<dyn Error>> {
+        for entry in lineage {
+            self.write(Record::Lineage(entry))?;
+        }
+        Ok(())
+    }
+
+    fn record_event(&mut self, event: &RunEvent) -> Result<(), Box<dyn Error>> {
+        self.write(Record::Event(event))?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::events::EventKind;
+
+    #[test]
+    fn writes_one_tagged_record_per_line() {
+        let path = std::env::temp_dir().join(format!("evobrain_ndjson_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let mut sink = NdjsonSink::open(&path).expect("open sink");
+            sink.record_event(&RunEvent::now(EventKind::Start, None, "seed 1"))
+                .unwrap();
+            sink.record_lineage(&[
+                LineageRecord {
+                    id: 3,
+                    generation: 1,
+                    parent_a: Some(0),
+                    parent_b: Some(2),
+                },
+                LineageRecord {
+                    id: 4,
+                    generation: 1,
+                    parent_a: Some(1),
+                    parent_b: Some(1),
+                },
+            ])
+            .unwrap();
+            sink.record_event(&RunEvent::now(EventKind::Finish, None, "done"))
+                .unwrap();
+        }
+        let content = std::fs::read_to_string(&path).expect("read ndjson");
+        let _ = std::fs::remove_file(&path);
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("valid json line"))
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["record"], "event");
+        assert_eq!(lines[0]["kind"], "start");
+        assert_eq!(lines[1]["record"], "lineage");
+        assert_eq!(lines[1]["parent_b"], 2);
+        assert_eq!(lines[3]["message"], "done");
+    }
+}
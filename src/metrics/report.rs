@@ -1,11 +1,17 @@
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 
+use crate::action::ActionSpace;
+use crate::baselines::BaselineScores;
 use crate::config::{Config, SelectionMethod};
 use crate::creature::Creature;
-use crate::fitness::compute_fitness;
+use crate::evolution::nsga2::ParetoFront;
+use crate::evolution::qd::ArchiveStats;
+use crate::fitness::fitness_of;
 use crate::metrics::collector::MetricsCollector;
+use crate::metrics::diversity::Diversity;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndividualSummary {
@@ -18,6 +24,38 @@ pub struct IndividualSummary {
     pub hidden: Option<u32>,
 }
 
+/// One island's share of a generation under `--islands`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IslandSummary {
+    pub island: u32,
+    pub population_size: u32,
+    pub fitness_best: f32,
+    pub fitness_mean: f32,
+    pub food_eaten_total: u32,
+    pub survivors: u32,
+}
+
+impl IslandSummary {
+    pub fn of(island: u32, population: &[Creature], config: &Config) -> Self {
+        let fitness: Vec<f32> = population
+            .iter()
+            .map(|creature| fitness_of(creature, config))
+            .filter(|fitness| fitness.is_finite())
+            .collect();
+        Self {
+            island,
+            population_size: population.len() as u32,
+            fitness_best: fitness.iter().copied().reduce(f32::max).unwrap_or(0.0),
+            fitness_mean: mean(&fitness),
+            food_eaten_total: population
+                .iter()
+                .map(|creature| creature.food_collected)
+                .sum(),
+            survivors: population.iter().filter(|creature| creature.alive).count() as u32,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenerationReport {
     pub generation: u32,
@@ -28,9 +66,34 @@ pub struct GenerationReport {
     pub fitness_median: f32,
     pub fitness_std: f32,
     pub fitness_iqr: f32,
+    /// Creatures with a NaN or infinite fitness; the fitness statistics above
+    /// only cover finite values.
+    pub non_finite_fitness: u32,
+    /// NaN or infinite weights and biases across the population.
+    pub non_finite_params: u32,
+    /// Mean fitness of each `--baselines` controller on this generation's world.
+    pub baseline_random: Option<f32>,
+    pub baseline_greedy: Option<f32>,
+    pub baseline_shortest_path: Option<f32>,
+    /// Mean fitness on the baselines' world as a percentage of the greedy
+    /// baseline.
+    pub percent_of_greedy: Option<f32>,
+    /// Size and hypervolume of the first front under `--selection nsga2`.
+    pub pareto_front_size: Option<u32>,
+    pub hypervolume: Option<f64>,
+    /// Archive state under `--search novelty` or `map-elites`.
+    pub novelty_mean: Option<f32>,
+    pub archive_size: Option<u32>,
+    pub archive_coverage: Option<f64>,
+    pub qd_score: Option<f64>,
+    /// One entry per island when `--islands` is above 1.
+    #[serde(default)]
+    pub islands: Vec<IslandSummary>,
     pub food_eaten_total: u32,
     pub food_eaten_mean: f32,
     pub survival_steps_mean: f32,
+    pub age_mean: f32,
+    pub survivors: u32,
     pub reproductions_total: u32,
     pub params_mean: f32,
     pub params_median: f32,
@@ -38,6 +101,27 @@ pub struct GenerationReport {
     pub params_std: f32,
     pub layers_mean: Option<f32>,
     pub hidden_mean: Option<f32>,
+    /// Number of hidden layers using each activation across the population.
+    pub activation_counts: BTreeMap<String, u32>,
+    /// Creatures per architecture group (see `diversity::architecture_label`).
+    pub architecture_counts: BTreeMap<String, u32>,
+    pub unique_architectures: u32,
+    /// Mean pairwise genome distance, overall and within each group.
+    pub genome_distance_mean: Option<f32>,
+    pub genome_distance_by_arch: BTreeMap<String, f32>,
+    /// Share of all actions taken this episode, per action.
+    pub action_frequencies: BTreeMap<String, f32>,
+    /// Fraction of world cells visited by at least one creature.
+    pub visited_cell_coverage: f32,
+    /// `survived`, `starved_unfed` (never ate), `starved_after_eating` or,
+    /// under `--mode open-ended`, `old_age`.
+    pub death_causes: BTreeMap<String, u32>,
+    /// Energy left at the end of the episode.
+    pub energy_mean: f32,
+    pub energy_median: f32,
+    pub energy_std: f32,
+    pub energy_min: f32,
+    pub energy_max: f32,
     pub mutation_rate: f32,
     pub mutation_sigma: f32,
     pub crossover_rate: Option<f32>,
@@ -50,6 +134,61 @@ pub struct GenerationReport {
     pub individuals: Option<Vec<IndividualSummary>>,
 }
 
+impl GenerationReport {
+    /// `fitness_mean` is the mean fitness on the baselines' world, which
+    /// under `--islands` holds only the first island.
+    pub fn set_baselines(&mut self, scores: &BaselineScores, fitness_mean: f32) {
+        self.baseline_random = scores.random;
+        self.baseline_greedy = scores.greedy;
+        self.baseline_shortest_path = scores.shortest_path;
+        self.percent_of_greedy = scores
+            .greedy
+            .filter(|greedy| *greedy != 0.0)
+            .map(|greedy| 100.0 * fitness_mean / greedy);
+    }
+}
+
+impl GenerationReport {
+    pub fn set_pareto_front(&mut self, front: &ParetoFront) {
+        self.pareto_front_size = Some(front.members.len() as u32);
+        self.hypervolume = Some(front.hypervolume);
+    }
+
+    pub fn set_archive(&mut self, stats: &ArchiveStats) {
+        self.novelty_mean = stats.novelty_mean;
+        self.archive_size = Some(stats.size);
+        self.archive_coverage = stats.coverage;
+        self.qd_score = stats.qd_score;
+    }
+}
+
+/// Non-finite values found in an evaluated population.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NonFiniteCounts {
+    pub fitness: u32,
+    pub params: u32,
+}
+
+impl NonFiniteCounts {
+    pub fn of(population: &[Creature], config: &Config) -> Self {
+        let mut counts = Self::default();
+        for creature in population {
+            if !fitness_of(creature, config).is_finite() {
+                counts.fitness += 1;
+            }
+            if let Some(genome) = creature.brain.genome() {
+                counts.params += genome.non_finite_params();
+            }
+        }
+        counts
+    }
+
+    pub fn any(&self) -> bool {
+        self.fitness > 0 || self.params > 0
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn build_generation_report(
     generation: u32,
     steps_per_gen: u32,
@@ -67,6 +206,7 @@ pub fn build_generation_report(
     let mut layers_values = Vec::with_capacity(population.len());
     let mut hidden_values = Vec::with_capacity(population.len());
     let mut survival_sum = 0.0;
+    let mut activation_counts = BTreeMap::new();
     let mut best_fitness = f32::MIN;
     let mut params_best = 0;
     let mut individuals = if include_individuals {
@@ -75,23 +215,32 @@ pub fn build_generation_report(
         None
     };
 
-    for (idx, creature) in population.iter().enumerate() {
-        let fitness = compute_fitness(creature, config);
+    for creature in population {
+        let fitness = fitness_of(creature, config);
         let params = creature.brain.param_count();
         let layers = creature.brain.layer_count();
         let hidden = creature.brain.hidden_count();
-        fitness_values.push(fitness);
+        // Best, mean and spread all cover finite values only; the rest are
+        // counted in `non_finite_fitness`.
+        if fitness.is_finite() {
+            fitness_values.push(fitness);
+            if fitness > best_fitness {
+                best_fitness = fitness;
+                params_best = params;
+            }
+        }
         params_values.push(params as f32);
         layers_values.push(layers as f32);
         hidden_values.push(hidden as f32);
         survival_sum += creature.survival_steps as f32;
-        if fitness > best_fitness {
-            best_fitness = fitness;
-            params_best = params;
+        for activation in creature.brain.hidden_activations() {
+            *activation_counts
+                .entry(activation.name().to_string())
+                .or_insert(0) += 1;
         }
         if let Some(ref mut list) = individuals {
             list.push(IndividualSummary {
-                id: idx as u64,
+                id: creature.id,
                 fitness,
                 food_eaten: creature.food_collected,
                 survival_steps: creature.survival_steps,
@@ -103,6 +252,14 @@ pub fn build_generation_report(
     }
 
     let population_size = population.len() as u32;
+    let diversity = Diversity::of(population);
+    let energies: Vec<f32> = population.iter().map(|creature| creature.energy).collect();
+    let ages: Vec<f32> = population
+        .iter()
+        .map(|creature| creature.age as f32)
+        .collect();
+    let energy_mean = mean(&energies);
+    let non_finite = NonFiniteCounts::of(population, config);
     let fitness_mean = mean(&fitness_values);
     let fitness_median = median(&fitness_values);
     let fitness_std = std_dev(&fitness_values, fitness_mean);
@@ -125,11 +282,7 @@ pub fn build_generation_report(
     };
 
     let individuals = individuals.map(|mut list| {
-        list.sort_by(|a, b| {
-            b.fitness
-                .partial_cmp(&a.fitness)
-                .unwrap_or(Ordering::Equal)
-        });
+        list.sort_by(|a, b| b.fitness.partial_cmp(&a.fitness).unwrap_or(Ordering::Equal));
         list.into_iter().take(top_n).collect()
     });
 
@@ -137,14 +290,33 @@ pub fn build_generation_report(
         generation,
         steps_per_gen,
         population_size,
-        fitness_best: if best_fitness == f32::MIN { 0.0 } else { best_fitness },
+        fitness_best: if best_fitness == f32::MIN {
+            0.0
+        } else {
+            best_fitness
+        },
         fitness_mean,
         fitness_median,
         fitness_std,
         fitness_iqr,
+        non_finite_fitness: non_finite.fitness,
+        non_finite_params: non_finite.params,
+        baseline_random: None,
+        baseline_greedy: None,
+        baseline_shortest_path: None,
+        percent_of_greedy: None,
+        pareto_front_size: None,
+        hypervolume: None,
+        novelty_mean: None,
+        archive_size: None,
+        archive_coverage: None,
+        qd_score: None,
+        islands: Vec::new(),
         food_eaten_total,
         food_eaten_mean,
         survival_steps_mean,
+        age_mean: mean(&ages),
+        survivors: population.iter().filter(|creature| creature.alive).count() as u32,
         reproductions_total: collector.reproductions_total(),
         params_mean,
         params_median,
@@ -152,11 +324,27 @@ pub fn build_generation_report(
         params_std,
         layers_mean,
         hidden_mean,
+        activation_counts,
+        unique_architectures: diversity.architecture_counts.len() as u32,
+        architecture_counts: diversity.architecture_counts,
+        genome_distance_mean: diversity.distance_mean,
+        genome_distance_by_arch: diversity.distance_by_arch,
+        action_frequencies: action_frequencies(population, config),
+        visited_cell_coverage: collector.visited_cell_coverage(),
+        death_causes: death_causes(population),
+        energy_mean,
+        energy_median: median(&energies),
+        energy_std: std_dev(&energies, energy_mean),
+        energy_min: energies.iter().copied().reduce(f32::min).unwrap_or(0.0),
+        energy_max: energies.iter().copied().reduce(f32::max).unwrap_or(0.0),
         mutation_rate: config.mut_rate,
         mutation_sigma: config.mut_strength,
         crossover_rate: None,
         selection_mode: config.selection_method,
-        tournament_k: if matches!(config.selection_method, SelectionMethod::Tournament) {
+        tournament_k: if matches!(
+            config.selection_method,
+            SelectionMethod::Tournament | SelectionMethod::Nsga2
+        ) {
             Some(config.tournament_k)
         } else {
             None
@@ -169,11 +357,51 @@ pub fn build_generation_report(
     }
 }
 
+fn action_frequencies(population: &[Creature], config: &Config) -> BTreeMap<String, f32> {
+    let actions = ActionSpace::from_config(config);
+    let counts: Vec<u64> = actions
+        .actions()
+        .iter()
+        .map(|&action| {
+            population
+                .iter()
+                .map(|creature| creature.action_counts[action as usize] as u64)
+                .sum()
+        })
+        .collect();
+    let total = counts.iter().sum::<u64>().max(1) as f32;
+    actions
+        .actions()
+        .iter()
+        .zip(counts)
+        .map(|(action, count)| (action.name().to_string(), count as f32 / total))
+        .collect()
+}
+
+fn death_causes(population: &[Creature]) -> BTreeMap<String, u32> {
+    let mut causes = BTreeMap::new();
+    for creature in population {
+        let cause = if creature.alive {
+            "survived"
+        } else if creature.energy > 0.0 {
+            "old_age"
+        } else if creature.food_collected == 0 {
+            "starved_unfed"
+        } else {
+            "starved_after_eating"
+        };
+        *causes.entry(cause.to_string()).or_insert(0) += 1;
+    }
+    causes
+}
+
 fn mean(values: &[f32]) -> f32 {
     if values.is_empty() {
         return 0.0;
     }
-    let sum: f32 = values.iter().sum();
+    // Folding from +0.0 (not `Sum`, which starts at -0.0) keeps an all-zero
+    // column printing as `0`.
+    let sum = values.iter().fold(0.0, |acc, value| acc + value);
     sum / values.len() as f32
 }
 
@@ -192,7 +420,7 @@ fn median(values: &[f32]) -> f32 {
     let mut sorted = values.to_vec();
     sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
     let mid = sorted.len() / 2;
-    if sorted.len() % 2 == 0 {
+    if sorted.len().is_multiple_of(2) {
         (sorted[mid - 1] + sorted[mid]) / 2.0
     } else {
         sorted[mid]
@@ -225,3 +453,41 @@ fn iqr(values: &[f32]) -> f32 {
     let q3_idx = (3 * n) / 4;
     sorted[q3_idx] - sorted[q1_idx]
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::evolution::random_population;
+
+    #[test]
+    fn fitness_summary_skips_non_finite_values() {
+        let config = Config::try_parse_from(["evobrain", "--population=4", "--hidden=2"]).unwrap();
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut population = random_population(4, &config, &mut rng);
+        for (creature, fitness) in population
+            .iter_mut()
+            .zip([2.0, f32::INFINITY, 4.0, f32::NAN])
+        {
+            creature.fitness = Some(fitness);
+        }
+        let report = build_generation_report(
+            0,
+            1,
+            &population,
+            &MetricsCollector::new(),
+            &config,
+            "test",
+            "hash",
+            None,
+            false,
+            0,
+        );
+        assert_eq!(report.fitness_best, 4.0);
+        assert_eq!(report.fitness_mean, 3.0);
+        assert_eq!(report.non_finite_fitness, 2);
+    }
+}
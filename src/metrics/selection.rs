@@ -34,7 +34,7 @@ impl GenRange {
             return false;
         }
         let offset = gen - self.start;
-        offset % self.step == 0
+        offset.is_multiple_of(self.step)
     }
 }
 
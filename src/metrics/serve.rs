@@ -0,0 +1,308 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::metrics::events::RunEvent;
+use crate::metrics::report::GenerationReport;
+use crate::metrics::sink::{MetricsSink, RunSummary};
+use crate::metrics::writer::{RunIdentity, RunManifest};
+use crate::world::WorldSnapshot;
+
+const PAGE: &str = include_str!("serve/index.html");
+const HISTORY_LEN: usize = 1000;
+const EVENTS_LEN: usize = 1000;
+/// Generations whose `--serve-frames` frames are kept.
+const FRAME_GENERATIONS: usize = 4;
+/// Idle time after which an event stream sends a comment, so closed clients
+/// are noticed.
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// One point of `/api/history` and of the event stream.
+#[derive(Debug, Serialize)]
+struct HistoryPoint {
+    generation: u32,
+    fitness_best: f32,
+    fitness_mean: f32,
+    fitness_median: f32,
+    survivors: u32,
+    food_eaten_total: u32,
+    energy_mean: f32,
+    unique_architectures: u32,
+}
+
+impl From<&GenerationReport> for HistoryPoint {
+    fn from(report: &GenerationReport) -> Self {
+        Self {
+            generation: report.generation,
+            fitness_best: report.fitness_best,
+            fitness_mean: report.fitness_mean,
+            fitness_median: report.fitness_median,
+            survivors: report.survivors,
+            food_eaten_total: report.food_eaten_total,
+            energy_mean: report.energy_mean,
+            unique_architectures: report.unique_architectures,
+        }
+    }
+}
+
+/// Everything the server hands out, pre-serialized by the simulation thread.
+#[derive(Debug, Default)]
+struct State {
+    manifest: String,
+    report: Option<String>,
+    /// `(sequence number, HistoryPoint JSON)`, oldest first.
+    history: VecDeque<(u64, String)>,
+    next_seq: u64,
+    events: VecDeque<String>,
+    world: Option<String>,
+    frames: VecDeque<(u32, String)>,
+    finished: Option<String>,
+}
+
+type Shared = Arc<(Mutex<State>, Condvar)>;
+
+fn lock(shared: &Shared) -> MutexGuard<'_, State> {
+    shared.0.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// `--serve`: a read-only HTTP endpoint on a background thread. Serves a
+/// plotting page at `/`, JSON under `/api/` (`manifest`, `report`, `history`,
+/// `events`, `world`, `frames`, `frames/<generation>`) and a server-sent event
+/// stream of generations at `/api/stream`. The simulation only publishes
+/// serialized copies, so it runs exactly as without the server.
+#[derive(Debug)]
+pub struct ServeSink {
+    shared: Shared,
+    addr: SocketAddr,
+}
+
+impl ServeSink {
+    pub fn bind(
+        addr: &str,
+        config: &Config,
+        identity: &RunIdentity,
+    ) -> Result<Self, Box<dyn Error>> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let state = State {
+            manifest: serde_json::to_string(&RunManifest::new(config, identity))?,
+            ..State::default()
+        };
+        let shared: Shared = Arc::new((Mutex::new(state), Condvar::new()));
+        let server = shared.clone();
+        thread::Builder::new()
+            .name("evobrain-serve".to_string())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let shared = server.clone();
+                    thread::spawn(move || {
+                        let _ = handle(stream, &shared);
+                    });
+                }
+            })?;
+        Ok(Self { shared, addr })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut State)) {
+        apply(&mut lock(&self.shared));
+        self.shared.1.notify_all();
+    }
+}
+
+impl MetricsSink for ServeSink {
+    fn name(&self) -> &'static str {
+        "server"
+    }
+
+    fn record(&mut self, report: &GenerationReport) -> Result<(), Box<dyn Error>> {
+        let full = serde_json::to_string(report)?;
+        let point = serde_json::to_string(&HistoryPoint::from(report))?;
+        self.update(|state| {
+            state.report = Some(full);
+            state.history.push_back((state.next_seq, point));
+            state.next_seq += 1;
+            if state.history.len() > HISTORY_LEN {
+                state.history.pop_front();
+            }
+        });
+        Ok(())
+    }
+
+    fn record_event(&mut self, event: &RunEvent) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string(event)?;
+        self.update(|state| {
+            state.events.push_back(json);
+            if state.events.len() > EVENTS_LEN {
+                state.events.pop_front();
+            }
+        });
+        Ok(())
+    }
+
+    fn record_world(&mut self, world: &WorldSnapshot) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string(world)?;
+        self.update(|state| state.world = Some(json));
+        Ok(())
+    }
+
+    fn record_frames(
+        &mut self,
+        generation: u32,
+        frames: &[WorldSnapshot],
+    ) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string(frames)?;
+        self.update(|state| {
+            state.frames.push_back((generation, json));
+            if state.frames.len() > FRAME_GENERATIONS {
+                state.frames.pop_front();
+            }
+        });
+        Ok(())
+    }
+
+    fn finish(&mut self, summary: &RunSummary<'_>) -> Result<(), Box<dyn Error>> {
+        let message = match summary.aborted {
+            Some(reason) => format!("aborted: {reason}"),
+            None => format!("finished {} generations", summary.total_generations),
+        };
+        self.update(|state| state.finished = Some(message));
+        Ok(())
+    }
+}
+
+fn handle(mut stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are not needed; read past them.
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("/").split('?').next().unwrap_or("/");
+    if method != "GET" {
+        return respond(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            "read-only\n",
+        );
+    }
+    if path == "/api/stream" {
+        return stream_generations(stream, shared);
+    }
+
+    let body = {
+        let state = lock(shared);
+        match path {
+            "/" | "/index.html" => Some(("text/html; charset=utf-8", PAGE.to_string())),
+            "/api/manifest" => Some(("application/json", state.manifest.clone())),
+            "/api/report" => Some((
+                "application/json",
+                state.report.clone().unwrap_or_else(|| "null".to_string()),
+            )),
+            "/api/history" => Some((
+                "application/json",
+                json_array(state.history.iter().map(|(_, json)| json.as_str())),
+            )),
+            "/api/events" => Some((
+                "application/json",
+                json_array(state.events.iter().map(String::as_str)),
+            )),
+            "/api/world" => Some((
+                "application/json",
+                state.world.clone().unwrap_or_else(|| "null".to_string()),
+            )),
+            "/api/frames" => Some((
+                "application/json",
+                serde_json::to_string(
+                    &state.frames.iter().map(|(gen, _)| *gen).collect::<Vec<_>>(),
+                )
+                .unwrap_or_default(),
+            )),
+            _ => path
+                .strip_prefix("/api/frames/")
+                .and_then(|gen| gen.parse::<u32>().ok())
+                .and_then(|gen| state.frames.iter().find(|(g, _)| *g == gen))
+                .map(|(_, json)| ("application/json", json.clone())),
+        }
+    };
+    match body {
+        Some((content_type, body)) => respond(&mut stream, "200 OK", content_type, &body),
+        None => respond(&mut stream, "404 Not Found", "text/plain", "not found\n"),
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Sends every generation recorded after the client connected as a
+/// `generation` event, then a `finish` event when the run ends.
+fn stream_generations(mut stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nAccess-Control-Allow-Origin: *\r\n\r\n",
+    )?;
+    stream.flush()?;
+    let mut seen = lock(shared).next_seq;
+    loop {
+        let (pending, finished) = {
+            let mut state = lock(shared);
+            while state.next_seq == seen && state.finished.is_none() {
+                let (next, timeout) = shared
+                    .1
+                    .wait_timeout(state, KEEPALIVE)
+                    .unwrap_or_else(PoisonError::into_inner);
+                state = next;
+                if timeout.timed_out() && state.next_seq == seen && state.finished.is_none() {
+                    drop(state);
+                    stream.write_all(b": keepalive\n\n")?;
+                    stream.flush()?;
+                    state = lock(shared);
+                }
+            }
+            let pending: Vec<String> = state
+                .history
+                .iter()
+                .filter(|(seq, _)| *seq >= seen)
+                .map(|(_, json)| json.clone())
+                .collect();
+            seen = state.next_seq;
+            (pending, state.finished.clone())
+        };
+        for json in pending {
+            write!(stream, "event: generation\ndata: {json}\n\n")?;
+        }
+        if let Some(message) = finished {
+            let message = serde_json::to_string(&message).unwrap_or_default();
+            write!(stream, "event: finish\ndata: {message}\n\n")?;
+            return stream.flush();
+        }
+        stream.flush()?;
+    }
+}
+
+fn json_array<'a>(items: impl Iterator<Item = &'a str>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(","))
+}
@@ -0,0 +1,383 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use serde::Serialize;
+
+use crate::action::ActionSpace;
+use crate::config::{Config, LoggingMode};
+use crate::creature::Creature;
+use crate::evolution::nsga2::ParetoFront;
+use crate::evolution::qd::ArchiveExport;
+use crate::metrics::events::{EventKind, RunEvent};
+use crate::metrics::report::GenerationReport;
+use crate::metrics::writer::Champion;
+use crate::metrics::GenerationMetrics;
+use crate::sensors::SensorSuite;
+use crate::world::WorldSnapshot;
+
+/// End-of-run state handed to every sink's `finish`.
+#[derive(Debug, Clone, Copy)]
+pub struct RunSummary<'a> {
+    pub total_generations: usize,
+    pub aborted: Option<&'a str>,
+    pub champion: Option<&'a Champion>,
+    pub archive: Option<&'a ArchiveExport>,
+}
+
+/// Where a creature came from, recorded once for the generation it lives in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LineageRecord {
+    pub id: u64,
+    pub generation: u32,
+    pub parent_a: Option<u64>,
+    pub parent_b: Option<u64>,
+}
+
+impl LineageRecord {
+    pub fn of(generation: u32, population: &[Creature]) -> Vec<Self> {
+        population
+            .iter()
+            .map(|creature| Self {
+                id: creature.id,
+                generation,
+                parent_a: creature.parents.map(|(a, _)| a),
+                parent_b: creature.parents.map(|(_, b)| b),
+            })
+            .collect()
+    }
+}
+
+/// A consumer of the per-generation [`GenerationReport`]s. Every output of a
+/// run is a sink fed from the same report, so they cannot disagree.
+pub trait MetricsSink {
+    /// Names the sink in error messages.
+    fn name(&self) -> &'static str;
+
+    fn record(&mut self, report: &GenerationReport) -> Result<(), Box<dyn Error>>;
+
+    fn record_pareto_front(&mut self, _front: &ParetoFront) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn record_lineage(&mut self, _lineage: &[LineageRecord]) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn record_event(&mut self, _event: &RunEvent) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// The world at the end of the episode; only sent under `--tui` or
+    /// `--serve`.
+    fn record_world(&mut self, _world: &WorldSnapshot) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// The best individual of a `--save-best` generation.
+    fn record_best(&mut self, _best: &Champion) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// The world after every step of a `--serve-frames` generation.
+    fn record_frames(
+        &mut self,
+        _generation: u32,
+        _frames: &[WorldSnapshot],
+    ) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn finish(&mut self, _summary: &RunSummary<'_>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Fans every record out to a list of sinks. A sink that fails is reported as
+/// an `error` event to the other sinks, and on stderr only if that fails too;
+/// the run carries on.
+pub struct SinkSet<'s> {
+    sinks: Vec<&'s mut dyn MetricsSink>,
+}
+
+impl<'s> SinkSet<'s> {
+    pub fn new(sinks: Vec<&'s mut dyn MetricsSink>) -> Self {
+        Self { sinks }
+    }
+
+    pub fn event(&mut self, event: RunEvent) {
+        let mut failures = Vec::new();
+        for (idx, sink) in self.sinks.iter_mut().enumerate() {
+            if let Err(err) = sink.record_event(&event) {
+                failures.push((
+                    idx,
+                    format!(
+                        "Failed to write {} event to {}: {err}",
+                        event.kind.as_str(),
+                        sink.name()
+                    ),
+                ));
+            }
+        }
+        self.report(event.generation, failures);
+    }
+
+    /// Sends an `error` event to every sink.
+    pub fn error(&mut self, generation: Option<u32>, message: String) {
+        self.deliver_error(None, &RunEvent::now(EventKind::Error, generation, message));
+    }
+
+    pub fn world(&mut self, world: &WorldSnapshot) {
+        let mut failures = Vec::new();
+        for (idx, sink) in self.sinks.iter_mut().enumerate() {
+            if let Err(err) = sink.record_world(world) {
+                failures.push((
+                    idx,
+                    format!("Failed to send world to {}: {err}", sink.name()),
+                ));
+            }
+        }
+        self.report(None, failures);
+    }
+
+    pub fn best(&mut self, best: &Champion) {
+        let mut failures = Vec::new();
+        for (idx, sink) in self.sinks.iter_mut().enumerate() {
+            if let Err(err) = sink.record_best(best) {
+                failures.push((
+                    idx,
+                    format!("Failed to save best brain to {}: {err}", sink.name()),
+                ));
+            }
+        }
+        self.report(Some(best.generation), failures);
+    }
+
+    pub fn frames(&mut self, generation: u32, frames: &[WorldSnapshot]) {
+        let mut failures = Vec::new();
+        for (idx, sink) in self.sinks.iter_mut().enumerate() {
+            if let Err(err) = sink.record_frames(generation, frames) {
+                failures.push((
+                    idx,
+                    format!("Failed to send frames to {}: {err}", sink.name()),
+                ));
+            }
+        }
+        self.report(Some(generation), failures);
+    }
+
+    pub fn record(
+        &mut self,
+        report: &GenerationReport,
+        front: Option<&ParetoFront>,
+        lineage: &[LineageRecord],
+    ) {
+        let mut failures = Vec::new();
+        for (idx, sink) in self.sinks.iter_mut().enumerate() {
+            if let Some(front) = front {
+                if let Err(err) = sink.record_pareto_front(front) {
+                    failures.push((
+                        idx,
+                        format!("Failed to write Pareto front to {}: {err}", sink.name()),
+                    ));
+                }
+            }
+            if let Err(err) = sink.record_lineage(lineage) {
+                failures.push((
+                    idx,
+                    format!("Failed to write lineage to {}: {err}", sink.name()),
+                ));
+            }
+            if let Err(err) = sink.record(report) {
+                failures.push((
+                    idx,
+                    format!(
+                        "Failed to write generation report to {}: {err}",
+                        sink.name()
+                    ),
+                ));
+            }
+        }
+        self.report(Some(report.generation), failures);
+    }
+
+    /// Sends each failure as an `error` event to every sink but the one that
+    /// failed.
+    fn report(&mut self, generation: Option<u32>, failures: Vec<(usize, String)>) {
+        for (failed, message) in failures {
+            let event = RunEvent::now(EventKind::Error, generation, message);
+            self.deliver_error(Some(failed), &event);
+        }
+    }
+
+    fn deliver_error(&mut self, skip: Option<usize>, event: &RunEvent) {
+        for (idx, sink) in self.sinks.iter_mut().enumerate() {
+            if Some(idx) == skip {
+                continue;
+            }
+            if let Err(err) = sink.record_event(event) {
+                eprintln!(
+                    "{} (and failed to log it to {}: {err})",
+                    event.message,
+                    sink.name()
+                );
+            }
+        }
+    }
+
+    /// Logs the `finish` event, then lets every sink write its final output.
+    /// Returns the sinks that failed to.
+    pub fn finish(mut self, summary: &RunSummary<'_>) -> Vec<String> {
+        let message = match summary.aborted {
+            Some(reason) => format!(
+                "aborted after {} generations: {reason}",
+                summary.total_generations
+            ),
+            None => format!("completed {} generations", summary.total_generations),
+        };
+        self.event(RunEvent::now(EventKind::Finish, None, message));
+        let mut errors = Vec::new();
+        for sink in self.sinks.iter_mut() {
+            if let Err(err) = sink.finish(summary) {
+                errors.push(format!("failed to write {}: {err}", sink.name()));
+            }
+        }
+        errors
+    }
+}
+
+impl From<&GenerationReport> for GenerationMetrics {
+    fn from(report: &GenerationReport) -> Self {
+        Self {
+            generation: report.generation as usize,
+            avg_fitness: report.fitness_mean,
+            max_fitness: report.fitness_best,
+            avg_age: report.age_mean,
+            survivors: report.survivors as usize,
+            avg_energy: report.energy_mean,
+            food_eaten_total: report.food_eaten_total as usize,
+        }
+    }
+}
+
+/// The per-generation rows of a run, as returned in its `SimulationResult`.
+/// With `--logging quick` only the first generation and the last
+/// `quick-keep - 1` are kept.
+#[derive(Debug, Clone)]
+pub struct MetricsRows {
+    quick_keep: Option<usize>,
+    head: Option<GenerationMetrics>,
+    rows: Vec<GenerationMetrics>,
+}
+
+impl MetricsRows {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            quick_keep: match config.logging_mode {
+                LoggingMode::Full => None,
+                LoggingMode::Quick => Some(config.quick_keep.saturating_sub(1) as usize),
+            },
+            head: None,
+            rows: Vec::new(),
+        }
+    }
+
+    /// The retained rows in generation order.
+    pub fn rows(&self) -> Vec<GenerationMetrics> {
+        self.head.iter().chain(&self.rows).cloned().collect()
+    }
+}
+
+impl MetricsSink for MetricsRows {
+    fn name(&self) -> &'static str {
+        "metrics rows"
+    }
+
+    fn record(&mut self, report: &GenerationReport) -> Result<(), Box<dyn Error>> {
+        let metrics = GenerationMetrics::from(report);
+        match self.quick_keep {
+            None => self.rows.push(metrics),
+            Some(_) if self.head.is_none() => self.head = Some(metrics),
+            Some(keep) => {
+                self.rows.push(metrics);
+                if self.rows.len() > keep {
+                    self.rows.remove(0);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `--out` CSV and `--run-metadata` JSON, holding the same rows as
+/// [`MetricsRows`].
+#[derive(Debug, Clone)]
+pub struct LegacyCsvSink<'a> {
+    config: &'a Config,
+    rows: MetricsRows,
+}
+
+impl<'a> LegacyCsvSink<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        Self {
+            config,
+            rows: MetricsRows::new(config),
+        }
+    }
+
+    fn write_csv(&self) -> Result<(), Box<dyn Error>> {
+        let mut wtr = csv::Writer::from_writer(BufWriter::new(File::create(&self.config.out)?));
+        for metric in self.rows.rows() {
+            wtr.serialize(metric)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+impl MetricsSink for LegacyCsvSink<'_> {
+    fn name(&self) -> &'static str {
+        "results csv"
+    }
+
+    fn record(&mut self, report: &GenerationReport) -> Result<(), Box<dyn Error>> {
+        self.rows.record(report)
+    }
+
+    fn finish(&mut self, summary: &RunSummary<'_>) -> Result<(), Box<dyn Error>> {
+        self.write_csv()?;
+        write_metadata(
+            &self.config.run_metadata,
+            self.config,
+            summary.total_generations,
+        )
+    }
+}
+
+fn write_metadata(
+    path: &str,
+    config: &Config,
+    total_generations: usize,
+) -> Result<(), Box<dyn Error>> {
+    #[derive(Serialize)]
+    struct RunMetadata<'a> {
+        config: &'a Config,
+        generations: usize,
+        nn_sizes: (usize, usize, usize),
+        inputs: Vec<String>,
+        outputs: Vec<String>,
+    }
+
+    let metadata = RunMetadata {
+        config,
+        generations: total_generations,
+        nn_sizes: config.nn_sizes(),
+        inputs: SensorSuite::from_config(config).input_names(),
+        outputs: ActionSpace::from_config(config).names(),
+    };
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, &metadata)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
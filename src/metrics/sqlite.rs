@@ -0,0 +1,182 @@
+use std::error::Error;
+use std::fs::create_dir_all;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::metrics::events::RunEvent;
+use crate::metrics::report::GenerationReport;
+use crate::metrics::sink::{LineageRecord, MetricsSink};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS generations (
+    generation INTEGER PRIMARY KEY,
+    fitness_best REAL,
+    fitness_mean REAL,
+    fitness_median REAL,
+    survivors INTEGER NOT NULL,
+    food_eaten_total INTEGER NOT NULL,
+    energy_mean REAL,
+    report TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS individuals (
+    generation INTEGER NOT NULL,
+    id INTEGER NOT NULL,
+    fitness REAL,
+    food_eaten INTEGER NOT NULL,
+    survival_steps INTEGER NOT NULL,
+    params INTEGER NOT NULL,
+    layers INTEGER,
+    hidden INTEGER,
+    PRIMARY KEY (generation, id)
+);
+CREATE TABLE IF NOT EXISTS lineage (
+    id INTEGER PRIMARY KEY,
+    generation INTEGER NOT NULL,
+    parent_a INTEGER,
+    parent_b INTEGER
+);
+CREATE TABLE IF NOT EXISTS events (
+    timestamp_ms INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    generation INTEGER,
+    message TEXT NOT NULL
+);
+";
+
+/// An SQLite database with one table per record kind: `generations` (key
+/// columns plus the full report as JSON), `individuals` (under full logging),
+/// `lineage` and `events`.
+#[derive(Debug)]
+pub struct SqliteSink {
+    conn: Connection,
+}
+
+impl SqliteSink {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+}
+
+impl MetricsSink for SqliteSink {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn record(&mut self, report: &GenerationReport) -> Result<(), Box<dyn Error>> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO generations VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                report.generation,
+                report.fitness_best,
+                report.fitness_mean,
+                report.fitness_median,
+                report.survivors,
+                report.food_eaten_total,
+                report.energy_mean,
+                serde_json::to_string(report)?,
+            ],
+        )?;
+        if let Some(individuals) = &report.individuals {
+            let mut insert = tx.prepare(
+                "INSERT OR REPLACE INTO individuals VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            for individual in individuals {
+                insert.execute(params![
+                    report.generation,
+                    individual.id,
+                    individual.fitness,
+                    individual.food_eaten,
+                    individual.survival_steps,
+                    individual.params,
+                    individual.layers,
+                    individual.hidden,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn record_lineage(&mut self, lineage: &[LineageRecord]) -> Result<(), Box<dyn Error>> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut insert =
+                tx.prepare("INSERT OR REPLACE INTO lineage VALUES (?1, ?2, ?3, ?4)")?;
+            for entry in lineage {
+                insert.execute(params![
+                    entry.id,
+                    entry.generation,
+                    entry.parent_a,
+                    entry.parent_b,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn record_event(&mut self, event: &RunEvent) -> Result<(), Box<dyn Error>> {
+        self.conn.execute(
+            "INSERT INTO events VALUES (?1, ?2, ?3, ?4)",
+            params![
+                event.timestamp_ms,
+                event.kind.as_str(),
+                event.generation,
+                event.message,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::events::EventKind;
+
+    #[test]
+    fn lineage_and_events_land_in_their_tables() {
+        let path = std::env::temp_dir().join(format!("evobrain_sqlite_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut sink = SqliteSink::open(&path).expect("open sink");
+        sink.record_lineage(&[LineageRecord {
+            id: 7,
+            generation: 2,
+            parent_a: Some(1),
+            parent_b: Some(4),
+        }])
+        .unwrap();
+        sink.record_event(&RunEvent::now(
+            EventKind::Extinction,
+            Some(2),
+            "None alive!",
+        ))
+        .unwrap();
+
+        let parents: (u64, u64) = sink
+            .conn
+            .query_row(
+                "SELECT parent_a, parent_b FROM lineage WHERE id = 7",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(parents, (1, 4));
+        let kind: String = sink
+            .conn
+            .query_row("SELECT kind FROM events WHERE generation = 2", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(kind, "extinction");
+        drop(sink);
+        let _ = std::fs::remove_file(&path);
+    }
+}
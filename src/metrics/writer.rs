@@ -1,4 +1,6 @@
+use std::collections::BTreeMap;
 use std::error::Error;
+use std::fmt;
 use std::fs::{create_dir_all, File, OpenOptions};
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
@@ -7,28 +9,139 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::Serialize;
 
+use crate::action::ActionSpace;
+use crate::brain::BrainSnapshot;
 use crate::config::Config;
-use crate::metrics::report::GenerationReport;
+use crate::creature::Creature;
+use crate::evolution::nsga2::ParetoFront;
+use crate::evolution::qd::ArchiveExport;
+use crate::metrics::events::RunEvent;
+use crate::metrics::report::{GenerationReport, IslandSummary};
+use crate::metrics::selection::{parse_gen_selection, GenSelection};
+use crate::metrics::sink::{MetricsSink, RunSummary};
+use crate::sensors::SensorSuite;
 
+/// Identifies a run in every report: its id, config hash and git revision.
+#[derive(Debug, Clone)]
+pub struct RunIdentity {
+    pub run_id: String,
+    pub config_hash: String,
+    pub git_commit: Option<String>,
+}
+
+impl RunIdentity {
+    pub fn new(config: &Config, run_id: String) -> Self {
+        Self {
+            run_id,
+            config_hash: hash_config(config),
+            git_commit: get_git_commit(),
+        }
+    }
+
+    /// Uses `--run-id`, or a fresh id derived from the seed and the time.
+    pub fn from_config(config: &Config) -> Self {
+        let run_id = config
+            .run_id
+            .clone()
+            .unwrap_or_else(|| default_run_id(config.seed));
+        Self::new(config, run_id)
+    }
+}
+
+/// The `runs/<id>/` directory: manifest, `generations.csv`, per-generation
+/// JSON for the `--log-gens` selection, Pareto fronts, champion, archive and
+/// the `events.ndjson` run event log.
 #[derive(Debug)]
 pub struct MetricsWriter {
     run_dir: PathBuf,
     csv_writer: csv::Writer<BufWriter<File>>,
-    config_hash: String,
-    run_id: String,
-    git_commit: Option<String>,
+    event_log: File,
+    log_selection: GenSelection,
+}
+
+/// Best individual seen over the whole run, written as `champion.json`.
+#[derive(Debug, Serialize)]
+pub struct Champion {
+    pub generation: u32,
+    pub fitness: f32,
+    pub brain: BrainSnapshot,
 }
 
+/// Population state written by `--fail-fast` before the run aborts.
+/// Non-finite numbers are serialised as `null`.
 #[derive(Debug, Serialize)]
-struct RunManifest<'a> {
+pub struct Checkpoint {
+    pub generation: u32,
+    pub reason: String,
+    pub population: Vec<CheckpointEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckpointEntry {
+    pub fitness: Option<f32>,
+    pub brain: BrainSnapshot,
+}
+
+impl Checkpoint {
+    pub fn new(generation: u32, reason: String, population: &[Creature]) -> Self {
+        Self {
+            generation,
+            reason,
+            population: population
+                .iter()
+                .map(|creature| CheckpointEntry {
+                    fitness: creature.fitness,
+                    brain: creature.brain.snapshot(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Writes `runs/<run_id>/checkpoint.json`, creating the run directory if
+/// metrics logging is off.
+pub fn write_checkpoint(run_id: &str, checkpoint: &Checkpoint) -> Result<PathBuf, Box<dyn Error>> {
+    let run_dir = PathBuf::from("runs").join(run_id);
+    create_dir_all(&run_dir)?;
+    let path = run_dir.join("checkpoint.json");
+    let mut writer = BufWriter::new(File::create(&path)?);
+    serde_json::to_writer_pretty(&mut writer, checkpoint)?;
+    writer.write_all(b"\n")?;
+    Ok(path)
+}
+
+/// `manifest.json`: what was run, when and from which revision.
+#[derive(Debug, Serialize)]
+pub struct RunManifest<'a> {
     run_id: &'a str,
     seed: u64,
     timestamp: String,
     config_hash: &'a str,
     git_commit: Option<&'a str>,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
     config: &'a Config,
 }
 
+impl<'a> RunManifest<'a> {
+    pub fn new(config: &'a Config, identity: &'a RunIdentity) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs().to_string())
+            .unwrap_or_else(|_| "0".to_string());
+        Self {
+            run_id: &identity.run_id,
+            seed: config.seed,
+            timestamp,
+            config_hash: &identity.config_hash,
+            git_commit: identity.git_commit.as_deref(),
+            inputs: SensorSuite::from_config(config).input_names(),
+            outputs: ActionSpace::from_config(config).names(),
+            config,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct GenerationReportCsvRow {
     generation: u32,
@@ -39,9 +152,25 @@ struct GenerationReportCsvRow {
     fitness_median: f32,
     fitness_std: f32,
     fitness_iqr: f32,
+    non_finite_fitness: u32,
+    non_finite_params: u32,
+    baseline_random: Option<f32>,
+    baseline_greedy: Option<f32>,
+    baseline_shortest_path: Option<f32>,
+    percent_of_greedy: Option<f32>,
+    pareto_front_size: Option<u32>,
+    hypervolume: Option<f64>,
+    novelty_mean: Option<f32>,
+    archive_size: Option<u32>,
+    archive_coverage: Option<f64>,
+    qd_score: Option<f64>,
+    island_fitness_best: String,
+    island_fitness_mean: String,
     food_eaten_total: u32,
     food_eaten_mean: f32,
     survival_steps_mean: f32,
+    age_mean: f32,
+    survivors: u32,
     reproductions_total: u32,
     params_mean: f32,
     params_median: f32,
@@ -49,6 +178,19 @@ struct GenerationReportCsvRow {
     params_std: f32,
     layers_mean: Option<f32>,
     hidden_mean: Option<f32>,
+    activation_counts: String,
+    architecture_counts: String,
+    unique_architectures: u32,
+    genome_distance_mean: Option<f32>,
+    genome_distance_by_arch: String,
+    action_frequencies: String,
+    visited_cell_coverage: f32,
+    death_causes: String,
+    energy_mean: f32,
+    energy_median: f32,
+    energy_std: f32,
+    energy_min: f32,
+    energy_max: f32,
     mutation_rate: f32,
     mutation_sigma: f32,
     crossover_rate: Option<f32>,
@@ -71,9 +213,25 @@ impl From<&GenerationReport> for GenerationReportCsvRow {
             fitness_median: report.fitness_median,
             fitness_std: report.fitness_std,
             fitness_iqr: report.fitness_iqr,
+            non_finite_fitness: report.non_finite_fitness,
+            non_finite_params: report.non_finite_params,
+            baseline_random: report.baseline_random,
+            baseline_greedy: report.baseline_greedy,
+            baseline_shortest_path: report.baseline_shortest_path,
+            percent_of_greedy: report.percent_of_greedy,
+            pareto_front_size: report.pareto_front_size,
+            hypervolume: report.hypervolume,
+            novelty_mean: report.novelty_mean,
+            archive_size: report.archive_size,
+            archive_coverage: report.archive_coverage,
+            qd_score: report.qd_score,
+            island_fitness_best: format_islands(report, |island| island.fitness_best),
+            island_fitness_mean: format_islands(report, |island| island.fitness_mean),
             food_eaten_total: report.food_eaten_total,
             food_eaten_mean: report.food_eaten_mean,
             survival_steps_mean: report.survival_steps_mean,
+            age_mean: report.age_mean,
+            survivors: report.survivors,
             reproductions_total: report.reproductions_total,
             params_mean: report.params_mean,
             params_median: report.params_median,
@@ -81,6 +239,19 @@ impl From<&GenerationReport> for GenerationReportCsvRow {
             params_std: report.params_std,
             layers_mean: report.layers_mean,
             hidden_mean: report.hidden_mean,
+            activation_counts: format_counts(&report.activation_counts),
+            architecture_counts: format_counts(&report.architecture_counts),
+            unique_architectures: report.unique_architectures,
+            genome_distance_mean: report.genome_distance_mean,
+            genome_distance_by_arch: format_counts(&report.genome_distance_by_arch),
+            action_frequencies: format_counts(&report.action_frequencies),
+            visited_cell_coverage: report.visited_cell_coverage,
+            death_causes: format_counts(&report.death_causes),
+            energy_mean: report.energy_mean,
+            energy_median: report.energy_median,
+            energy_std: report.energy_std,
+            energy_min: report.energy_min,
+            energy_max: report.energy_max,
             mutation_rate: report.mutation_rate,
             mutation_sigma: report.mutation_sigma,
             crossover_rate: report.crossover_rate,
@@ -95,12 +266,25 @@ impl From<&GenerationReport> for GenerationReportCsvRow {
 }
 
 impl MetricsWriter {
-    pub fn new(config: &Config, run_id: String) -> Result<Self, Box<dyn Error>> {
-        let config_hash = hash_config(config);
-        let git_commit = get_git_commit();
-        let run_dir = PathBuf::from("runs").join(&run_id);
+    /// Opens `runs/<run_id>/`.
+    pub fn new(config: &Config, identity: &RunIdentity) -> Result<Self, Box<dyn Error>> {
+        Self::open(
+            PathBuf::from("runs").join(&identity.run_id),
+            config,
+            identity,
+        )
+    }
+
+    /// Opens the run directory at `run_dir`, appending to any earlier output.
+    pub fn open(
+        run_dir: PathBuf,
+        config: &Config,
+        identity: &RunIdentity,
+    ) -> Result<Self, Box<dyn Error>> {
+        let log_selection =
+            parse_gen_selection(&config.log_gens).expect("log-gens should be validated");
         create_dir_all(&run_dir)?;
-        write_manifest(&run_dir, &run_id, config, &config_hash, git_commit.as_deref())?;
+        write_manifest(&run_dir, &RunManifest::new(config, identity))?;
         let csv_path = run_dir.join("generations.csv");
         let file_exists = csv_path.exists();
         let file = OpenOptions::new()
@@ -114,12 +298,15 @@ impl MetricsWriter {
         if !file_exists {
             csv_writer.flush()?;
         }
+        let event_log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(run_dir.join("events.ndjson"))?;
         Ok(Self {
             run_dir,
             csv_writer,
-            config_hash,
-            run_id,
-            git_commit,
+            event_log,
+            log_selection,
         })
     }
 
@@ -138,19 +325,103 @@ impl MetricsWriter {
         Ok(())
     }
 
-    pub fn config_hash(&self) -> &str {
-        &self.config_hash
+    pub fn write_pareto_front(&self, front: &ParetoFront) -> Result<(), Box<dyn Error>> {
+        let path = self
+            .run_dir
+            .join(format!("pareto_gen_{:04}.json", front.generation));
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, front)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn write_champion(&self, champion: &Champion) -> Result<(), Box<dyn Error>> {
+        let file = File::create(self.run_dir.join("champion.json"))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, champion)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn write_best(&self, best: &Champion) -> Result<(), Box<dyn Error>> {
+        let path = self
+            .run_dir
+            .join(format!("best_gen_{:04}.json", best.generation));
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, best)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn write_archive(&self, archive: &ArchiveExport) -> Result<(), Box<dyn Error>> {
+        let file = File::create(self.run_dir.join("archive.json"))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, archive)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+impl MetricsSink for MetricsWriter {
+    fn name(&self) -> &'static str {
+        "run directory"
     }
 
-    pub fn run_id(&self) -> &str {
-        &self.run_id
+    fn record(&mut self, report: &GenerationReport) -> Result<(), Box<dyn Error>> {
+        if self.log_selection.matches(report.generation) {
+            self.write_generation(report)?;
+        }
+        Ok(())
+    }
+
+    fn record_pareto_front(&mut self, front: &ParetoFront) -> Result<(), Box<dyn Error>> {
+        if self.log_selection.matches(front.generation) {
+            self.write_pareto_front(front)?;
+        }
+        Ok(())
+    }
+
+    fn record_event(&mut self, event: &RunEvent) -> Result<(), Box<dyn Error>> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        self.event_log.write_all(&line)?;
+        Ok(())
+    }
+
+    fn record_best(&mut self, best: &Champion) -> Result<(), Box<dyn Error>> {
+        self.write_best(best)
     }
 
-    pub fn git_commit(&self) -> Option<&str> {
-        self.git_commit.as_deref()
+    fn finish(&mut self, summary: &RunSummary<'_>) -> Result<(), Box<dyn Error>> {
+        if let Some(champion) = summary.champion {
+            self.write_champion(champion)?;
+        }
+        if let Some(archive) = summary.archive {
+            self.write_archive(archive)?;
+        }
+        Ok(())
     }
 }
 
+/// Flattens a histogram into a single CSV cell, e.g. `relu:12;tanh:88`.
+fn format_counts<V: fmt::Display>(counts: &BTreeMap<String, V>) -> String {
+    counts
+        .iter()
+        .map(|(key, count)| format!("{key}:{count}"))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// `island:value` pairs in island order, like `format_counts`.
+fn format_islands(report: &GenerationReport, value: impl Fn(&IslandSummary) -> f32) -> String {
+    report
+        .islands
+        .iter()
+        .map(|island| format!("{}:{}", island.island, value(island)))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
 pub fn default_run_id(seed: u64) -> String {
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -159,29 +430,11 @@ pub fn default_run_id(seed: u64) -> String {
     format!("{timestamp}-{seed}")
 }
 
-fn write_manifest(
-    run_dir: &Path,
-    run_id: &str,
-    config: &Config,
-    config_hash: &str,
-    git_commit: Option<&str>,
-) -> Result<(), Box<dyn Error>> {
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|duration| duration.as_secs().to_string())
-        .unwrap_or_else(|_| "0".to_string());
-    let manifest = RunManifest {
-        run_id,
-        seed: config.seed,
-        timestamp,
-        config_hash,
-        git_commit,
-        config,
-    };
+fn write_manifest(run_dir: &Path, manifest: &RunManifest<'_>) -> Result<(), Box<dyn Error>> {
     let path = run_dir.join("manifest.json");
     let file = File::create(path)?;
     let mut writer = BufWriter::new(file);
-    serde_json::to_writer_pretty(&mut writer, &manifest)?;
+    serde_json::to_writer_pretty(&mut writer, manifest)?;
     writer.write_all(b"\n")?;
     Ok(())
 }
@@ -204,7 +457,10 @@ fn fnv1a_64(bytes: &[u8]) -> u64 {
 }
 
 fn get_git_commit() -> Option<String> {
-    let output = Command::new("git").args(["rev-parse", "HEAD"]).output().ok()?;
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
     if !output.status.success() {
         return None;
     }
@@ -0,0 +1,644 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::genome::{Activation, WeightBounds};
+use crate::neural_net::ForwardScratch;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeKind {
+    Input,
+    Hidden,
+    Output,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeGene {
+    pub id: u32,
+    pub kind: NodeKind,
+    pub activation: Activation,
+    pub bias: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionGene {
+    pub innovation: u32,
+    pub from: u32,
+    pub to: u32,
+    pub weight: f32,
+    pub enabled: bool,
+}
+
+/// Direct encoding with explicit node and connection genes, NEAT style.
+///
+/// Input nodes use ids `0..inputs`, output nodes `inputs..inputs + outputs`,
+/// hidden nodes get ids from the [`InnovationTracker`]. The connection
+/// structure (enabled or not) is always acyclic, so any subset of enabled
+/// connections compiles to a feed-forward network.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphGenome {
+    pub inputs: usize,
+    pub outputs: usize,
+    pub nodes: Vec<NodeGene>,
+    pub connections: Vec<ConnectionGene>,
+}
+
+/// Hands out innovation numbers and hidden node ids so that the same
+/// structural mutation gets the same numbers wherever it happens in a run.
+#[derive(Debug, Clone, Default)]
+pub struct InnovationTracker {
+    connections: HashMap<(u32, u32), u32>,
+    splits: HashMap<u32, u32>,
+    next_innovation: u32,
+    next_node: u32,
+}
+
+impl InnovationTracker {
+    /// Registers the input-to-output connections every initial genome draws
+    /// from, using the same numbering as [`GraphGenome::random`].
+    pub fn new(inputs: usize, outputs: usize) -> Self {
+        let mut tracker = Self {
+            next_node: (inputs + outputs) as u32,
+            ..Self::default()
+        };
+        for input in 0..inputs as u32 {
+            for output in 0..outputs as u32 {
+                tracker.connection(input, inputs as u32 + output);
+            }
+        }
+        tracker
+    }
+
+    pub fn connection(&mut self, from: u32, to: u32) -> u32 {
+        let next = &mut self.next_innovation;
+        *self.connections.entry((from, to)).or_insert_with(|| {
+            let innovation = *next;
+            *next += 1;
+            innovation
+        })
+    }
+
+    fn split_node(&mut self, genome: &GraphGenome, innovation: u32) -> u32 {
+        if let Some(&id) = self.splits.get(&innovation) {
+            if genome.node(id).is_none() {
+                return id;
+            }
+        }
+        let id = self.next_node;
+        self.next_node += 1;
+        self.splits.insert(innovation, id);
+        id
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GraphMutation<'a> {
+    pub weight_rate: f32,
+    pub weight_strength: f32,
+    pub add_connection_rate: f32,
+    pub add_node_rate: f32,
+    pub toggle_rate: f32,
+    pub activation_rate: f32,
+    pub activation_pool: &'a [Activation],
+    pub hidden_activation: Activation,
+}
+
+impl GraphGenome {
+    /// Inputs wired straight to outputs; each of those connections exists with
+    /// probability `density`.
+    pub fn random(
+        inputs: usize,
+        outputs: usize,
+        density: f32,
+        output_activation: Activation,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut nodes = Vec::with_capacity(inputs + outputs);
+        for id in 0..inputs as u32 {
+            nodes.push(NodeGene {
+                id,
+                kind: NodeKind::Input,
+                activation: Activation::Identity,
+                bias: 0.0,
+            });
+        }
+        for id in 0..outputs as u32 {
+            nodes.push(NodeGene {
+                id: inputs as u32 + id,
+                kind: NodeKind::Output,
+                activation: output_activation,
+                bias: rng.gen_range(-1.0..=1.0),
+            });
+        }
+        let mut connections = Vec::new();
+        for input in 0..inputs as u32 {
+            for output in 0..outputs as u32 {
+                let weight = rng.gen_range(-1.0..=1.0);
+                if rng.gen::<f32>() < density {
+                    connections.push(ConnectionGene {
+                        innovation: input * outputs as u32 + output,
+                        from: input,
+                        to: inputs as u32 + output,
+                        weight,
+                        enabled: true,
+                    });
+                }
+            }
+        }
+        Self {
+            inputs,
+            outputs,
+            nodes,
+            connections,
+        }
+    }
+
+    pub fn node(&self, id: u32) -> Option<&NodeGene> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub fn hidden_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|node| node.kind == NodeKind::Hidden)
+            .count()
+    }
+
+    pub fn enabled_connections(&self) -> usize {
+        self.connections.iter().filter(|conn| conn.enabled).count()
+    }
+
+    /// Evolvable parameters: enabled connection weights plus node biases.
+    pub fn param_count(&self) -> usize {
+        let biases = self
+            .nodes
+            .iter()
+            .filter(|node| node.kind != NodeKind::Input)
+            .count();
+        self.enabled_connections() + biases
+    }
+
+    /// Applies `bounds` to every connection weight and node bias.
+    pub fn regularize(&mut self, bounds: WeightBounds) {
+        for conn in &mut self.connections {
+            bounds.apply(&mut conn.weight);
+        }
+        for node in &mut self.nodes {
+            bounds.apply(&mut node.bias);
+        }
+    }
+
+    /// Silences node `id` by disabling every connection leaving it.
+    pub fn knock_out(&mut self, id: u32) {
+        for conn in &mut self.connections {
+            if conn.from == id {
+                conn.enabled = false;
+            }
+        }
+    }
+
+    pub fn mutate(
+        &mut self,
+        params: &GraphMutation<'_>,
+        innovations: &mut InnovationTracker,
+        rng: &mut impl Rng,
+    ) {
+        for conn in &mut self.connections {
+            if rng.gen::<f32>() < params.weight_rate {
+                conn.weight += rng.gen_range(-params.weight_strength..=params.weight_strength);
+            }
+        }
+        for node in &mut self.nodes {
+            if node.kind != NodeKind::Input && rng.gen::<f32>() < params.weight_rate {
+                node.bias += rng.gen_range(-params.weight_strength..=params.weight_strength);
+            }
+        }
+        if rng.gen::<f32>() < params.add_connection_rate {
+            self.add_connection(innovations, rng);
+        }
+        if rng.gen::<f32>() < params.add_node_rate {
+            self.add_node(params.hidden_activation, innovations, rng);
+        }
+        if !self.connections.is_empty() && rng.gen::<f32>() < params.toggle_rate {
+            let idx = rng.gen_range(0..self.connections.len());
+            self.connections[idx].enabled = !self.connections[idx].enabled;
+        }
+        let pool = params.activation_pool;
+        if params.activation_rate > 0.0 && !pool.is_empty() {
+            for node in &mut self.nodes {
+                if node.kind == NodeKind::Hidden && rng.gen::<f32>() < params.activation_rate {
+                    node.activation = pool[rng.gen_range(0..pool.len())];
+                }
+            }
+        }
+    }
+
+    /// Adds a connection between two previously unconnected nodes, skipping
+    /// candidates that would close a cycle. Gives up after a few attempts.
+    pub fn add_connection(
+        &mut self,
+        innovations: &mut InnovationTracker,
+        rng: &mut impl Rng,
+    ) -> bool {
+        let sources: Vec<u32> = self
+            .nodes
+            .iter()
+            .filter(|node| node.kind != NodeKind::Output)
+            .map(|node| node.id)
+            .collect();
+        let targets: Vec<u32> = self
+            .nodes
+            .iter()
+            .filter(|node| node.kind != NodeKind::Input)
+            .map(|node| node.id)
+            .collect();
+        if sources.is_empty() || targets.is_empty() {
+            return false;
+        }
+        for _ in 0..20 {
+            let from = sources[rng.gen_range(0..sources.len())];
+            let to = targets[rng.gen_range(0..targets.len())];
+            if from == to
+                || self
+                    .connections
+                    .iter()
+                    .any(|conn| conn.from == from && conn.to == to)
+                || self.reaches(to, from)
+            {
+                continue;
+            }
+            self.connections.push(ConnectionGene {
+                innovation: innovations.connection(from, to),
+                from,
+                to,
+                weight: rng.gen_range(-1.0..=1.0),
+                enabled: true,
+            });
+            return true;
+        }
+        false
+    }
+
+    /// Splits an enabled connection `a -> b` into `a -> new -> b`. The incoming
+    /// link gets weight 1 and the outgoing one keeps the old weight, so the
+    /// network initially behaves almost the same.
+    pub fn add_node(
+        &mut self,
+        activation: Activation,
+        innovations: &mut InnovationTracker,
+        rng: &mut impl Rng,
+    ) -> bool {
+        let enabled: Vec<usize> = self
+            .connections
+            .iter()
+            .enumerate()
+            .filter(|(_, conn)| conn.enabled)
+            .map(|(idx, _)| idx)
+            .collect();
+        if enabled.is_empty() {
+            return false;
+        }
+        let idx = enabled[rng.gen_range(0..enabled.len())];
+        self.connections[idx].enabled = false;
+        let ConnectionGene {
+            innovation,
+            from,
+            to,
+            weight,
+            ..
+        } = self.connections[idx].clone();
+        let id = innovations.split_node(self, innovation);
+        self.nodes.push(NodeGene {
+            id,
+            kind: NodeKind::Hidden,
+            activation,
+            bias: 0.0,
+        });
+        self.connections.push(ConnectionGene {
+            innovation: innovations.connection(from, id),
+            from,
+            to: id,
+            weight: 1.0,
+            enabled: true,
+        });
+        self.connections.push(ConnectionGene {
+            innovation: innovations.connection(id, to),
+            from: id,
+            to,
+            weight,
+            enabled: true,
+        });
+        true
+    }
+
+    fn reaches(&self, start: u32, goal: u32) -> bool {
+        let mut stack = vec![start];
+        let mut seen = vec![start];
+        while let Some(node) = stack.pop() {
+            if node == goal {
+                return true;
+            }
+            for conn in self.connections.iter().filter(|conn| conn.from == node) {
+                if !seen.contains(&conn.to) {
+                    seen.push(conn.to);
+                    stack.push(conn.to);
+                }
+            }
+        }
+        false
+    }
+
+    /// Longest enabled path from an input to any node, counted in layers
+    /// including the input and output layers.
+    pub fn depth(&self) -> usize {
+        let depth = self.node_depths();
+        let deepest = self
+            .nodes
+            .iter()
+            .filter(|node| node.kind == NodeKind::Output)
+            .filter_map(|node| depth.get(&node.id))
+            .max()
+            .copied()
+            .unwrap_or(0);
+        deepest.max(1) + 1
+    }
+
+    /// Longest enabled path from an input to each node, in connections.
+    pub fn node_depths(&self) -> HashMap<u32, usize> {
+        let mut depth: HashMap<u32, usize> = HashMap::new();
+        for id in self.topological_order() {
+            let level = self
+                .connections
+                .iter()
+                .filter(|conn| conn.enabled && conn.to == id)
+                .filter_map(|conn| depth.get(&conn.from))
+                .max()
+                .map_or(0, |level| level + 1);
+            depth.insert(id, level);
+        }
+        depth
+    }
+
+    fn topological_order(&self) -> Vec<u32> {
+        let mut indegree: HashMap<u32, usize> =
+            self.nodes.iter().map(|node| (node.id, 0)).collect();
+        for conn in &self.connections {
+            *indegree.entry(conn.to).or_insert(0) += 1;
+        }
+        let mut ready: Vec<u32> = self
+            .nodes
+            .iter()
+            .filter(|node| indegree[&node.id] == 0)
+            .map(|node| node.id)
+            .collect();
+        ready.reverse();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(id) = ready.pop() {
+            order.push(id);
+            for conn in self.connections.iter().filter(|conn| conn.from == id) {
+                let count = indegree
+                    .get_mut(&conn.to)
+                    .expect("connection to unknown node");
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(conn.to);
+                }
+            }
+        }
+        order
+    }
+}
+
+/// NEAT compatibility distance: the share of connection genes the genomes do
+/// not have in common plus the mean weight difference of the shared ones.
+pub fn compatibility(a: &GraphGenome, b: &GraphGenome) -> f32 {
+    let other: HashMap<u32, f32> = b
+        .connections
+        .iter()
+        .map(|conn| (conn.innovation, conn.weight))
+        .collect();
+    let mut matching = 0usize;
+    let mut weight_diff = 0.0;
+    for conn in &a.connections {
+        if let Some(&weight) = other.get(&conn.innovation) {
+            matching += 1;
+            weight_diff += (conn.weight - weight).abs();
+        }
+    }
+    let mismatched = a.connections.len() + b.connections.len() - 2 * matching;
+    let genes = a.connections.len().max(b.connections.len()).max(1);
+    let weight_term = if matching > 0 {
+        weight_diff / matching as f32
+    } else {
+        0.0
+    };
+    mismatched as f32 / genes as f32 + weight_term
+}
+
+/// NEAT crossover: matching genes come from either parent (or are blended),
+/// disjoint and excess genes come from `primary`, which also fixes the
+/// child's structure. A gene disabled in either parent stays disabled with
+/// probability 0.75.
+pub fn crossover_graphs(
+    primary: &GraphGenome,
+    secondary: &GraphGenome,
+    blend: bool,
+    rng: &mut impl Rng,
+) -> GraphGenome {
+    let other: HashMap<u32, &ConnectionGene> = secondary
+        .connections
+        .iter()
+        .map(|conn| (conn.innovation, conn))
+        .collect();
+    let mut child = primary.clone();
+    for conn in &mut child.connections {
+        let Some(matching) = other.get(&conn.innovation) else {
+            continue;
+        };
+        if blend {
+            let alpha = rng.gen::<f32>();
+            conn.weight = alpha * conn.weight + (1.0 - alpha) * matching.weight;
+        } else if rng.gen::<bool>() {
+            conn.weight = matching.weight;
+        }
+        if !conn.enabled || !matching.enabled {
+            conn.enabled = rng.gen::<f32>() >= 0.75;
+        }
+    }
+    for node in &mut child.nodes {
+        if let Some(matching) = secondary.node(node.id) {
+            if rng.gen::<bool>() {
+                node.bias = matching.bias;
+                node.activation = matching.activation;
+            }
+        }
+    }
+    child
+}
+
+/// A [`GraphGenome`] compiled for evaluation: nodes in topological order with
+/// their incoming enabled connections stored contiguously.
+#[derive(Debug, Clone)]
+pub struct GraphNet {
+    pub genome: GraphGenome,
+    /// Slot of each input node in the value buffer.
+    inputs: Vec<usize>,
+    /// Slot of each output node in the value buffer.
+    outputs: Vec<usize>,
+    /// Non-input nodes in evaluation order.
+    order: Vec<CompiledNode>,
+    sources: Vec<usize>,
+    weights: Vec<f32>,
+    slots: usize,
+}
+
+#[derive(Debug, Clone)]
+struct CompiledNode {
+    slot: usize,
+    bias: f32,
+    activation: Activation,
+    edges: std::ops::Range<usize>,
+}
+
+impl GraphNet {
+    pub fn new(genome: GraphGenome) -> Self {
+        let slot_of: HashMap<u32, usize> = genome
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(slot, node)| (node.id, slot))
+            .collect();
+        let mut order = Vec::new();
+        let mut sources = Vec::new();
+        let mut weights = Vec::new();
+        for id in genome.topological_order() {
+            let node = genome.node(id).expect("ordered node exists");
+            if node.kind == NodeKind::Input {
+                continue;
+            }
+            let start = sources.len();
+            for conn in genome
+                .connections
+                .iter()
+                .filter(|conn| conn.enabled && conn.to == id)
+            {
+                sources.push(slot_of[&conn.from]);
+                weights.push(conn.weight);
+            }
+            order.push(CompiledNode {
+                slot: slot_of[&id],
+                bias: node.bias,
+                activation: node.activation,
+                edges: start..sources.len(),
+            });
+        }
+        let inputs = (0..genome.inputs as u32).map(|id| slot_of[&id]).collect();
+        let outputs = (0..genome.outputs as u32)
+            .map(|idx| slot_of[&(genome.inputs as u32 + idx)])
+            .collect();
+        Self {
+            slots: genome.nodes.len(),
+            genome,
+            inputs,
+            outputs,
+            order,
+            sources,
+            weights,
+        }
+    }
+
+    pub fn forward_into<'s>(
+        &mut self,
+        inputs: &[f32],
+        scratch: &'s mut ForwardScratch,
+    ) -> &'s [f32] {
+        assert_eq!(inputs.len(), self.inputs.len(), "Input size mismatch");
+        let ForwardScratch {
+            prev: values,
+            next: outputs,
+        } = scratch;
+        values.clear();
+        values.resize(self.slots, 0.0);
+        for (&slot, &value) in self.inputs.iter().zip(inputs) {
+            values[slot] = value;
+        }
+        for node in &self.order {
+            let mut sum = node.bias;
+            for edge in node.edges.clone() {
+                sum += values[self.sources[edge]] * self.weights[edge];
+            }
+            values[node.slot] = node.activation.apply(sum);
+        }
+        outputs.clear();
+        outputs.extend(self.outputs.iter().map(|&slot| values[slot]));
+        outputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn structural_mutations_keep_graph_acyclic_and_compilable() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let mut innovations = InnovationTracker::new(3, 2);
+        let mut genome = GraphGenome::random(3, 2, 0.5, Activation::Tanh, &mut rng);
+        let params = GraphMutation {
+            weight_rate: 0.2,
+            weight_strength: 0.1,
+            add_connection_rate: 0.8,
+            add_node_rate: 0.5,
+            toggle_rate: 0.2,
+            activation_rate: 0.1,
+            activation_pool: &Activation::ALL,
+            hidden_activation: Activation::Relu,
+        };
+        for _ in 0..50 {
+            genome.mutate(&params, &mut innovations, &mut rng);
+        }
+        assert!(genome.hidden_count() > 0);
+        assert_eq!(
+            genome.topological_order().len(),
+            genome.nodes.len(),
+            "no cycles"
+        );
+        let mut net = GraphNet::new(genome);
+        let mut scratch = ForwardScratch::default();
+        let outputs = net.forward_into(&[0.2, -0.4, 1.0], &mut scratch);
+        assert_eq!(outputs.len(), 2);
+        assert!(outputs.iter().all(|value| value.is_finite()));
+    }
+
+    #[test]
+    fn split_connection_preserves_path() {
+        let mut rng = StdRng::seed_from_u64(22);
+        let mut innovations = InnovationTracker::new(1, 1);
+        let mut genome = GraphGenome::random(1, 1, 1.0, Activation::Identity, &mut rng);
+        genome.nodes[1].bias = 0.0;
+        assert!(genome.add_node(Activation::Identity, &mut innovations, &mut rng));
+        let mut net = GraphNet::new(genome.clone());
+        let mut scratch = ForwardScratch::default();
+        let weight = genome.connections[0].weight;
+        let out = net.forward_into(&[0.5], &mut scratch)[0];
+        assert!((out - 0.5 * weight).abs() < 1e-6);
+        assert_eq!(genome.depth(), 3);
+    }
+
+    #[test]
+    fn crossover_aligns_by_innovation() {
+        let mut rng = StdRng::seed_from_u64(23);
+        let mut innovations = InnovationTracker::new(2, 2);
+        let a = GraphGenome::random(2, 2, 1.0, Activation::Tanh, &mut rng);
+        let mut b = a.clone();
+        b.add_node(Activation::Tanh, &mut innovations, &mut rng);
+        let child = crossover_graphs(&a, &b, false, &mut rng);
+        assert_eq!(child.connections.len(), a.connections.len());
+        let child = crossover_graphs(&b, &a, true, &mut rng);
+        assert_eq!(child.connections.len(), b.connections.len());
+        assert_eq!(child.hidden_count(), 1);
+    }
+}
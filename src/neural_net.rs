@@ -1,40 +1,110 @@
-use crate::genome::{genome_size_from_layers, Genome};
+use crate::genome::{context_size, Genome, Recurrence};
+
+pub mod batch;
+
+/// Reusable layer buffers for [`NeuralNet::forward_into`].
+#[derive(Debug, Clone, Default)]
+pub struct ForwardScratch {
+    pub(crate) prev: Vec<f32>,
+    pub(crate) next: Vec<f32>,
+}
 
 #[derive(Debug, Clone)]
 pub struct NeuralNet {
     pub genome: Genome,
+    /// Context values per weight block, carried over from the previous step.
+    state: Vec<Vec<f32>>,
 }
 
 impl NeuralNet {
     pub fn new(genome: Genome) -> Self {
-        let expected = genome_size_from_layers(&genome.layers);
+        let expected = genome.expected_size();
         assert_eq!(genome.weights.len(), expected, "Genome size mismatch");
-        Self { genome }
+        let state = (0..genome.layers.len().saturating_sub(1))
+            .map(|block| vec![0.0; context_size(&genome.layers, genome.recurrence, block)])
+            .collect();
+        Self { genome, state }
+    }
+
+    pub fn forward(&mut self, inputs: &[f32]) -> Vec<f32> {
+        let mut scratch = ForwardScratch::default();
+        self.forward_into(inputs, &mut scratch).to_vec()
+    }
+
+    /// Same as [`NeuralNet::forward`] but evaluates into `scratch`, so once the
+    /// buffers have grown to the widest layer no further allocation happens.
+    /// The returned slice holds the outputs and lives inside `scratch`.
+    pub fn forward_into<'s>(
+        &mut self,
+        inputs: &[f32],
+        scratch: &'s mut ForwardScratch,
+    ) -> &'s [f32] {
+        self.forward_impl(inputs, scratch, None)
     }
 
-    pub fn forward(&self, inputs: &[f32]) -> Vec<f32> {
+    /// Same as [`NeuralNet::forward_into`], and also appends the values of
+    /// every layer, inputs first, to `trace`.
+    pub fn forward_traced<'s>(
+        &mut self,
+        inputs: &[f32],
+        scratch: &'s mut ForwardScratch,
+        trace: &mut Vec<f32>,
+    ) -> &'s [f32] {
+        self.forward_impl(inputs, scratch, Some(trace))
+    }
+
+    fn forward_impl<'s>(
+        &mut self,
+        inputs: &[f32],
+        scratch: &'s mut ForwardScratch,
+        mut trace: Option<&mut Vec<f32>>,
+    ) -> &'s [f32] {
         let layers = &self.genome.layers;
         let input_size = layers.first().copied().unwrap_or(0);
         let output_size = layers.last().copied().unwrap_or(0);
         assert_eq!(inputs.len(), input_size, "Input size mismatch");
+        let ForwardScratch { prev, next } = scratch;
+        prev.clear();
+        prev.extend_from_slice(inputs);
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.extend_from_slice(inputs);
+        }
         let mut idx = 0;
-        let mut prev_vals = inputs.to_vec();
-        for pair in layers.windows(2) {
+        for (block, pair) in layers.windows(2).enumerate() {
             let next_size = pair[1];
-            let mut next_vals = vec![0.0; next_size];
-            for next_val in &mut next_vals {
+            let context = &self.state[block];
+            let activation = self.genome.activation(block);
+            next.clear();
+            next.resize(next_size, 0.0);
+            for next_val in next.iter_mut() {
                 let mut sum = self.genome.weights[idx];
                 idx += 1;
-                for &val in &prev_vals {
+                for &val in prev.iter().chain(context) {
                     sum += val * self.genome.weights[idx];
                     idx += 1;
                 }
-                *next_val = sum.tanh();
+                *next_val = activation.apply(sum);
             }
-            prev_vals = next_vals;
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.extend_from_slice(next);
+            }
+            if matches!(self.genome.recurrence, Recurrence::Elman) && !context.is_empty() {
+                self.state[block].copy_from_slice(next);
+            }
+            std::mem::swap(prev, next);
+        }
+        assert_eq!(prev.len(), output_size, "Output size mismatch");
+        if matches!(self.genome.recurrence, Recurrence::Jordan) && !self.state[0].is_empty() {
+            self.state[0].copy_from_slice(prev);
+        }
+        prev
+    }
+
+    /// Clears the recurrent context, e.g. at the start of an episode.
+    pub fn reset_state(&mut self) {
+        for context in &mut self.state {
+            context.fill(0.0);
         }
-        assert_eq!(prev_vals.len(), output_size, "Output size mismatch");
-        prev_vals
     }
 
     pub fn param_count(&self) -> u32 {
@@ -49,3 +119,98 @@ impl NeuralNet {
         self.genome.layers.len().saturating_sub(2) as u32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::Activation;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn recurrent_state_carries_over_and_resets() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for recurrence in [Recurrence::Elman, Recurrence::Jordan] {
+            let genome = Genome::random_with_recurrence(vec![2, 3, 2], recurrence, &mut rng);
+            let mut net = NeuralNet::new(genome);
+            let first = net.forward(&[0.5, -0.5]);
+            let second = net.forward(&[0.5, -0.5]);
+            assert_ne!(first, second, "{recurrence:?} output depends on history");
+            net.reset_state();
+            assert_eq!(net.forward(&[0.5, -0.5]), first);
+        }
+    }
+
+    #[test]
+    fn layers_use_their_own_activation() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut genome = Genome::random(vec![3, 5, 4], &mut rng);
+        genome.activations = vec![Activation::Identity, Activation::Relu];
+        let mut net = NeuralNet::new(genome);
+        for _ in 0..10 {
+            let inputs: Vec<f32> = (0..3).map(|_| rng.gen_range(-1.0..=1.0)).collect();
+            assert!(net.forward(&inputs).iter().all(|&value| value >= 0.0));
+        }
+    }
+
+    #[test]
+    fn trace_holds_every_layer() {
+        let mut rng = StdRng::seed_from_u64(13);
+        let genome = Genome::random(vec![3, 4, 2], &mut rng);
+        let mut net = NeuralNet::new(genome);
+        let mut scratch = ForwardScratch::default();
+        let mut trace = Vec::new();
+        let outputs = net
+            .forward_traced(&[0.1, 0.2, 0.3], &mut scratch, &mut trace)
+            .to_vec();
+        assert_eq!(trace.len(), 9);
+        assert_eq!(&trace[..3], &[0.1, 0.2, 0.3]);
+        assert_eq!(&trace[7..], outputs.as_slice());
+        assert_eq!(net.forward(&[0.1, 0.2, 0.3]), outputs);
+    }
+
+    /// The allocating forward pass `forward_into` replaced: a fresh `Vec` per
+    /// layer, `tanh` everywhere and no recurrence.
+    fn allocating_forward(genome: &Genome, inputs: &[f32]) -> Vec<f32> {
+        let mut idx = 0;
+        let mut prev_vals = inputs.to_vec();
+        for pair in genome.layers.windows(2) {
+            let mut next_vals = vec![0.0; pair[1]];
+            for next_val in &mut next_vals {
+                let mut sum = genome.weights[idx];
+                idx += 1;
+                for &val in &prev_vals {
+                    sum += val * genome.weights[idx];
+                    idx += 1;
+                }
+                *next_val = sum.tanh();
+            }
+            prev_vals = next_vals;
+        }
+        prev_vals
+    }
+
+    #[test]
+    fn forward_into_matches_the_allocating_forward() {
+        let genome = Genome {
+            layers: vec![2, 3, 2],
+            weights: vec![
+                0.1, 0.5, -0.3, //
+                -0.2, 0.8, 0.4, //
+                0.05, -0.6, 0.9, //
+                0.3, 0.7, -0.5, 0.2, //
+                -0.1, -0.4, 0.6, 0.8,
+            ],
+            recurrence: Recurrence::None,
+            activations: Vec::new(),
+        };
+        let mut net = NeuralNet::new(genome.clone());
+        let mut scratch = ForwardScratch::default();
+        for inputs in [[0.0, 0.0], [1.0, -1.0], [0.25, 0.75], [-3.0, 2.0]] {
+            assert_eq!(
+                net.forward_into(&inputs, &mut scratch),
+                allocating_forward(&genome, &inputs).as_slice()
+            );
+        }
+    }
+}
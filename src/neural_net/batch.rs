@@ -0,0 +1,200 @@
+use crate::genome::{context_size, Activation, Genome, Recurrence};
+
+/// Evaluates a batch of networks that share one architecture.
+///
+/// Weights are stored creature-minor (`[neuron][input][net]`), so the innermost
+/// loop is a unit-stride multiply-add across the batch that the compiler can
+/// vectorise. Every lane performs the same operations in the same order as
+/// [`NeuralNet::forward_into`](crate::neural_net::NeuralNet::forward_into),
+/// which makes the results bit-identical to evaluating each network on its own.
+#[derive(Debug, Clone)]
+pub struct BatchedNet {
+    layers: Vec<usize>,
+    recurrence: Recurrence,
+    activations: Vec<Activation>,
+    batch: usize,
+    blocks: Vec<Vec<f32>>,
+    state: Vec<Vec<f32>>,
+    prev: Vec<f32>,
+    next: Vec<f32>,
+}
+
+impl BatchedNet {
+    pub fn new(genomes: &[&Genome]) -> Result<Self, String> {
+        let first = genomes
+            .first()
+            .ok_or_else(|| "a batch needs at least one genome".to_string())?;
+        if let Some(other) = genomes
+            .iter()
+            .find(|genome| !same_architecture(first, genome))
+        {
+            return Err(format!(
+                "genomes in a batch must share an architecture ({:?} vs {:?})",
+                first.layers, other.layers
+            ));
+        }
+        let layers = first.layers.clone();
+        let recurrence = first.recurrence;
+        let batch = genomes.len();
+        let ranges = first.layer_ranges();
+        let mut blocks = Vec::with_capacity(ranges.len());
+        let mut state = Vec::with_capacity(ranges.len());
+        for (block, range) in ranges.iter().enumerate() {
+            let mut packed = vec![0.0; range.len() * batch];
+            for (lane, genome) in genomes.iter().enumerate() {
+                let weights = &genome.weights[range.clone()];
+                for (offset, &weight) in weights.iter().enumerate() {
+                    packed[offset * batch + lane] = weight;
+                }
+            }
+            blocks.push(packed);
+            state.push(vec![0.0; context_size(&layers, recurrence, block) * batch]);
+        }
+        Ok(Self {
+            activations: (0..ranges.len())
+                .map(|block| first.activation(block))
+                .collect(),
+            layers,
+            recurrence,
+            batch,
+            blocks,
+            state,
+            prev: Vec::new(),
+            next: Vec::new(),
+        })
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch
+    }
+
+    pub fn reset_state(&mut self) {
+        for context in &mut self.state {
+            context.fill(0.0);
+        }
+    }
+
+    /// Runs one step for every network. `inputs` holds one row per network
+    /// (`[net][input]`) and `outputs` is filled the same way.
+    pub fn forward(&mut self, inputs: &[f32], outputs: &mut Vec<f32>) {
+        let batch = self.batch;
+        let input_size = self.layers.first().copied().unwrap_or(0);
+        let output_size = self.layers.last().copied().unwrap_or(0);
+        assert_eq!(inputs.len(), input_size * batch, "Input size mismatch");
+        self.prev.clear();
+        self.prev.resize(input_size * batch, 0.0);
+        for (lane, row) in inputs.chunks_exact(input_size.max(1)).enumerate() {
+            for (idx, &value) in row.iter().enumerate() {
+                self.prev[idx * batch + lane] = value;
+            }
+        }
+        for block in 0..self.layers.len().saturating_sub(1) {
+            let prev_size = self.layers[block];
+            let next_size = self.layers[block + 1];
+            let context = &self.state[block];
+            let ctx_size = context.len() / batch.max(1);
+            let row_len = 1 + prev_size + ctx_size;
+            let weights = &self.blocks[block];
+            let activation = self.activations[block];
+            self.next.clear();
+            self.next.resize(next_size * batch, 0.0);
+            for (neuron, acc) in self.next.chunks_exact_mut(batch).enumerate() {
+                let row = &weights[neuron * row_len * batch..(neuron + 1) * row_len * batch];
+                let mut columns = row.chunks_exact(batch);
+                acc.copy_from_slice(columns.next().expect("bias column"));
+                let values = self
+                    .prev
+                    .chunks_exact(batch)
+                    .chain(context.chunks_exact(batch));
+                for (value, weight) in values.zip(columns) {
+                    for lane in 0..batch {
+                        acc[lane] += value[lane] * weight[lane];
+                    }
+                }
+                for value in acc.iter_mut() {
+                    *value = activation.apply(*value);
+                }
+            }
+            if matches!(self.recurrence, Recurrence::Elman) && ctx_size > 0 {
+                self.state[block].copy_from_slice(&self.next);
+            }
+            std::mem::swap(&mut self.prev, &mut self.next);
+        }
+        if matches!(self.recurrence, Recurrence::Jordan) && !self.state[0].is_empty() {
+            self.state[0].copy_from_slice(&self.prev);
+        }
+        outputs.clear();
+        outputs.resize(output_size * batch, 0.0);
+        for (lane, row) in outputs.chunks_exact_mut(output_size.max(1)).enumerate() {
+            for (idx, value) in row.iter_mut().enumerate() {
+                *value = self.prev[idx * batch + lane];
+            }
+        }
+    }
+}
+
+/// Groups genome indices by architecture, in order of first appearance, so
+/// each group can be evaluated as one [`BatchedNet`].
+pub fn architecture_groups(genomes: &[&Genome]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (idx, genome) in genomes.iter().enumerate() {
+        match groups
+            .iter_mut()
+            .find(|group| same_architecture(genomes[group[0]], genome))
+        {
+            Some(group) => group.push(idx),
+            None => groups.push(vec![idx]),
+        }
+    }
+    groups
+}
+
+fn same_architecture(a: &Genome, b: &Genome) -> bool {
+    a.layers == b.layers
+        && a.recurrence == b.recurrence
+        && (0..a.layers.len().saturating_sub(1))
+            .all(|block| a.activation(block) == b.activation(block))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neural_net::{ForwardScratch, NeuralNet};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn batch_matches_single_forward_bit_for_bit() {
+        let mut rng = StdRng::seed_from_u64(17);
+        for recurrence in [Recurrence::None, Recurrence::Elman, Recurrence::Jordan] {
+            let genomes: Vec<Genome> = (0..5)
+                .map(|_| Genome::random_with_recurrence(vec![4, 6, 3, 4], recurrence, &mut rng))
+                .collect();
+            let refs: Vec<&Genome> = genomes.iter().collect();
+            let mut batched = BatchedNet::new(&refs).unwrap();
+            let mut singles: Vec<NeuralNet> = genomes.iter().cloned().map(NeuralNet::new).collect();
+            let mut scratch = ForwardScratch::default();
+            let mut outputs = Vec::new();
+            for _ in 0..4 {
+                let inputs: Vec<f32> = (0..4 * 5).map(|_| rng.gen_range(-1.0..=1.0)).collect();
+                batched.forward(&inputs, &mut outputs);
+                for (lane, net) in singles.iter_mut().enumerate() {
+                    let expected =
+                        net.forward_into(&inputs[lane * 4..(lane + 1) * 4], &mut scratch);
+                    assert_eq!(&outputs[lane * 4..(lane + 1) * 4], expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn groups_split_by_architecture() {
+        let mut rng = StdRng::seed_from_u64(18);
+        let a = Genome::random(vec![2, 3, 2], &mut rng);
+        let b = Genome::random(vec![2, 4, 2], &mut rng);
+        let c = Genome::random(vec![2, 3, 2], &mut rng);
+        let groups = architecture_groups(&[&a, &b, &c]);
+        assert_eq!(groups, vec![vec![0, 2], vec![1]]);
+        assert!(BatchedNet::new(&[&a, &b]).is_err());
+    }
+}
@@ -0,0 +1,490 @@
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs::{create_dir_all, read_dir, write};
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+
+use crate::action::{Action, ActionPolicy, ActionSpace, Heading, PolicyMode};
+use crate::analysis::load::{load_run_config, SavedBrain};
+use crate::analysis::svg::{escape, PALETTE};
+use crate::brain::{Brain, BrainSnapshot};
+use crate::neural_net::ForwardScratch;
+use crate::sensors::SensorSuite;
+
+const PANEL: f64 = 240.0;
+const GAP: f64 = 28.0;
+const MARGIN_LEFT: f64 = 150.0;
+const MARGIN_TOP: f64 = 70.0;
+const LEGEND_HEIGHT: f64 = 40.0;
+
+/// `evobrain policy`: sweeps the food direction inputs of saved brains over a
+/// grid at several energy levels and maps the action each one picks. Every
+/// query starts from a reset brain, so recurrent brains show their
+/// first-step policy.
+#[derive(Parser, Debug, Clone)]
+#[command(name = "evobrain policy")]
+pub struct PolicyArgs {
+    /// Run directory containing `manifest.json` and `champion.json`.
+    pub run: PathBuf,
+    /// Brains to map instead of the champion; may be repeated.
+    #[arg(long = "brain")]
+    pub brains: Vec<PathBuf>,
+    /// Also map every `best_gen_<gen>.json` saved by `--save-best`, oldest
+    /// first, to see the policy mature.
+    #[arg(long)]
+    pub saved: bool,
+    /// Points per axis of the food_dx/food_dy grid.
+    #[arg(long, default_value_t = 11)]
+    pub grid: usize,
+    /// The grid spans [-range, range] on both axes.
+    #[arg(long, default_value_t = 1.0)]
+    pub range: f32,
+    /// Values of the energy input, one map each.
+    #[arg(long, value_delimiter = ',', default_value = "0.25,0.5,1.0")]
+    pub energy: Vec<f32>,
+    /// Value given to the noise input.
+    #[arg(long, default_value_t = 0.0)]
+    pub noise: f32,
+    /// Where `policy.svg` and `policy.json` go. Defaults to the run directory.
+    #[arg(long)]
+    pub out_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyCell {
+    pub dx: f32,
+    pub dy: f32,
+    pub action: &'static str,
+    pub outputs: Vec<f32>,
+}
+
+/// The actions one brain picks over the grid at one energy level.
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyPanel {
+    pub label: String,
+    pub generation: Option<u32>,
+    pub energy: Option<f32>,
+    /// Share of off-centre cells whose action moves towards the food.
+    pub toward_food: f32,
+    pub cells: Vec<PolicyCell>,
+}
+
+#[derive(Debug, Serialize)]
+struct PolicyExport<'a> {
+    inputs: &'a [String],
+    actions: Vec<String>,
+    noise: f32,
+    panels: &'a [PolicyPanel],
+}
+
+/// Where the swept inputs sit in the input vector; all others stay 0.
+#[derive(Debug, Clone)]
+pub struct InputLayout {
+    pub size: usize,
+    pub food_dx: usize,
+    pub food_dy: usize,
+    pub energy: Option<usize>,
+    pub noise: Option<usize>,
+}
+
+impl InputLayout {
+    pub fn new(names: &[String]) -> Result<Self, String> {
+        let find = |name: &str| names.iter().position(|input| input == name);
+        Ok(Self {
+            size: names.len(),
+            food_dx: find("food_dx").ok_or("policy maps need the food sensor")?,
+            food_dy: find("food_dy").ok_or("policy maps need the food sensor")?,
+            energy: find("energy"),
+            noise: find("noise"),
+        })
+    }
+}
+
+/// Grid offset an action moves by; forward is drawn and scored facing up,
+/// the heading every episode starts with.
+fn move_of(action: Action) -> Option<(i32, i32)> {
+    match action {
+        Action::Forward => Some(Heading::default().offset()),
+        _ => action.offset(),
+    }
+}
+
+/// Queries `brain` at every grid point, picking actions by argmax.
+pub fn sweep(
+    brain: &mut dyn Brain,
+    layout: &InputLayout,
+    actions: &ActionSpace,
+    energy: Option<f32>,
+    noise: f32,
+    grid: usize,
+    range: f32,
+) -> (Vec<PolicyCell>, f32) {
+    let policy = ActionPolicy {
+        mode: PolicyMode::Argmax,
+        temperature: 1.0,
+        epsilon: 0.0,
+        threshold: 0.0,
+    };
+    // Argmax never draws from the RNG.
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut scratch = ForwardScratch::default();
+    let mut inputs = vec![0.0; layout.size];
+    if let (Some(idx), Some(energy)) = (layout.energy, energy) {
+        inputs[idx] = energy;
+    }
+    if let Some(idx) = layout.noise {
+        inputs[idx] = noise;
+    }
+    let value = |step: usize| -range + 2.0 * range * step as f32 / (grid - 1) as f32;
+    let mut cells = Vec::with_capacity(grid * grid);
+    let (mut toward, mut off_centre) = (0, 0);
+    for row in 0..grid {
+        for col in 0..grid {
+            let (dx, dy) = (value(col), value(row));
+            inputs[layout.food_dx] = dx;
+            inputs[layout.food_dy] = dy;
+            brain.reset_state();
+            let outputs = brain.forward_into(&inputs, &mut scratch).to_vec();
+            let mut chosen = 0;
+            policy.select(&outputs, &mut rng, |idx| chosen = idx);
+            let action = actions.action(chosen);
+            if dx != 0.0 || dy != 0.0 {
+                off_centre += 1;
+                if move_of(action).is_some_and(|(mx, my)| mx as f32 * dx + my as f32 * dy > 0.0) {
+                    toward += 1;
+                }
+            }
+            cells.push(PolicyCell {
+                dx,
+                dy,
+                action: action.name(),
+                outputs,
+            });
+        }
+    }
+    (cells, toward as f32 / off_centre.max(1) as f32)
+}
+
+/// The brain files to map: `--brain` files, saved
+/// per-generation bests, or else the champion.
+fn brain_files(args: &PolicyArgs) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = args.brains.clone();
+    if args.saved {
+        let mut saved: Vec<PathBuf> = read_dir(&args.run)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("best_gen_") && name.ends_with(".json"))
+            })
+            .collect();
+        if saved.is_empty() {
+            return Err(format!(
+                "no best_gen_*.json in {}; run with --save-best",
+                args.run.display()
+            )
+            .into());
+        }
+        saved.sort();
+        files.extend(saved);
+    }
+    if files.is_empty() {
+        files.push(args.run.join("champion.json"));
+    }
+    Ok(files)
+}
+
+fn label_of(path: &Path, saved: &SavedBrain) -> String {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match saved.generation {
+        Some(gen) if stem.starts_with("best_gen_") => format!("gen {gen}"),
+        Some(gen) => format!("{stem} (gen {gen})"),
+        None => stem,
+    }
+}
+
+fn render(panels: &[PolicyPanel], columns: usize, actions: &ActionSpace, grid: usize) -> String {
+    let rows = panels.len().div_ceil(columns.max(1));
+    let width = MARGIN_LEFT + columns as f64 * (PANEL + GAP);
+    let height = MARGIN_TOP + rows as f64 * (PANEL + GAP + 16.0) + LEGEND_HEIGHT;
+    let cell = PANEL / grid as f64;
+    let colour = |action: &str| {
+        let idx = actions
+            .actions()
+            .iter()
+            .position(|candidate| candidate.name() == action)
+            .unwrap_or(0);
+        PALETTE[idx % PALETTE.len()]
+    };
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif" font-size="12">"#
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="22" text-anchor="middle" font-size="15">Action by food direction (food_dx right, food_dy down; creature at the centre)</text>"#,
+        width / 2.0
+    );
+    for (idx, panel) in panels.iter().enumerate() {
+        let (row, column) = (idx / columns, idx % columns);
+        let left = MARGIN_LEFT + column as f64 * (PANEL + GAP);
+        let top = MARGIN_TOP + row as f64 * (PANEL + GAP + 16.0);
+        if column == 0 {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"#,
+                MARGIN_LEFT - 12.0,
+                top + PANEL / 2.0,
+                escape(&panel.label)
+            );
+        }
+        if row == 0 {
+            if let Some(energy) = panel.energy {
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">energy {energy}</text>"#,
+                    left + PANEL / 2.0,
+                    top - 10.0
+                );
+            }
+        }
+        let _ = writeln!(
+            svg,
+            r##"<rect x="{left:.1}" y="{top:.1}" width="{PANEL}" height="{PANEL}" fill="none" stroke="#999"/>"##
+        );
+        let (cx, cy) = (left + PANEL / 2.0, top + PANEL / 2.0);
+        let _ = writeln!(
+            svg,
+            r##"<path d="M{left:.1},{cy:.1} H{:.1} M{cx:.1},{top:.1} V{:.1}" stroke="#ddd"/>"##,
+            left + PANEL,
+            top + PANEL
+        );
+        for (idx, policy_cell) in panel.cells.iter().enumerate() {
+            let x = left + (idx % grid) as f64 * cell + cell / 2.0;
+            let y = top + (idx / grid) as f64 * cell + cell / 2.0;
+            let fill = colour(policy_cell.action);
+            let glyph = match Action::from_name(policy_cell.action) {
+                Some(Action::TurnLeft) => Err("↺"),
+                Some(Action::TurnRight) => Err("↻"),
+                Some(action) => Ok(move_of(action).unwrap_or((0, 0))),
+                None => Ok((0, 0)),
+            };
+            match glyph {
+                Ok((0, 0)) => {
+                    let _ = writeln!(
+                        svg,
+                        r#"<circle cx="{x:.1}" cy="{y:.1}" r="{:.1}" fill="{fill}"/>"#,
+                        cell * 0.12
+                    );
+                }
+                Ok((mx, my)) => {
+                    let len = ((mx * mx + my * my) as f64).sqrt();
+                    let (ux, uy) = (mx as f64 / len, my as f64 / len);
+                    let half = cell * 0.36;
+                    let (tx, ty) = (x + ux * half, y + uy * half);
+                    let head = cell * 0.2;
+                    let (bx, by) = (tx - ux * head, ty - uy * head);
+                    let (px, py) = (-uy * head * 0.6, ux * head * 0.6);
+                    let _ = writeln!(
+                        svg,
+                        r#"<path d="M{:.1},{:.1} L{bx:.1},{by:.1}" stroke="{fill}" stroke-width="1.5"/><path d="M{tx:.1},{ty:.1} L{:.1},{:.1} L{:.1},{:.1} Z" fill="{fill}"/>"#,
+                        x - ux * half,
+                        y - uy * half,
+                        bx + px,
+                        by + py,
+                        bx - px,
+                        by - py
+                    );
+                }
+                Err(symbol) => {
+                    let _ = writeln!(
+                        svg,
+                        r#"<text x="{x:.1}" y="{:.1}" text-anchor="middle" fill="{fill}">{symbol}</text>"#,
+                        y + 4.0
+                    );
+                }
+            }
+        }
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{:.0}% toward food</text>"#,
+            left + PANEL / 2.0,
+            top + PANEL + 16.0,
+            100.0 * panel.toward_food
+        );
+    }
+    let legend_y = height - LEGEND_HEIGHT / 2.0;
+    let mut x = MARGIN_LEFT;
+    for action in actions.actions() {
+        let label = if *action == Action::Forward {
+            "forward (facing up)"
+        } else {
+            action.name()
+        };
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{x:.1}" y="{:.1}" width="12" height="12" fill="{}"/><text x="{:.1}" y="{legend_y:.1}">{label}</text>"#,
+            legend_y - 10.0,
+            colour(action.name()),
+            x + 16.0
+        );
+        x += 24.0 + 7.0 * label.len() as f64;
+    }
+    let _ = writeln!(svg, "</svg>");
+    svg
+}
+
+pub fn run_policy_map(args: &PolicyArgs) -> Result<(), Box<dyn Error>> {
+    if args.grid < 2 {
+        return Err("grid needs at least 2 points per axis".into());
+    }
+    if !(args.range.is_finite() && args.range > 0.0) {
+        return Err("range must be positive".into());
+    }
+    let config = load_run_config(&args.run)?;
+    let input_names = SensorSuite::from_config(&config).input_names();
+    let layout = InputLayout::new(&input_names)?;
+    let actions = ActionSpace::from_config(&config);
+    let energies: Vec<Option<f32>> = if layout.energy.is_some() && !args.energy.is_empty() {
+        args.energy.iter().copied().map(Some).collect()
+    } else {
+        vec![None]
+    };
+
+    let mut panels = Vec::new();
+    for path in brain_files(args)? {
+        let saved = SavedBrain::load(&path)?;
+        saved.check_inputs(&path, input_names.len())?;
+        if !matches!(
+            saved.brain,
+            BrainSnapshot::Layered { .. } | BrainSnapshot::Graph { .. }
+        ) {
+            return Err(format!("{} is not an evolved brain", path.display()).into());
+        }
+        let label = label_of(&path, &saved);
+        let mut brain = saved.brain.into_brain();
+        for &energy in &energies {
+            let (cells, toward_food) = sweep(
+                brain.as_mut(),
+                &layout,
+                &actions,
+                energy,
+                args.noise,
+                args.grid,
+                args.range,
+            );
+            panels.push(PolicyPanel {
+                label: label.clone(),
+                generation: saved.generation,
+                energy,
+                toward_food,
+                cells,
+            });
+        }
+    }
+
+    for panel in &panels {
+        match panel.energy {
+            Some(energy) => println!(
+                "{:<24} energy {energy:<5} {:>5.1}% toward food",
+                panel.label,
+                100.0 * panel.toward_food
+            ),
+            None => println!(
+                "{:<24} {:>5.1}% toward food",
+                panel.label,
+                100.0 * panel.toward_food
+            ),
+        }
+    }
+
+    let out_dir = args.out_dir.clone().unwrap_or_else(|| args.run.clone());
+    create_dir_all(&out_dir)?;
+    let json_path = out_dir.join("policy.json");
+    let svg_path = out_dir.join("policy.svg");
+    let export = PolicyExport {
+        inputs: &input_names,
+        actions: actions.names(),
+        noise: args.noise,
+        panels: &panels,
+    };
+    write(&json_path, serde_json::to_string_pretty(&export)? + "\n")?;
+    write(
+        &svg_path,
+        render(&panels, energies.len(), &actions, args.grid),
+    )?;
+    println!("Wrote {} and {}", json_path.display(), svg_path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::genome::{Activation, Genome, Recurrence};
+    use crate::neural_net::NeuralNet;
+
+    /// Inputs food_dx, food_dy, energy, noise straight to up, down, left,
+    /// right, each output scoring one direction.
+    fn seeker(sign: f32) -> NeuralNet {
+        #[rustfmt::skip]
+        let weights = vec![
+            0.0, 0.0, -sign, 0.0, 0.0,
+            0.0, 0.0, sign, 0.0, 0.0,
+            0.0, -sign, 0.0, 0.0, 0.0,
+            0.0, sign, 0.0, 0.0, 0.0,
+        ];
+        NeuralNet::new(Genome {
+            layers: vec![4, 4],
+            weights,
+            recurrence: Recurrence::None,
+            activations: vec![Activation::Identity],
+        })
+    }
+
+    #[test]
+    fn sweep_scores_food_seeking() {
+        let config = Config::try_parse_from(["evobrain"]).unwrap();
+        let names = SensorSuite::from_config(&config).input_names();
+        let layout = InputLayout::new(&names).unwrap();
+        let actions = ActionSpace::from_config(&config);
+
+        let (cells, toward) = sweep(&mut seeker(1.0), &layout, &actions, Some(1.0), 0.0, 5, 1.0);
+        assert_eq!(cells.len(), 25);
+        assert_eq!(toward, 1.0);
+        // Top-left corner: food up and to the left, ties go to "up".
+        assert_eq!(
+            (cells[0].dx, cells[0].dy, cells[0].action),
+            (-1.0, -1.0, "up")
+        );
+        assert_eq!(cells[14].action, "right");
+
+        let (_, away) = sweep(&mut seeker(-1.0), &layout, &actions, Some(1.0), 0.0, 5, 1.0);
+        assert_eq!(away, 0.0);
+
+        let svg = render(
+            &[PolicyPanel {
+                label: "seeker".to_string(),
+                generation: None,
+                energy: Some(1.0),
+                toward_food: toward,
+                cells,
+            }],
+            1,
+            &actions,
+            5,
+        );
+        assert!(svg.contains("100% toward food"));
+        assert!(InputLayout::new(&["energy".to_string()]).is_err());
+    }
+}
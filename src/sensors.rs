@@ -0,0 +1,471 @@
+use clap::ValueEnum;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::action::{Action, ActionSpace, Heading};
+use crate::config::{Config, DistanceMetric};
+use crate::creature::Creature;
+use crate::world::World;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum SensorKind {
+    Food,
+    Energy,
+    Noise,
+    Rays,
+    Quadrants,
+    Walls,
+    #[value(name = "nearest-foods")]
+    NearestFoods,
+    Creatures,
+    #[value(name = "last-action")]
+    LastAction,
+    Heading,
+    Step,
+}
+
+impl SensorKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            SensorKind::Food => "food",
+            SensorKind::Energy => "energy",
+            SensorKind::Noise => "noise",
+            SensorKind::Rays => "rays",
+            SensorKind::Quadrants => "quadrants",
+            SensorKind::Walls => "walls",
+            SensorKind::NearestFoods => "nearest-foods",
+            SensorKind::Creatures => "creatures",
+            SensorKind::LastAction => "last-action",
+            SensorKind::Heading => "heading",
+            SensorKind::Step => "step",
+        }
+    }
+}
+
+/// Per-step state shared by every creature's sensors.
+pub struct SensorContext<'a> {
+    pub world: &'a World,
+    /// Positions of all creatures at the start of the step, `None` for dead ones.
+    pub positions: &'a [Option<(usize, usize)>],
+    pub index: usize,
+    pub step: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SensorSuite {
+    sensors: Vec<SensorKind>,
+    vision_radius: u32,
+    distance_metric: DistanceMetric,
+    max_energy: f32,
+    max_steps: usize,
+    ray_count: usize,
+    ray_range: f32,
+    nearest_foods: usize,
+    creature_radius: u32,
+    actions: Vec<Action>,
+}
+
+impl SensorSuite {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            sensors: config.sensors.clone(),
+            vision_radius: config.food_vision_radius,
+            distance_metric: config.distance_metric,
+            max_energy: config.max_energy,
+            max_steps: config.max_steps,
+            ray_count: config.ray_count as usize,
+            ray_range: config.ray_range as f32,
+            nearest_foods: config.sense_foods as usize,
+            creature_radius: config.creature_radius,
+            actions: ActionSpace::from_config(config).actions().to_vec(),
+        }
+    }
+
+    pub fn sensors(&self) -> &[SensorKind] {
+        &self.sensors
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.sensors.iter().map(|&kind| self.width(kind)).sum()
+    }
+
+    pub fn input_names(&self) -> Vec<String> {
+        let mut names = Vec::with_capacity(self.input_size());
+        for &kind in &self.sensors {
+            match kind {
+                SensorKind::Food => {
+                    names.push("food_dx".to_string());
+                    names.push("food_dy".to_string());
+                }
+                SensorKind::Energy => names.push("energy".to_string()),
+                SensorKind::Noise => names.push("noise".to_string()),
+                SensorKind::Rays => {
+                    names.extend((0..self.ray_count).map(|idx| format!("ray_{idx}")));
+                }
+                SensorKind::Quadrants => {
+                    for quadrant in ["ne", "nw", "sw", "se"] {
+                        names.push(format!("food_{quadrant}"));
+                    }
+                }
+                SensorKind::Walls => {
+                    for side in ["up", "down", "left", "right"] {
+                        names.push(format!("wall_{side}"));
+                    }
+                }
+                SensorKind::NearestFoods => {
+                    for idx in 0..self.nearest_foods {
+                        names.push(format!("food{idx}_dx"));
+                        names.push(format!("food{idx}_dy"));
+                    }
+                }
+                SensorKind::Creatures => {
+                    names.push("creature_dx".to_string());
+                    names.push("creature_dy".to_string());
+                    names.push("creature_density".to_string());
+                }
+                SensorKind::LastAction => {
+                    names.extend(
+                        self.actions
+                            .iter()
+                            .map(|action| format!("last_{}", action.name())),
+                    );
+                }
+                SensorKind::Heading => {
+                    names.extend(
+                        Heading::ALL
+                            .iter()
+                            .map(|heading| format!("heading_{}", heading.name())),
+                    );
+                }
+                SensorKind::Step => names.push("step".to_string()),
+            }
+        }
+        names
+    }
+
+    /// Whether the context needs the positions of the other creatures.
+    pub fn needs_positions(&self) -> bool {
+        self.sensors.contains(&SensorKind::Creatures)
+    }
+
+    pub fn sense(
+        &self,
+        creature: &Creature,
+        ctx: &SensorContext,
+        rng: &mut impl Rng,
+        out: &mut Vec<f32>,
+    ) {
+        out.clear();
+        for &kind in &self.sensors {
+            match kind {
+                SensorKind::Food => {
+                    let (dx, dy) = ctx.world.food_direction(
+                        creature.x,
+                        creature.y,
+                        self.vision_radius,
+                        self.distance_metric,
+                    );
+                    out.push(dx);
+                    out.push(dy);
+                }
+                SensorKind::Energy => {
+                    let energy_norm = if self.max_energy > 0.0 {
+                        (creature.energy / self.max_energy).clamp(0.0, 1.0)
+                    } else {
+                        0.0
+                    };
+                    out.push(energy_norm);
+                }
+                SensorKind::Noise => out.push(rng.gen_range(-1.0..=1.0)),
+                SensorKind::Rays => self.sense_rays(creature, ctx.world, out),
+                SensorKind::Quadrants => self.sense_quadrants(creature, ctx.world, out),
+                SensorKind::Walls => sense_walls(creature, ctx.world, out),
+                SensorKind::NearestFoods => self.sense_nearest_foods(creature, ctx.world, out),
+                SensorKind::Creatures => self.sense_creatures(creature, ctx, out),
+                SensorKind::LastAction => {
+                    let last = creature.last_action();
+                    out.extend(self.actions.iter().map(|&action| {
+                        if last == Some(action) {
+                            1.0
+                        } else {
+                            0.0
+                        }
+                    }));
+                }
+                SensorKind::Heading => {
+                    out.extend(Heading::ALL.iter().map(|&heading| {
+                        if creature.heading == heading {
+                            1.0
+                        } else {
+                            0.0
+                        }
+                    }));
+                }
+                SensorKind::Step => {
+                    let step = if self.max_steps > 0 {
+                        ctx.step as f32 / self.max_steps as f32
+                    } else {
+                        0.0
+                    };
+                    out.push(step);
+                }
+            }
+        }
+    }
+
+    fn width(&self, kind: SensorKind) -> usize {
+        match kind {
+            SensorKind::Food => 2,
+            SensorKind::Energy | SensorKind::Noise | SensorKind::Step => 1,
+            SensorKind::Rays => self.ray_count,
+            SensorKind::Quadrants | SensorKind::Walls | SensorKind::Heading => 4,
+            SensorKind::NearestFoods => 2 * self.nearest_foods,
+            SensorKind::Creatures => 3,
+            SensorKind::LastAction => self.actions.len(),
+        }
+    }
+
+    fn visible(&self, dx: i32, dy: i32) -> bool {
+        if self.vision_radius == 0 {
+            return true;
+        }
+        let radius = self.vision_radius as i32;
+        match self.distance_metric {
+            DistanceMetric::Euclidean => dx * dx + dy * dy <= radius * radius,
+            DistanceMetric::Manhattan => dx.abs() + dy.abs() <= radius,
+        }
+    }
+
+    /// Casts `ray_count` rays evenly around the creature, starting straight up.
+    /// Each reading is `1 - t / range` for the closest food cell the ray passes
+    /// through, or 0 when nothing is hit within `ray_range`.
+    fn sense_rays(&self, creature: &Creature, world: &World, out: &mut Vec<f32>) {
+        let start = out.len();
+        out.resize(start + self.ray_count, 0.0);
+        if self.ray_range <= 0.0 {
+            return;
+        }
+        for idx in 0..self.ray_count {
+            let angle = std::f32::consts::TAU * idx as f32 / self.ray_count as f32;
+            let (dir_x, dir_y) = (angle.sin(), -angle.cos());
+            let mut closest = f32::INFINITY;
+            for &(fx, fy) in &world.food {
+                let dx = fx as f32 - creature.x as f32;
+                let dy = fy as f32 - creature.y as f32;
+                if let Some(t) = ray_hits_cell(dir_x, dir_y, dx, dy) {
+                    if t <= self.ray_range && t < closest {
+                        closest = t;
+                    }
+                }
+            }
+            if closest.is_finite() {
+                out[start + idx] = 1.0 - closest / self.ray_range;
+            }
+        }
+    }
+
+    fn sense_quadrants(&self, creature: &Creature, world: &World, out: &mut Vec<f32>) {
+        let mut counts = [0u32; 4];
+        for &(fx, fy) in &world.food {
+            let dx = fx as i32 - creature.x as i32;
+            let dy = fy as i32 - creature.y as i32;
+            if (dx == 0 && dy == 0) || !self.visible(dx, dy) {
+                continue;
+            }
+            let quadrant = match (dx >= 0, dy < 0) {
+                (true, true) => 0,
+                (false, true) => 1,
+                (false, false) => 2,
+                (true, false) => 3,
+            };
+            counts[quadrant] += 1;
+        }
+        let total = world.food.len().max(1) as f32;
+        out.extend(counts.iter().map(|&count| count as f32 / total));
+    }
+
+    fn sense_nearest_foods(&self, creature: &Creature, world: &World, out: &mut Vec<f32>) {
+        let mut candidates: Vec<(i32, i32, i32)> = world
+            .food
+            .iter()
+            .map(|&(fx, fy)| (fx as i32 - creature.x as i32, fy as i32 - creature.y as i32))
+            .filter(|&(dx, dy)| self.visible(dx, dy))
+            .map(|(dx, dy)| (dx * dx + dy * dy, dx, dy))
+            .collect();
+        candidates.sort_unstable();
+        for idx in 0..self.nearest_foods {
+            match candidates.get(idx) {
+                Some(&(_, dx, dy)) => {
+                    let (nx, ny) = normalize_offset(dx as f32, dy as f32, world);
+                    out.push(nx);
+                    out.push(ny);
+                }
+                None => {
+                    out.push(0.0);
+                    out.push(0.0);
+                }
+            }
+        }
+    }
+
+    fn sense_creatures(&self, creature: &Creature, ctx: &SensorContext, out: &mut Vec<f32>) {
+        let radius = self.creature_radius as i32;
+        let mut nearest: Option<(i32, i32, i32)> = None;
+        let mut nearby = 0u32;
+        let mut others = 0u32;
+        for (idx, position) in ctx.positions.iter().enumerate() {
+            let Some((x, y)) = *position else {
+                continue;
+            };
+            if idx == ctx.index {
+                continue;
+            }
+            others += 1;
+            let dx = x as i32 - creature.x as i32;
+            let dy = y as i32 - creature.y as i32;
+            let dist_sq = dx * dx + dy * dy;
+            if dist_sq <= radius * radius {
+                nearby += 1;
+            }
+            if nearest.is_none_or(|(best, _, _)| dist_sq < best) {
+                nearest = Some((dist_sq, dx, dy));
+            }
+        }
+        let (nx, ny) = nearest
+            .map(|(_, dx, dy)| normalize_offset(dx as f32, dy as f32, ctx.world))
+            .unwrap_or((0.0, 0.0));
+        out.push(nx);
+        out.push(ny);
+        out.push(if others > 0 {
+            nearby as f32 / others as f32
+        } else {
+            0.0
+        });
+    }
+}
+
+fn sense_walls(creature: &Creature, world: &World, out: &mut Vec<f32>) {
+    let span_x = (world.width.max(2) - 1) as f32;
+    let span_y = (world.height.max(2) - 1) as f32;
+    let right = world.width.saturating_sub(1).saturating_sub(creature.x) as f32;
+    let down = world.height.saturating_sub(1).saturating_sub(creature.y) as f32;
+    out.push(1.0 - creature.y as f32 / span_y);
+    out.push(1.0 - down / span_y);
+    out.push(1.0 - creature.x as f32 / span_x);
+    out.push(1.0 - right / span_x);
+}
+
+/// Distance along the ray `(dir_x, dir_y)` at which it enters the unit cell
+/// centred on `(cx, cy)`, or `None` if the ray misses it. The origin cell is
+/// never reported as a hit.
+fn ray_hits_cell(dir_x: f32, dir_y: f32, cx: f32, cy: f32) -> Option<f32> {
+    if cx == 0.0 && cy == 0.0 {
+        return None;
+    }
+    let mut t_enter = f32::NEG_INFINITY;
+    let mut t_exit = f32::INFINITY;
+    for (dir, center) in [(dir_x, cx), (dir_y, cy)] {
+        let (lo, hi) = (center - 0.5, center + 0.5);
+        if dir.abs() < 1e-6 {
+            if lo > 0.0 || hi < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let (a, b) = (lo / dir, hi / dir);
+        t_enter = t_enter.max(a.min(b));
+        t_exit = t_exit.min(a.max(b));
+    }
+    if t_enter <= t_exit && t_exit > 0.0 {
+        Some(t_enter.max(0.0))
+    } else {
+        None
+    }
+}
+
+fn normalize_offset(dx: f32, dy: f32, world: &World) -> (f32, f32) {
+    let nx = if world.width > 1 {
+        (dx / (world.width as f32 - 1.0)).clamp(-1.0, 1.0)
+    } else {
+        0.0
+    };
+    let ny = if world.height > 1 {
+        (dy / (world.height as f32 - 1.0)).clamp(-1.0, 1.0)
+    } else {
+        0.0
+    };
+    (nx, ny)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::Genome;
+    use crate::neural_net::NeuralNet;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn suite(sensors: Vec<SensorKind>) -> SensorSuite {
+        SensorSuite {
+            sensors,
+            vision_radius: 0,
+            distance_metric: DistanceMetric::Euclidean,
+            max_energy: 10.0,
+            max_steps: 100,
+            ray_count: 4,
+            ray_range: 5.0,
+            nearest_foods: 2,
+            creature_radius: 3,
+            actions: vec![Action::Up, Action::Down, Action::Left, Action::Right],
+        }
+    }
+
+    fn creature_at(x: usize, y: usize, rng: &mut StdRng) -> Creature {
+        let brain = NeuralNet::new(Genome::random(vec![1, 1, 4], rng));
+        Creature::from_brain(brain, x, y, 5.0)
+    }
+
+    #[test]
+    fn input_size_matches_names() {
+        let suite = suite(vec![
+            SensorKind::Food,
+            SensorKind::Energy,
+            SensorKind::Rays,
+            SensorKind::NearestFoods,
+            SensorKind::Creatures,
+            SensorKind::LastAction,
+        ]);
+        assert_eq!(suite.input_size(), 2 + 1 + 4 + 4 + 3 + 4);
+        assert_eq!(suite.input_names().len(), suite.input_size());
+    }
+
+    #[test]
+    fn rays_and_walls_see_their_targets() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let world = World {
+            width: 10,
+            height: 10,
+            food: vec![(5, 2)],
+        };
+        let creature = creature_at(5, 5, &mut rng);
+        let ctx = SensorContext {
+            world: &world,
+            positions: &[],
+            index: 0,
+            step: 0,
+        };
+        let mut out = Vec::new();
+        suite(vec![SensorKind::Rays, SensorKind::Walls]).sense(&creature, &ctx, &mut rng, &mut out);
+        assert!(
+            (out[0] - (1.0 - 2.5 / 5.0)).abs() < 1e-5,
+            "up ray hits food"
+        );
+        assert_eq!(&out[1..4], &[0.0, 0.0, 0.0]);
+        let walls = &out[4..];
+        assert!(walls.iter().all(|value| (0.0..=1.0).contains(value)));
+        assert!(
+            walls[3] > walls[2],
+            "closer to the right wall than the left"
+        );
+    }
+}
@@ -1,23 +1,49 @@
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 
+use crate::action::{Action, ActionPolicy, ActionSpace, Heading};
+use crate::baselines::BaselineEvaluator;
+use crate::brain::BrainGenome;
 use crate::config::parse_full_log_keep;
 use crate::config::Config;
-use crate::config::LoggingMode;
+use crate::config::{EvolutionMode, LoggingMode, SelectionMethod, SinkKind};
 use crate::creature::Creature;
-use crate::evolution::{random_population, EvolutionManager};
+use crate::evolution::islands::Islands;
+use crate::evolution::nsga2::{orient, Nsga2Ranking, ParetoFront};
+use crate::evolution::qd::ArchiveExport;
+use crate::fitness::{evaluate_population, ranked_fitness};
 use crate::frame_dump::dump_frame;
+use crate::genome::Genome;
 use crate::metrics::collector::MetricsCollector;
-use crate::metrics::report::build_generation_report;
-use crate::metrics::selection::parse_gen_selection;
-use crate::metrics::writer::{default_run_id, MetricsWriter};
-use crate::metrics::{compute_metrics, GenerationMetrics};
-use crate::world::World;
+use crate::metrics::console::ConsoleSink;
+use crate::metrics::dashboard::DashboardSink;
+use crate::metrics::events::{EventKind, RunEvent};
+use crate::metrics::ndjson::NdjsonSink;
+use crate::metrics::report::{build_generation_report, NonFiniteCounts};
+use crate::metrics::selection::{parse_gen_selection, GenSelection};
+use crate::metrics::serve::ServeSink;
+use crate::metrics::sink::{
+    LegacyCsvSink, LineageRecord, MetricsRows, MetricsSink, RunSummary, SinkSet,
+};
+#[cfg(feature = "sqlite")]
+use crate::metrics::sqlite::SqliteSink;
+use crate::metrics::writer::{write_checkpoint, Champion, Checkpoint, MetricsWriter, RunIdentity};
+use crate::metrics::GenerationMetrics;
+use crate::neural_net::batch::{architecture_groups, BatchedNet};
+use crate::neural_net::ForwardScratch;
+use crate::sensors::{SensorContext, SensorSuite};
+use crate::world::{World, WorldSnapshot};
+
+mod open_ended;
 
 #[derive(Debug, Clone)]
 pub struct SimulationResult {
     pub metrics: Vec<GenerationMetrics>,
     pub total_generations: usize,
+    /// Why `--fail-fast` stopped the run early, if it did.
+    pub aborted: Option<String>,
+    /// Sinks that failed to write their final output.
+    pub sink_errors: Vec<String>,
 }
 
 pub fn run_simulation(config: &Config) -> SimulationResult {
@@ -26,149 +52,698 @@ pub fn run_simulation(config: &Config) -> SimulationResult {
 }
 
 pub fn run_simulation_with_rng(config: &Config, rng: &mut StdRng) -> SimulationResult {
-    let mut metrics = Vec::with_capacity(config.generations);
-    let mut head_metric: Option<GenerationMetrics> = None;
-    let mut tail_metrics: Vec<GenerationMetrics> = Vec::new();
-    let mut population = random_population(config.population, config, rng);
-    let log_selection =
-        parse_gen_selection(&config.log_gens).expect("log-gens should be validated");
-    let full_log_selection = config
-        .full_log_gens
-        .as_deref()
-        .map(parse_gen_selection)
-        .transpose()
-        .expect("full-log-gens should be validated");
-    let run_id = config
-        .run_id
-        .clone()
-        .unwrap_or_else(|| default_run_id(config.seed));
-    let full_log_keep = parse_full_log_keep(&config.full_log_keep)
-        .expect("full-log-keep should be validated")
-        .unwrap_or(config.population);
-    let mut metrics_writer = if log_selection.is_none() {
-        None
-    } else {
-        match MetricsWriter::new(config, run_id.clone()) {
-            Ok(writer) => Some(writer),
-            Err(err) => {
-                eprintln!("Failed to initialize metrics writer: {err}");
-                None
-            }
-        }
+    run_simulation_with_sinks(config, rng, &RunIdentity::from_config(config), Vec::new())
+}
+
+/// Runs the simulation and feeds each generation's report to every sink in
+/// `sinks`. Writes no files of its own apart from a `--fail-fast`
+/// checkpoint; see [`open_sinks`] for the command line's outputs.
+pub fn run_simulation_with_sinks<'a>(
+    config: &'a Config,
+    rng: &mut StdRng,
+    identity: &RunIdentity,
+    mut sinks: Vec<Box<dyn MetricsSink + 'a>>,
+) -> SimulationResult {
+    let mut rows = MetricsRows::new(config);
+    let mut all: Vec<&mut dyn MetricsSink> = vec![&mut rows];
+    all.extend(
+        sinks
+            .iter_mut()
+            .map(|sink| sink.as_mut() as &mut dyn MetricsSink),
+    );
+    let mut sinks = SinkSet::new(all);
+    sinks.event(RunEvent::now(
+        EventKind::Start,
+        None,
+        format!(
+            "run {} with seed {}, {} generations",
+            identity.run_id, config.seed, config.generations
+        ),
+    ));
+
+    let outcome = match config.mode {
+        EvolutionMode::Generational => run_generations(config, rng, &mut sinks, identity),
+        EvolutionMode::OpenEnded => open_ended::run(config, rng, &mut sinks, identity),
     };
-    let mut collector = MetricsCollector::new();
-    let evolution = EvolutionManager {
-        population_size: config.population,
-        elite_fraction: config.elite,
-        mutation_rate: config.mut_rate,
-        mutation_strength: config.mut_strength,
+    let summary = RunSummary {
+        total_generations: outcome.total_generations,
+        aborted: outcome.aborted.as_deref(),
+        champion: outcome.champion.as_ref(),
+        archive: outcome.archive.as_ref(),
     };
+    let sink_errors = sinks.finish(&summary);
+
+    SimulationResult {
+        metrics: rows.rows(),
+        total_generations: outcome.total_generations,
+        aborted: outcome.aborted,
+        sink_errors,
+    }
+}
+
+/// How a run's loop ended, for its [`RunSummary`].
+struct RunOutcome {
+    total_generations: usize,
+    aborted: Option<String>,
+    champion: Option<Champion>,
+    archive: Option<ArchiveExport>,
+}
+
+/// The per-generation choices of `--serve-frames`, `--save-best` and the
+/// full log, shared by both `--mode`s.
+struct Selections {
+    frames: Option<GenSelection>,
+    save_best: Option<GenSelection>,
+    full_log: Option<GenSelection>,
+    full_log_keep: usize,
+}
+
+impl Selections {
+    fn from_config(config: &Config) -> Self {
+        let parse = |spec: Option<&str>, what: &str| {
+            spec.map(parse_gen_selection)
+                .transpose()
+                .unwrap_or_else(|_| panic!("{what} should be validated"))
+        };
+        Self {
+            frames: parse(config.serve_frames.as_deref(), "serve-frames"),
+            save_best: parse(config.save_best.as_deref(), "save-best"),
+            full_log: parse(config.full_log_gens.as_deref(), "full-log-gens"),
+            full_log_keep: parse_full_log_keep(&config.full_log_keep)
+                .expect("full-log-keep should be validated")
+                .unwrap_or(config.population),
+        }
+    }
+
+    fn captures_frames(&self, gen: u32) -> bool {
+        self.frames
+            .as_ref()
+            .is_some_and(|selection| selection.matches(gen))
+    }
+
+    fn include_individuals(&self, config: &Config, gen: u32) -> bool {
+        matches!(config.logging_mode, LoggingMode::Full)
+            && self
+                .full_log
+                .as_ref()
+                .map(|selection| selection.matches(gen))
+                .unwrap_or(true)
+    }
+
+    /// Hands the best creature of `population` to the sinks if `--save-best`
+    /// selects `gen`.
+    fn save_best(&self, sinks: &mut SinkSet, gen: u32, population: &[Creature], config: &Config) {
+        if !self
+            .save_best
+            .as_ref()
+            .is_some_and(|selection| selection.matches(gen))
+        {
+            return;
+        }
+        if let Some((creature, fitness)) = generation_best(population, config) {
+            sinks.best(&Champion {
+                generation: gen,
+                fitness,
+                brain: creature.brain.snapshot(),
+            });
+        }
+    }
+}
+
+fn run_generations(
+    config: &Config,
+    rng: &mut StdRng,
+    sinks: &mut SinkSet,
+    identity: &RunIdentity,
+) -> RunOutcome {
+    let island_configs = config
+        .island_configs()
+        .expect("island-config should be validated");
+    let (mut islands, mut population) = Islands::new(config, &island_configs, rng);
+    let selections = Selections::from_config(config);
+    let mut collector = MetricsCollector::new();
+    // Frame dumps and captured frames follow the first island only.
+    let mut runners: Vec<EpisodeRunner> = island_configs
+        .iter()
+        .enumerate()
+        .map(
+            |(idx, island_config)| match EpisodeRunner::new(island_config) {
+                runner if idx > 0 => runner.quiet(),
+                runner => runner,
+            },
+        )
+        .collect();
+    // The baselines play the first island's world under its settings.
+    let mut baselines = BaselineEvaluator::new(&island_configs[0]);
+    let mut champion: Option<Champion> = None;
+    let mut aborted = None;
+    let mut total_generations = config.generations;
+    let mut newborn_from = 0;
+    let mut hv_reference =
+        (!config.hv_reference.is_empty()).then(|| orient(&config.objectives, &config.hv_reference));
 
     for gen in 0..config.generations {
-        let mut world = World::new(config.width, config.height, config.food, rng);
-        initialize_population(&mut population, &world, config.max_energy, rng);
-        let mut food_eaten_total = 0;
+        let capture = selections.captures_frames(gen as u32);
+        let mut baseline_start = None;
         let mut steps_run = 0;
-        for step in 0..config.max_steps {
-            steps_run = step + 1;
-            let mut alive_any = false;
-            for creature in &mut population {
-                if !creature.alive {
-                    continue;
+        for (idx, (island, runner)) in islands.iter().zip(&mut runners).enumerate() {
+            let creatures = &mut population[island.range.clone()];
+            let mut world = World::new(config.width, config.height, island.config.food, rng);
+            if idx == 0 {
+                if !config.baselines.is_empty() {
+                    baseline_start = Some((world.clone(), rng.clone()));
+                }
+                runner.capture_frames(capture);
+            }
+            let outcome = runner.run(gen, creatures, &mut world, rng, &mut collector);
+            if idx == 0 {
+                if config.tui || config.serve.is_some() {
+                    sinks.world(&WorldSnapshot::capture(&world, creatures));
+                }
+                if capture {
+                    sinks.frames(gen as u32, &runner.take_frames());
                 }
-                alive_any = true;
-                let (dx, dy) = if config.food_vision_radius == 0 {
-                    world.nearest_food(creature.x, creature.y)
+            }
+            if outcome.extinct {
+                let island_prefix = if islands.len() > 1 {
+                    format!("island {idx}: ")
                 } else {
-                    world
-                        .nearest_food_within(
-                            creature.x,
-                            creature.y,
-                            config.food_vision_radius,
-                            config.distance_metric,
-                        )
-                        .unwrap_or((0.0, 0.0))
+                    String::new()
                 };
-                let inputs = creature.perceive(dx, dy, config.max_energy, rng);
-                let action = creature.decide(&inputs);
-                creature.act(action, world.width, world.height, config.move_cost);
-                if creature.alive && world.try_eat_food(creature.x, creature.y, rng) {
-                    creature.energy = (creature.energy + config.food_energy).min(config.max_energy);
-                    creature.food_collected = creature.food_collected.saturating_add(1);
-                    food_eaten_total += 1;
-                    collector.on_food_eaten(1);
-                }
+                sinks.event(RunEvent::now(
+                    EventKind::Extinction,
+                    Some(gen as u32),
+                    format!(
+                        "{island_prefix}None alive after {} steps",
+                        outcome.steps_run
+                    ),
+                ));
             }
-            if config.dump_frames && step % config.frame_every == 0 && gen == 9999 || gen == 10{
-                let _ = dump_frame(&config.frames_dir, gen, step, &world, &population);
+            steps_run = steps_run.max(outcome.steps_run);
+        }
+        let baseline_scores = baseline_start
+            .map(|(world, rng)| baselines.evaluate(gen, &world, &rng))
+            .unwrap_or_default();
+
+        let archive_stats = islands.record_generation(&population);
+        update_champion(&mut champion, gen as u32, &population, config);
+        selections.save_best(sinks, gen as u32, &population, config);
+
+        let mut next_population = None;
+        if gen + 1 < config.generations {
+            let (next, migrated) = islands.next_generation(gen, &population, rng, &mut collector);
+            if migrated > 0 {
+                sinks.event(RunEvent::now(
+                    EventKind::Migration,
+                    Some(gen as u32),
+                    format!(
+                        "{migrated} creatures migrated between {} islands",
+                        islands.len()
+                    ),
+                ));
             }
-            if !alive_any {
-                println!("None alive!");
+            next_population = Some(next);
+        }
+
+        let mut report = build_generation_report(
+            gen as u32,
+            steps_run as u32,
+            &population,
+            &collector,
+            config,
+            &identity.run_id,
+            &identity.config_hash,
+            identity.git_commit.as_deref(),
+            selections.include_individuals(config, gen as u32),
+            selections.full_log_keep,
+        );
+        if islands.len() > 1 {
+            report.islands = islands.summaries(&population);
+        }
+        // The baselines played the first island's world only.
+        let played_mean = report
+            .islands
+            .first()
+            .map_or(report.fitness_mean, |island| island.fitness_mean);
+        report.set_baselines(&baseline_scores, played_mean);
+        if let Some(stats) = &archive_stats {
+            report.set_archive(stats);
+        }
+        let front = matches!(config.selection_method, SelectionMethod::Nsga2).then(|| {
+            let ranking = Nsga2Ranking::new(&population, &config.objectives);
+            let reference = hv_reference.get_or_insert_with(|| ranking.default_reference());
+            ParetoFront::new(
+                gen as u32,
+                &population,
+                &ranking,
+                &config.objectives,
+                reference,
+            )
+        });
+        if let Some(front) = &front {
+            report.set_pareto_front(front);
+        }
+        // Ids only grow, so creatures below `newborn_from` were recorded in
+        // an earlier generation they survived from.
+        let lineage: Vec<LineageRecord> = LineageRecord::of(gen as u32, &population)
+            .into_iter()
+            .filter(|record| record.id >= newborn_from)
+            .collect();
+        newborn_from = population
+            .iter()
+            .map(|creature| creature.id + 1)
+            .fold(newborn_from, u64::max);
+        sinks.record(&report, front.as_ref(), &lineage);
+
+        if config.fail_fast {
+            if let Some(reason) = check_finite(gen as u32, &population, config, identity, sinks) {
+                aborted = Some(reason);
+                total_generations = gen + 1;
                 break;
             }
         }
+        if let Some(next_population) = next_population {
+            population = next_population;
+        }
+        collector.reset();
+    }
 
-        let gen_metrics = compute_metrics(gen, &population, food_eaten_total, config);
-        if matches!(config.logging_mode, LoggingMode::Full) {
-            metrics.push(gen_metrics);
-        } else if gen == 0 {
-            head_metric = Some(gen_metrics);
-        } else {
-            tail_metrics.push(gen_metrics);
-            let keep = (config.quick_keep.saturating_sub(1)) as usize;
-            if tail_metrics.len() > keep {
-                tail_metrics.remove(0);
+    RunOutcome {
+        total_generations,
+        aborted,
+        champion,
+        archive: islands.export_archive(),
+    }
+}
+
+/// For `--fail-fast`: writes a checkpoint and returns why the run stops if
+/// `population` holds a NaN or infinite fitness or weight.
+fn check_finite(
+    gen: u32,
+    population: &[Creature],
+    config: &Config,
+    identity: &RunIdentity,
+    sinks: &mut SinkSet,
+) -> Option<String> {
+    let non_finite = NonFiniteCounts::of(population, config);
+    if !non_finite.any() {
+        return None;
+    }
+    let reason = format!(
+        "generation {gen}: {} non-finite fitness values, {} non-finite weights",
+        non_finite.fitness, non_finite.params
+    );
+    let checkpoint = Checkpoint::new(gen, reason.clone(), population);
+    match write_checkpoint(&identity.run_id, &checkpoint) {
+        Ok(path) => {
+            sinks.event(RunEvent::now(
+                EventKind::Checkpoint,
+                Some(gen),
+                format!("{} ({reason})", path.display()),
+            ));
+        }
+        Err(err) => sinks.error(Some(gen), format!("Failed to write checkpoint: {err}")),
+    }
+    Some(reason)
+}
+
+pub struct EpisodeOutcome {
+    pub food_eaten_total: usize,
+    pub steps_run: usize,
+    /// Every creature died before `max_steps`.
+    pub extinct: bool,
+}
+
+/// Opens the command line's outputs: the `--out` CSV, the run directory
+/// (unless `--log-gens none`), the `--sinks` outputs in `runs/<run_id>/`, the
+/// `--serve` endpoint, the `--tui` dashboard and the console. One that cannot
+/// be opened is skipped and reported as an `error` event to the others.
+pub fn open_sinks<'a>(
+    config: &'a Config,
+    identity: &RunIdentity,
+) -> Vec<Box<dyn MetricsSink + 'a>> {
+    let log_selection =
+        parse_gen_selection(&config.log_gens).expect("log-gens should be validated");
+    let mut sinks: Vec<Box<dyn MetricsSink + 'a>> = vec![Box::new(LegacyCsvSink::new(config))];
+    let mut errors = Vec::new();
+    if !log_selection.is_none() {
+        match MetricsWriter::new(config, identity) {
+            Ok(writer) => sinks.push(Box::new(writer)),
+            Err(err) => errors.push(format!("Failed to initialize metrics writer: {err}")),
+        }
+    }
+    let run_dir = std::path::PathBuf::from("runs").join(&identity.run_id);
+    for kind in &config.sinks {
+        let opened: Result<Box<dyn MetricsSink>, Box<dyn std::error::Error>> = match kind {
+            SinkKind::Ndjson => NdjsonSink::open(&run_dir.join("metrics.ndjson"))
+                .map(|sink| Box::new(sink) as Box<dyn MetricsSink>),
+            #[cfg(feature = "sqlite")]
+            SinkKind::Sqlite => SqliteSink::open(&run_dir.join("metrics.sqlite"))
+                .map(|sink| Box::new(sink) as Box<dyn MetricsSink>),
+            #[cfg(not(feature = "sqlite"))]
+            SinkKind::Sqlite => Err("built without the sqlite feature".into()),
+        };
+        match opened {
+            Ok(sink) => sinks.push(sink),
+            Err(err) => errors.push(format!("Failed to open {kind:?} sink: {err}")),
+        }
+    }
+    let mut console = ConsoleSink::new(config);
+    if let Some(addr) = &config.serve {
+        match ServeSink::bind(addr, config, identity) {
+            Ok(server) => {
+                console = console.serving(server.local_addr());
+                sinks.push(Box::new(server));
             }
+            Err(err) => errors.push(format!("Failed to serve on {addr}: {err}")),
         }
+    }
+    if config.tui {
+        sinks.push(Box::new(DashboardSink::new(config)));
+    }
+    sinks.push(Box::new(console));
 
-        let mut next_population = None;
-        if gen + 1 < config.generations {
-            next_population = Some(evolution.next_generation(&population, config, rng, &mut collector));
-        }
-        if let Some(writer) = metrics_writer.as_mut() {
-            let should_log = log_selection.matches(gen as u32);
-            if should_log {
-                let should_full = matches!(config.logging_mode, LoggingMode::Full)
-                    && full_log_selection
-                        .as_ref()
-                        .map(|selection| selection.matches(gen as u32))
-                        .unwrap_or(true);
-                let report = build_generation_report(
-                    gen as u32,
-                    steps_run as u32,
-                    &population,
-                    &collector,
-                    config,
-                    writer.run_id(),
-                    writer.config_hash(),
-                    writer.git_commit(),
-                    should_full,
-                    full_log_keep,
-                );
-                if let Err(err) = writer.write_generation(&report) {
-                    eprintln!("Failed to write generation report: {err}");
+    let mut opened = SinkSet::new(sinks.iter_mut().map(|sink| sink.as_mut() as _).collect());
+    for message in errors {
+        opened.error(None, message);
+    }
+    sinks
+}
+
+/// Plays one generation's episode: places the population, then steps every
+/// living creature until `max_steps` or extinction. Reuses its buffers across
+/// episodes.
+pub struct EpisodeRunner<'a> {
+    config: &'a Config,
+    sensors: SensorSuite,
+    actions: ActionSpace,
+    policy: ActionPolicy,
+    chosen: Vec<Action>,
+    inputs: Vec<f32>,
+    scratch: ForwardScratch,
+    batches: Option<Batches>,
+    quiet: bool,
+    frames: Option<Vec<WorldSnapshot>>,
+}
+
+/// `--batch-forward`: the episode's layered brains as one [`BatchedNet`] per
+/// architecture, with the sensor inputs and network outputs of a step stored
+/// one row per creature.
+#[derive(Debug, Default)]
+struct Batches {
+    groups: Vec<ForwardBatch>,
+    /// Whether each creature's brain runs in one of `groups`.
+    batched: Vec<bool>,
+    inputs: Vec<f32>,
+    outputs: Vec<f32>,
+    lane_inputs: Vec<f32>,
+    lane_outputs: Vec<f32>,
+}
+
+#[derive(Debug)]
+struct ForwardBatch {
+    /// The creature index of every lane.
+    members: Vec<usize>,
+    net: BatchedNet,
+}
+
+impl Batches {
+    /// Batches the layered brains of `population` by architecture. Other
+    /// brains are evaluated one by one.
+    fn rebuild(&mut self, population: &[Creature]) {
+        let layered: Vec<(usize, &Genome)> = population
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, creature)| match creature.brain.genome() {
+                Some(BrainGenome::Layered(genome)) => Some((idx, genome)),
+                _ => None,
+            })
+            .collect();
+        let genomes: Vec<&Genome> = layered.iter().map(|&(_, genome)| genome).collect();
+        self.batched.clear();
+        self.batched.resize(population.len(), false);
+        self.groups = architecture_groups(&genomes)
+            .into_iter()
+            .map(|group| {
+                let members: Vec<usize> = group.iter().map(|&lane| layered[lane].0).collect();
+                for &member in &members {
+                    self.batched[member] = true;
                 }
+                let lanes: Vec<&Genome> = group.iter().map(|&lane| genomes[lane]).collect();
+                ForwardBatch {
+                    members,
+                    net: BatchedNet::new(&lanes).expect("groups share an architecture"),
+                }
+            })
+            .collect();
+    }
+
+    /// Runs every batch on the stored inputs.
+    fn forward(&mut self, input_size: usize, output_size: usize) {
+        self.outputs.clear();
+        self.outputs.resize(self.batched.len() * output_size, 0.0);
+        for group in &mut self.groups {
+            self.lane_inputs.clear();
+            for &member in &group.members {
+                self.lane_inputs
+                    .extend_from_slice(&self.inputs[member * input_size..][..input_size]);
+            }
+            group.net.forward(&self.lane_inputs, &mut self.lane_outputs);
+            for (lane, &member) in group.members.iter().enumerate() {
+                self.outputs[member * output_size..][..output_size]
+                    .copy_from_slice(&self.lane_outputs[lane * output_size..][..output_size]);
             }
         }
-        if let Some(next_population) = next_population {
-            population = next_population;
+    }
+}
+
+impl<'a> EpisodeRunner<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        let sensors = SensorSuite::from_config(config);
+        let actions = ActionSpace::from_config(config);
+        Self {
+            config,
+            policy: ActionPolicy::from_config(config),
+            chosen: Vec::with_capacity(actions.len()),
+            inputs: Vec::with_capacity(sensors.input_size()),
+            scratch: ForwardScratch::default(),
+            batches: config.batch_forward.then(Batches::default),
+            sensors,
+            actions,
+            quiet: false,
+            frames: None,
         }
-        collector.reset();
-        if config.progress > 0 && (gen + 1) % config.progress == 0 {
-            println!("Generation {} complete", gen + 1);
+    }
+
+    pub fn with_policy(mut self, policy: ActionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// No frame dumps, for side evaluations.
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    /// Whether the next episodes keep a snapshot of the world after every
+    /// step, for `take_frames`.
+    pub fn capture_frames(&mut self, capture: bool) {
+        self.frames = capture.then(Vec::new);
+    }
+
+    pub fn take_frames(&mut self) -> Vec<WorldSnapshot> {
+        self.frames.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    pub fn run(
+        &mut self,
+        gen: usize,
+        population: &mut [Creature],
+        world: &mut World,
+        rng: &mut StdRng,
+        collector: &mut MetricsCollector,
+    ) -> EpisodeOutcome {
+        let config = self.config;
+        initialize_population(population, world, config.max_energy, rng);
+        if let Some(batches) = self.batches.as_mut() {
+            batches.rebuild(population);
+        }
+        let mut food_eaten_total = 0;
+        let mut steps_run = 0;
+        let mut extinct = false;
+        for step in 0..config.max_steps {
+            steps_run = step + 1;
+            let eaten = self.step(step, population, world, rng, collector);
+            food_eaten_total += eaten.unwrap_or(0);
+            if !self.quiet
+                && (config.dump_frames && step % config.frame_every == 0 && gen == 9999
+                    || gen == 10)
+            {
+                let _ = dump_frame(&config.frames_dir, gen, step, world, population);
+            }
+            if let Some(frames) = self.frames.as_mut() {
+                frames.push(WorldSnapshot::capture(world, population));
+            }
+            if eaten.is_none() {
+                extinct = true;
+                break;
+            }
+        }
+        evaluate_population(population, config);
+        EpisodeOutcome {
+            food_eaten_total,
+            steps_run,
+            extinct,
         }
     }
 
-    if !matches!(config.logging_mode, LoggingMode::Full) {
-        if let Some(head) = head_metric.take() {
-            metrics.push(head);
+    /// Lets every living creature sense, decide, act and eat once. Returns
+    /// the food eaten, or `None` if no creature was alive.
+    ///
+    /// Under `--batch-forward` every creature senses before any of them
+    /// acts; otherwise each one senses the world the previous ones left.
+    fn step(
+        &mut self,
+        step: usize,
+        population: &mut [Creature],
+        world: &mut World,
+        rng: &mut StdRng,
+        collector: &mut MetricsCollector,
+    ) -> Option<usize> {
+        let config = self.config;
+        let mut food_eaten = 0;
+        let mut alive_any = false;
+        let positions: Vec<Option<(usize, usize)>> = if self.sensors.needs_positions() {
+            population
+                .iter()
+                .map(|creature| creature.alive.then_some((creature.x, creature.y)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        if self.batches.is_some() {
+            self.sense_batched(step, population, world, rng, &positions);
         }
-        metrics.extend(tail_metrics);
+        let input_size = self.sensors.input_size();
+        let output_size = self.actions.len();
+        for (idx, creature) in population.iter_mut().enumerate() {
+            if !creature.alive {
+                continue;
+            }
+            alive_any = true;
+            match &self.batches {
+                Some(batches) => {
+                    let outputs = if batches.batched[idx] {
+                        &batches.outputs[idx * output_size..][..output_size]
+                    } else {
+                        creature.brain.forward_into(
+                            &batches.inputs[idx * input_size..][..input_size],
+                            &mut self.scratch,
+                        )
+                    };
+                    self.actions
+                        .choose(outputs, &self.policy, rng, &mut self.chosen);
+                }
+                None => {
+                    let ctx = SensorContext {
+                        world,
+                        positions: &positions,
+                        index: idx,
+                        step,
+                    };
+                    self.sensors.sense(creature, &ctx, rng, &mut self.inputs);
+                    creature
+                        .brain
+                        .observe_world(world, (creature.x, creature.y), creature.heading);
+                    creature.decide(
+                        &self.inputs,
+                        &self.actions,
+                        &self.policy,
+                        rng,
+                        &mut self.scratch,
+                        &mut self.chosen,
+                    );
+                }
+            }
+            creature.act(&self.chosen, world.width, world.height, &self.actions);
+            collector.on_visit(creature.x, creature.y, world.width, world.height);
+            if creature.alive && world.try_eat_food(creature.x, creature.y, rng) {
+                creature.energy = (creature.energy + config.food_energy).min(config.max_energy);
+                creature.food_collected = creature.food_collected.saturating_add(1);
+                food_eaten += 1;
+                collector.on_food_eaten(1);
+            }
+        }
+        alive_any.then_some(food_eaten)
     }
 
-    SimulationResult {
-        metrics,
-        total_generations: config.generations,
+    /// Stores the sensor inputs of every living creature, then runs the
+    /// batches on them.
+    fn sense_batched(
+        &mut self,
+        step: usize,
+        population: &mut [Creature],
+        world: &World,
+        rng: &mut StdRng,
+        positions: &[Option<(usize, usize)>],
+    ) {
+        let batches = self.batches.as_mut().expect("batch-forward is on");
+        let input_size = self.sensors.input_size();
+        batches.inputs.clear();
+        batches.inputs.resize(population.len() * input_size, 0.0);
+        for (idx, creature) in population.iter_mut().enumerate() {
+            if !creature.alive {
+                continue;
+            }
+            let ctx = SensorContext {
+                world,
+                positions,
+                index: idx,
+                step,
+            };
+            self.sensors.sense(creature, &ctx, rng, &mut self.inputs);
+            batches.inputs[idx * input_size..][..input_size].copy_from_slice(&self.inputs);
+            creature
+                .brain
+                .observe_world(world, (creature.x, creature.y), creature.heading);
+        }
+        batches.forward(input_size, self.actions.len());
+    }
+}
+
+/// The first creature with the highest ranked fitness.
+fn generation_best<'p>(population: &'p [Creature], config: &Config) -> Option<(&'p Creature, f32)> {
+    population
+        .iter()
+        .map(|creature| (creature, ranked_fitness(creature, config)))
+        .fold(
+            None,
+            |best: Option<(&Creature, f32)>, (creature, fitness)| match best {
+                Some((_, best_fitness)) if best_fitness >= fitness => best,
+                _ => Some((creature, fitness)),
+            },
+        )
+}
+
+fn update_champion(
+    champion: &mut Option<Champion>,
+    gen: u32,
+    population: &[Creature],
+    config: &Config,
+) {
+    let Some((creature, fitness)) = generation_best(population, config) else {
+        return;
+    };
+    if champion
+        .as_ref()
+        .is_none_or(|current| fitness > current.fitness)
+    {
+        *champion = Some(Champion {
+            generation: gen,
+            fitness,
+            brain: creature.brain.snapshot(),
+        });
     }
 }
 
@@ -184,6 +759,52 @@ fn initialize_population(
         creature.energy = max_energy;
         creature.age = 0;
         creature.alive = true;
+        creature.heading = Heading::default();
+        creature.brain.reset_state();
         creature.reset_tracking();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::evolution::random_population;
+
+    /// Without food and under `argmax` no creature changes what another
+    /// senses, so batching the brains must not change the episode.
+    #[test]
+    fn batched_episode_matches_the_sequential_one() {
+        let play = |batch: bool| {
+            let mut args = vec![
+                "evobrain",
+                "--food=0",
+                "--population=16",
+                "--max-steps=30",
+                "--brain=evolvable",
+                "--recurrence=elman",
+                "--seed=5",
+            ];
+            if batch {
+                args.push("--batch-forward");
+            }
+            let config = Config::try_parse_from(args).unwrap();
+            let mut rng = StdRng::seed_from_u64(config.seed);
+            let mut population = random_population(config.population, &config, &mut rng);
+            let mut world = World::new(config.width, config.height, config.food, &mut rng);
+            EpisodeRunner::new(&config).quiet().run(
+                0,
+                &mut population,
+                &mut world,
+                &mut rng,
+                &mut MetricsCollector::new(),
+            );
+            population
+                .iter()
+                .map(|creature| (creature.x, creature.y, creature.action_counts))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(play(false), play(true));
+    }
+}
@@ -0,0 +1,297 @@
+use rand::rngs::StdRng;
+
+use super::{
+    check_finite, initialize_population, update_champion, EpisodeRunner, RunOutcome, Selections,
+};
+use crate::config::{Config, Reproduction};
+use crate::creature::Creature;
+use crate::evolution::{random_population, EvolutionManager};
+use crate::fitness::evaluate_population;
+use crate::metrics::collector::MetricsCollector;
+use crate::metrics::events::{EventKind, RunEvent};
+use crate::metrics::report::build_generation_report;
+use crate::metrics::sink::{LineageRecord, SinkSet};
+use crate::metrics::writer::{Champion, RunIdentity};
+use crate::world::{World, WorldSnapshot};
+
+/// `--mode open-ended`: one world that is never reset. After every step,
+/// creatures past `--max-age` die, those with `--birth-energy` give birth to
+/// a mutated child on their cell, the dead are removed and random newcomers
+/// top the population up to `--min-population`. Every `--sample-every` steps
+/// a report covers everyone alive at some point of the window, and the
+/// lineage lists the creatures that appeared in it.
+pub(super) fn run(
+    config: &Config,
+    rng: &mut StdRng,
+    sinks: &mut SinkSet,
+    identity: &RunIdentity,
+) -> RunOutcome {
+    let mut evolution = EvolutionManager::new(config);
+    let mut population = random_population(config.population, config, rng);
+    let mut world = World::new(config.width, config.height, config.food, rng);
+    initialize_population(&mut population, &world, config.max_energy, rng);
+    evolution.assign_ids(&mut population);
+    let selections = Selections::from_config(config);
+    let mut runner = EpisodeRunner::new(config);
+    let mut collector = MetricsCollector::new();
+    let mut champion: Option<Champion> = None;
+    let mut aborted = None;
+    let mut total_generations = config.generations;
+    let mut appeared = LineageRecord::of(0, &population);
+    let mut departed: Vec<Creature> = Vec::new();
+    let mut step = 0;
+
+    for window in 0..config.generations {
+        let gen = window as u32;
+        let capture = selections.captures_frames(gen);
+        let mut frames = Vec::new();
+        let mut steps_run = 0;
+        for _ in 0..config.sample_every {
+            // The step sensor cycles through `--max-steps`.
+            runner.step(
+                step % config.max_steps.max(1),
+                &mut population,
+                &mut world,
+                rng,
+                &mut collector,
+            );
+            step += 1;
+            steps_run += 1;
+            for creature in &mut population {
+                if creature.alive && creature.age >= config.max_age {
+                    creature.alive = false;
+                }
+            }
+            give_birth(&mut population, &mut evolution, config, rng, &mut collector)
+                .for_each(|child| appeared.push(lineage(gen, child)));
+            let (alive, dead): (Vec<Creature>, Vec<Creature>) =
+                population.drain(..).partition(|creature| creature.alive);
+            population = alive;
+            departed.extend(dead);
+            let missing = config.min_population.saturating_sub(population.len());
+            if missing > 0 {
+                let mut newcomers = random_population(missing, config, rng);
+                initialize_population(&mut newcomers, &world, config.max_energy, rng);
+                evolution.assign_ids(&mut newcomers);
+                appeared.extend(LineageRecord::of(gen, &newcomers));
+                population.extend(newcomers);
+            }
+            if capture {
+                frames.push(WorldSnapshot::capture(&world, &population));
+            }
+            if population.is_empty() {
+                break;
+            }
+        }
+        if config.tui || config.serve.is_some() {
+            sinks.world(&WorldSnapshot::capture(&world, &population));
+        }
+        if capture {
+            sinks.frames(gen, &frames);
+        }
+
+        // The window's creatures, dead first, in one slice for the report;
+        // the living are split off again afterwards.
+        let dead = departed.len();
+        departed.append(&mut population);
+        let creatures = &mut departed;
+        evaluate_population(creatures, config);
+        update_champion(&mut champion, gen, creatures, config);
+        selections.save_best(sinks, gen, creatures, config);
+        let report = build_generation_report(
+            gen,
+            steps_run,
+            creatures,
+            &collector,
+            config,
+            &identity.run_id,
+            &identity.config_hash,
+            identity.git_commit.as_deref(),
+            selections.include_individuals(config, gen),
+            selections.full_log_keep,
+        );
+        sinks.record(&report, None, &appeared);
+        appeared.clear();
+        let stop = if config.fail_fast {
+            check_finite(gen, creatures, config, identity, sinks)
+        } else {
+            None
+        };
+        population = departed.split_off(dead);
+        departed.clear();
+        // Window scores must not outlive their window: `--arch-inherit
+        // fitter` compares parents by fitness.
+        for creature in &mut population {
+            creature.fitness = None;
+        }
+        collector.reset();
+
+        if let Some(reason) = stop {
+            aborted = Some(reason);
+            total_generations = window + 1;
+            break;
+        }
+        if population.is_empty() {
+            sinks.event(RunEvent::now(
+                EventKind::Extinction,
+                Some(gen),
+                format!("None alive after {step} steps"),
+            ));
+            total_generations = window + 1;
+            break;
+        }
+    }
+
+    RunOutcome {
+        total_generations,
+        aborted,
+        champion,
+        archive: None,
+    }
+}
+
+fn lineage(generation: u32, creature: &Creature) -> LineageRecord {
+    LineageRecord {
+        id: creature.id,
+        generation,
+        parent_a: creature.parents.map(|(a, _)| a),
+        parent_b: creature.parents.map(|(_, b)| b),
+    }
+}
+
+fn ready(creature: &Creature, config: &Config) -> bool {
+    creature.alive && creature.energy >= config.birth_energy
+}
+
+/// Lets every living creature with `--birth-energy` reproduce, alone or with
+/// the next ready creature within `--mate-radius`, until the living reach
+/// `--max-population`. Children start on their (first) parent's cell
+/// with the energy the birth cost. Returns the children.
+fn give_birth<'p>(
+    population: &'p mut Vec<Creature>,
+    evolution: &mut EvolutionManager,
+    config: &Config,
+    rng: &mut StdRng,
+    collector: &mut MetricsCollector,
+) -> impl Iterator<Item = &'p Creature> {
+    let parents = population.len();
+    // Creatures that died this step are still listed but hold no slot.
+    let mut living = population.iter().filter(|creature| creature.alive).count();
+    for idx in 0..parents {
+        if living >= config.max_population {
+            break;
+        }
+        if !ready(&population[idx], config) {
+            continue;
+        }
+        let mate = match config.reproduction {
+            Reproduction::Asexual => idx,
+            Reproduction::Sexual => {
+                let parent = &population[idx];
+                let found = (idx + 1..parents).find(|&other| {
+                    let other = &population[other];
+                    ready(other, config)
+                        && parent.x.abs_diff(other.x) <= config.mate_radius
+                        && parent.y.abs_diff(other.y) <= config.mate_radius
+                });
+                match found {
+                    Some(mate) => mate,
+                    None => continue,
+                }
+            }
+        };
+        let mut child = evolution.offspring(&population[idx], &population[mate], config, rng);
+        if mate == idx {
+            population[idx].energy -= config.birth_cost;
+        } else {
+            population[idx].energy -= config.birth_cost / 2.0;
+            population[mate].energy -= config.birth_cost / 2.0;
+        }
+        child.x = population[idx].x;
+        child.y = population[idx].y;
+        child.energy = config.birth_cost;
+        population.push(child);
+        living += 1;
+        collector.on_reproduction();
+    }
+    population[parents..].iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn ready_creatures_pay_for_children_up_to_the_living_cap() {
+        let config =
+            Config::try_parse_from(["evobrain", "--mode", "open-ended", "--max-population", "5"])
+                .unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut evolution = EvolutionManager::new(&config);
+        let mut population = random_population(4, &config, &mut rng);
+        evolution.assign_ids(&mut population);
+        for (creature, energy) in population.iter_mut().zip([90.0, 10.0, 85.0, 95.0]) {
+            creature.energy = energy;
+        }
+        population[1].alive = false;
+        let mut collector = MetricsCollector::new();
+
+        let children: Vec<_> = give_birth(
+            &mut population,
+            &mut evolution,
+            &config,
+            &mut rng,
+            &mut collector,
+        )
+        .map(|child| (child.id, child.parents, child.energy))
+        .collect();
+        assert_eq!(
+            children,
+            vec![(4, Some((0, 0)), 40.0), (5, Some((2, 2)), 40.0)]
+        );
+        assert_eq!(population[0].energy, 50.0);
+        assert_eq!(population[3].energy, 95.0);
+        assert_eq!(collector.reproductions_total(), 2);
+    }
+
+    #[test]
+    fn sexual_reproduction_needs_a_ready_mate_nearby() {
+        let config = Config::try_parse_from([
+            "evobrain",
+            "--mode",
+            "open-ended",
+            "--reproduction",
+            "sexual",
+            "--crossover",
+            "blend",
+        ])
+        .unwrap();
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut evolution = EvolutionManager::new(&config);
+        let mut population = random_population(3, &config, &mut rng);
+        evolution.assign_ids(&mut population);
+        for (creature, x) in population.iter_mut().zip([0, 20, 2]) {
+            creature.x = x;
+            creature.y = 0;
+            creature.energy = 90.0;
+        }
+        let mut collector = MetricsCollector::new();
+
+        let parents: Vec<Option<(u64, u64)>> = give_birth(
+            &mut population,
+            &mut evolution,
+            &config,
+            &mut rng,
+            &mut collector,
+        )
+        .map(|child| child.parents)
+        .collect();
+        assert_eq!(parents, vec![Some((0, 2))]);
+        assert_eq!(population[0].energy, 70.0);
+        assert_eq!(population[1].energy, 90.0);
+        assert_eq!(population[2].energy, 70.0);
+    }
+}
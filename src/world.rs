@@ -1,6 +1,8 @@
 use rand::Rng;
+use serde::Serialize;
 
 use crate::config::DistanceMetric;
+use crate::creature::Creature;
 
 #[derive(Debug, Clone)]
 pub struct World {
@@ -9,6 +11,30 @@ pub struct World {
     pub food: Vec<(usize, usize)>,
 }
 
+/// Food and creature positions at one moment, for display.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WorldSnapshot {
+    pub width: usize,
+    pub height: usize,
+    pub food: Vec<(usize, usize)>,
+    /// `(x, y, alive)` per creature.
+    pub creatures: Vec<(usize, usize, bool)>,
+}
+
+impl WorldSnapshot {
+    pub fn capture(world: &World, population: &[Creature]) -> Self {
+        Self {
+            width: world.width,
+            height: world.height,
+            food: world.food.clone(),
+            creatures: population
+                .iter()
+                .map(|creature| (creature.x, creature.y, creature.alive))
+                .collect(),
+        }
+    }
+}
+
 impl World {
     pub fn new(width: usize, height: usize, food_count: usize, rng: &mut impl Rng) -> Self {
         let mut food = Vec::with_capacity(food_count);
@@ -79,6 +105,23 @@ impl World {
         best.map(|(dx, dy, _)| Self::normalize_vector(dx, dy, self.width, self.height))
     }
 
+    /// Direction to the nearest food as the `food` sensor reports it: unlimited
+    /// range when `radius` is 0, otherwise zero when nothing is in view.
+    pub fn food_direction(
+        &self,
+        x: usize,
+        y: usize,
+        radius: u32,
+        metric: DistanceMetric,
+    ) -> (f32, f32) {
+        if radius == 0 {
+            self.nearest_food(x, y)
+        } else {
+            self.nearest_food_within(x, y, radius, metric)
+                .unwrap_or((0.0, 0.0))
+        }
+    }
+
     pub fn try_eat_food(&mut self, x: usize, y: usize, rng: &mut impl Rng) -> bool {
         if let Some(idx) = self.food.iter().position(|&(fx, fy)| fx == x && fy == y) {
             self.food[idx] = Self::random_pos(self.width, self.height, rng);
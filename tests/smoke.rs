@@ -1,10 +1,20 @@
+use std::cell::RefCell;
+use std::error::Error;
 use std::fs;
-use std::io::Read;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
 use std::path::PathBuf;
+use std::rc::Rc;
 
+use clap::Parser;
 use evobrain::config::Config;
-use evobrain::genome::{genome_size, Genome};
-use evobrain::simulation::run_simulation;
+use evobrain::genome::{genome_size, Genome, WeightBounds};
+use evobrain::metrics::report::GenerationReport;
+use evobrain::metrics::serve::ServeSink;
+use evobrain::metrics::sink::{LegacyCsvSink, MetricsSink};
+use evobrain::metrics::writer::{MetricsWriter, RunIdentity};
+use evobrain::metrics::GenerationMetrics;
+use evobrain::simulation::{run_simulation, run_simulation_with_sinks};
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 
@@ -20,53 +30,28 @@ fn smoke_run_and_csv() {
     let csv_path = temp_path("evobrain_results.csv");
     let run_path = temp_path("evobrain_run.json");
 
-    let config = Config {
-        generations: 3,
-        population: 10,
-        width: 10,
-        height: 10,
-        food: 10,
-        max_steps: 50,
-        max_energy: 20.0,
-        move_cost: 1.0,
-        food_energy: 5.0,
-        seed: 123,
-        input: 4,
-        hidden: 6,
-        output: 4,
-        selection_method: evobrain::config::SelectionMethod::Roulette,
-        tournament_k: 5,
-        elite: 0.2,
-        mut_rate: 0.1,
-        mut_strength: 0.3,
-        fitness_mode: evobrain::config::FitnessMode::Classic,
-        fitness_food_weight: 1.0,
-        fitness_efficiency_weight: 1.0,
-        fitness_survival_weight: 0.1,
-        fitness_idle_weight: 0.5,
-        fitness_jitter_weight: 0.2,
-        idle_tolerance: 10,
-        logging_mode: evobrain::config::LoggingMode::Full,
-        log_gens: "none".to_string(),
-        full_log_gens: None,
-        full_log_keep: "10".to_string(),
-        run_id: None,
-        quick_keep: 2,
-        food_vision_radius: 0,
-        distance_metric: evobrain::config::DistanceMetric::Euclidean,
-        brain_mode: evobrain::config::BrainMode::Fixed,
-        max_hidden_layers: 4,
-        layer_min_neurons: 4,
-        layer_max_neurons: 64,
-        crossover_mode: evobrain::config::CrossoverMode::None,
-        arch_inherit: evobrain::config::ArchInherit::Fitter,
-        out: csv_path.to_string_lossy().to_string(),
-        run_metadata: run_path.to_string_lossy().to_string(),
-        dump_frames: false,
-        frame_every: 10,
-        frames_dir: "frames".to_string(),
-        progress: 0,
-    };
+    let config = Config::try_parse_from([
+        "evobrain",
+        "--generations=3",
+        "--population=10",
+        "--width=10",
+        "--height=10",
+        "--food=10",
+        "--max-steps=50",
+        "--max-energy=20",
+        "--food-energy=5",
+        "--seed=123",
+        "--hidden=6",
+        "--elite=0.2",
+        "--mut-rate=0.1",
+        "--mut-strength=0.3",
+        "--log-gens=none",
+        "--frame-every=10",
+        "--progress=0",
+        &format!("--out={}", csv_path.display()),
+        &format!("--run-metadata={}", run_path.display()),
+    ])
+    .expect("parse config");
 
     let result = run_simulation(&config);
     assert_eq!(result.metrics.len(), 3);
@@ -91,53 +76,25 @@ fn smoke_run_and_csv() {
 
 #[test]
 fn determinism_same_seed() {
-    let config = Config {
-        generations: 3,
-        population: 12,
-        width: 8,
-        height: 8,
-        food: 6,
-        max_steps: 30,
-        max_energy: 15.0,
-        move_cost: 1.0,
-        food_energy: 4.0,
-        seed: 999,
-        input: 4,
-        hidden: 5,
-        output: 4,
-        selection_method: evobrain::config::SelectionMethod::Roulette,
-        tournament_k: 5,
-        elite: 0.2,
-        mut_rate: 0.1,
-        mut_strength: 0.2,
-        fitness_mode: evobrain::config::FitnessMode::Classic,
-        fitness_food_weight: 1.0,
-        fitness_efficiency_weight: 1.0,
-        fitness_survival_weight: 0.1,
-        fitness_idle_weight: 0.5,
-        fitness_jitter_weight: 0.2,
-        idle_tolerance: 10,
-        logging_mode: evobrain::config::LoggingMode::Full,
-        log_gens: "none".to_string(),
-        full_log_gens: None,
-        full_log_keep: "10".to_string(),
-        run_id: None,
-        quick_keep: 2,
-        food_vision_radius: 0,
-        distance_metric: evobrain::config::DistanceMetric::Euclidean,
-        brain_mode: evobrain::config::BrainMode::Fixed,
-        max_hidden_layers: 4,
-        layer_min_neurons: 4,
-        layer_max_neurons: 64,
-        crossover_mode: evobrain::config::CrossoverMode::None,
-        arch_inherit: evobrain::config::ArchInherit::Fitter,
-        out: "unused.csv".to_string(),
-        run_metadata: "unused.json".to_string(),
-        dump_frames: false,
-        frame_every: 10,
-        frames_dir: "frames".to_string(),
-        progress: 0,
-    };
+    let config = Config::try_parse_from([
+        "evobrain",
+        "--generations=3",
+        "--population=12",
+        "--width=8",
+        "--height=8",
+        "--food=6",
+        "--max-steps=30",
+        "--max-energy=15",
+        "--food-energy=4",
+        "--seed=999",
+        "--hidden=5",
+        "--elite=0.2",
+        "--mut-rate=0.1",
+        "--log-gens=none",
+        "--frame-every=10",
+        "--progress=0",
+    ])
+    .expect("parse config");
 
     let result_a = run_simulation(&config);
     let result_b = run_simulation(&config);
@@ -159,3 +116,153 @@ fn genome_size_and_mutation() {
         .zip(original.iter())
         .any(|(a, b)| a != b));
 }
+
+#[test]
+fn regularize_decays_and_clamps_weights() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut genome = Genome::random(vec![4, 2, 4], &mut rng);
+    genome.weights = vec![4.0, -0.5, f32::INFINITY];
+    genome.regularize(WeightBounds {
+        decay: 0.5,
+        clamp: Some(1.0),
+    });
+    assert_eq!(genome.weights, vec![1.0, -0.25, 1.0]);
+}
+
+struct CaptureSink(Rc<RefCell<Vec<GenerationReport>>>);
+
+impl MetricsSink for CaptureSink {
+    fn name(&self) -> &'static str {
+        "capture"
+    }
+
+    fn record(&mut self, report: &GenerationReport) -> Result<(), Box<dyn Error>> {
+        self.0.borrow_mut().push(report.clone());
+        Ok(())
+    }
+}
+
+#[test]
+fn metrics_outputs_agree() {
+    let dir = temp_path("evobrain_agree");
+    let csv_path = dir.join("results.csv");
+    let run_path = dir.join("run.json");
+    let run_dir = dir.join("run");
+    let config = Config::try_parse_from([
+        "evobrain",
+        "--generations=4",
+        "--population=12",
+        "--width=12",
+        "--height=12",
+        "--max-steps=60",
+        "--seed=7",
+        "--progress=0",
+        &format!("--out={}", csv_path.display()),
+        &format!("--run-metadata={}", run_path.display()),
+    ])
+    .expect("parse config");
+    fs::create_dir_all(&dir).expect("create temp dir");
+    let identity = RunIdentity::new(&config, "agree".to_string());
+    let writer = MetricsWriter::open(run_dir.clone(), &config, &identity).expect("open run dir");
+    let captured = Rc::new(RefCell::new(Vec::new()));
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let result = run_simulation_with_sinks(
+        &config,
+        &mut rng,
+        &identity,
+        vec![
+            Box::new(LegacyCsvSink::new(&config)),
+            Box::new(writer),
+            Box::new(CaptureSink(captured.clone())),
+        ],
+    );
+    assert!(result.sink_errors.is_empty(), "{:?}", result.sink_errors);
+    let reports = captured.borrow();
+    assert_eq!(reports.len(), 4);
+
+    let legacy: Vec<csv::StringRecord> = csv::Reader::from_path(&csv_path)
+        .expect("open results csv")
+        .records()
+        .map(|record| record.expect("results row"))
+        .collect();
+    let mut generations =
+        csv::Reader::from_path(run_dir.join("generations.csv")).expect("open generations csv");
+    let headers = generations.headers().expect("headers").clone();
+    let column = |name: &str| headers.iter().position(|h| h == name).expect(name);
+    let rows: Vec<csv::StringRecord> = generations
+        .records()
+        .map(|record| record.expect("generations row"))
+        .collect();
+    assert_eq!(legacy.len(), reports.len());
+    assert_eq!(rows.len(), reports.len());
+
+    for ((report, old), new) in reports.iter().zip(&legacy).zip(&rows) {
+        assert_eq!(
+            GenerationMetrics::from(report),
+            result.metrics[report.generation as usize]
+        );
+        // Columns: generation, avg_fitness, max_fitness, avg_age, survivors,
+        // avg_energy, food_eaten_total.
+        assert_eq!(old[0], new[column("generation")]);
+        assert_eq!(old[1], new[column("fitness_mean")]);
+        assert_eq!(old[2], new[column("fitness_best")]);
+        assert_eq!(old[3], new[column("age_mean")]);
+        assert_eq!(old[4], new[column("survivors")]);
+        assert_eq!(old[5], new[column("energy_mean")]);
+        assert_eq!(old[6], new[column("food_eaten_total")]);
+        assert_eq!(old[6], report.food_eaten_total.to_string());
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+fn http_get(addr: SocketAddr, path: &str) -> (String, String) {
+    let mut stream = TcpStream::connect(addr).expect("connect");
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").expect("send request");
+    let mut response = String::new();
+    stream.read_to_string(&mut response).expect("read response");
+    let (head, body) = response.split_once("\r\n\r\n").expect("http response");
+    (
+        head.lines().next().unwrap_or("").to_string(),
+        body.to_string(),
+    )
+}
+
+#[test]
+fn serve_exposes_reports_and_stream() {
+    let config = Config::try_parse_from([
+        "evobrain",
+        "--generations=3",
+        "--population=10",
+        "--max-steps=40",
+        "--seed=11",
+        "--progress=0",
+    ])
+    .expect("parse config");
+    let identity = RunIdentity::new(&config, "serve_test".to_string());
+    let server = ServeSink::bind("127.0.0.1:0", &config, &identity).expect("bind");
+    let addr = server.local_addr();
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let result = run_simulation_with_sinks(&config, &mut rng, &identity, vec![Box::new(server)]);
+
+    let (status, body) = http_get(addr, "/api/history");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    let history: Vec<serde_json::Value> = serde_json::from_str(&body).expect("history json");
+    assert_eq!(history.len(), 3);
+    assert_eq!(
+        history[2]["fitness_best"].as_f64().unwrap() as f32,
+        result.metrics[2].max_fitness
+    );
+    let (_, body) = http_get(addr, "/api/manifest");
+    let manifest: serde_json::Value = serde_json::from_str(&body).expect("manifest json");
+    assert_eq!(manifest["run_id"], "serve_test");
+    let (_, body) = http_get(addr, "/api/report");
+    let report: serde_json::Value = serde_json::from_str(&body).expect("report json");
+    assert_eq!(report["generation"], 2);
+    let (_, body) = http_get(addr, "/api/events");
+    assert!(body.contains("\"finish\""));
+    let (_, body) = http_get(addr, "/api/stream");
+    assert!(body.starts_with("event: finish"));
+    assert!(http_get(addr, "/").1.contains("<canvas"));
+    assert_eq!(http_get(addr, "/missing").0, "HTTP/1.1 404 Not Found");
+}